@@ -1,6 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::time::Duration;
+
+use crate::error::KusanagiError;
+use crate::http_util;
 
 /// Single alert from Alertmanager
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,32 +49,23 @@ struct AmAlertStatus {
     state: String,
 }
 
-fn get_alertmanager_url() -> String {
+/// Alertmanager request timeout (override with ALERTMANAGER_TIMEOUT_SECS)
+fn alertmanager_timeout() -> Duration {
+    http_util::timeout_from_env("ALERTMANAGER_TIMEOUT_SECS", 10)
+}
+
+/// Client reused across Alertmanager calls
+static CLIENT: http_util::SharedClient = http_util::SharedClient::new(alertmanager_timeout);
+
+pub fn get_alertmanager_url() -> String {
     std::env::var("ALERTMANAGER_URL")
         .unwrap_or_else(|_| "http://kube-prometheus-stack-alertmanager.kube-prometheus-stack.svc:9093".to_string())
 }
 
 /// Get all active alerts from Alertmanager
-pub async fn get_active_alerts() -> Result<AlertsResponse, String> {
-    let client = reqwest::Client::new();
-    let url = format!("{}/api/v2/alerts", get_alertmanager_url());
-    
-    let response = client
-        .get(&url)
-        .query(&[("active", "true"), ("silenced", "false"), ("inhibited", "false")])
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
-        .await
-        .map_err(|e| format!("Alertmanager request failed: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Alertmanager returned status: {}", response.status()));
-    }
-    
-    let am_alerts: Vec<AmAlert> = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Alertmanager response: {}", e))?;
+/// With a namespace, only alerts carrying that `namespace` label are returned
+pub async fn get_active_alerts(namespace: Option<&str>) -> Result<AlertsResponse, String> {
+    let am_alerts = fetch_am_alerts().await?;
     
     let mut critical = Vec::new();
     let mut warning = Vec::new();
@@ -78,7 +73,7 @@ pub async fn get_active_alerts() -> Result<AlertsResponse, String> {
     let mut firing = 0;
     let mut pending = 0;
     
-    for am_alert in am_alerts {
+    for am_alert in am_alerts.into_iter().filter(|a| in_namespace(a, namespace)) {
         let severity = am_alert.labels.get("severity")
             .cloned()
             .unwrap_or_else(|| "info".to_string());
@@ -130,6 +125,49 @@ pub async fn get_active_alerts() -> Result<AlertsResponse, String> {
     })
 }
 
+/// Alerts in display order: critical, then warning, then info, most recent first within each
+/// Sorts each group itself rather than relying on the response already being sorted
+pub fn order_alerts(resp: &AlertsResponse) -> Vec<&Alert> {
+    [&resp.critical, &resp.warning, &resp.info]
+        .into_iter()
+        .flat_map(|group| {
+            let mut group: Vec<&Alert> = group.iter().collect();
+            group.sort_by_key(|a| std::cmp::Reverse(a.started_at));
+            group
+        })
+        .collect()
+}
+
+/// Fetch active, unsilenced, uninhibited alerts as returned by Alertmanager
+async fn fetch_am_alerts() -> Result<Vec<AmAlert>, String> {
+    let client = CLIENT.get()?;
+    let url = format!("{}/api/v2/alerts", get_alertmanager_url());
+    
+    let response = client
+        .get(&url)
+        .query(&[("active", "true"), ("silenced", "false"), ("inhibited", "false")])
+        .send()
+        .await
+        .map_err(|e| format!("Alertmanager request failed: {}", http_util::describe_error(&e)))?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Alertmanager returned status: {}", response.status()));
+    }
+    
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Alertmanager response: {}", e))
+}
+
+/// Whether an alert matches the namespace filter (alerts without a namespace never match one)
+fn in_namespace(alert: &AmAlert, namespace: Option<&str>) -> bool {
+    match namespace {
+        Some(ns) => alert.labels.get("namespace").map(|n| n == ns).unwrap_or(false),
+        None => true,
+    }
+}
+
 /// Silence management structures
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Silence {
@@ -157,15 +195,14 @@ pub struct SilenceStatus {
 
 /// Get all active silences
 pub async fn get_silences() -> Result<Vec<Silence>, String> {
-    let client = reqwest::Client::new();
+    let client = CLIENT.get()?;
     let url = format!("{}/api/v2/silences", get_alertmanager_url());
     
     let response = client
         .get(&url)
-        .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
-        .map_err(|e| format!("Alertmanager silences request failed: {}", e))?;
+        .map_err(|e| format!("Alertmanager silences request failed: {}", http_util::describe_error(&e)))?;
     
     if !response.status().is_success() {
         return Err(format!("Alertmanager returned status: {}", response.status()));
@@ -182,12 +219,218 @@ pub async fn get_silences() -> Result<Vec<Silence>, String> {
         .collect())
 }
 
+/// Create a silence for the given matchers, starting now
+/// Returns the ID of the new silence
+pub async fn create_silence(
+    matchers: &[SilenceMatcher],
+    duration: chrono::Duration,
+    created_by: &str,
+    comment: &str,
+) -> Result<String, String> {
+    if matchers.is_empty() {
+        return Err("A silence needs at least one matcher".to_string());
+    }
+
+    let client = CLIENT.get()?;
+    let url = format!("{}/api/v2/silences", get_alertmanager_url());
+
+    let now = Utc::now();
+    let body = serde_json::json!({
+        "matchers": matchers.iter().map(|m| serde_json::json!({
+            "name": m.name,
+            "value": m.value,
+            "isRegex": m.is_regex,
+            "isEqual": m.is_equal,
+        })).collect::<Vec<_>>(),
+        "startsAt": now.to_rfc3339(),
+        "endsAt": (now + duration).to_rfc3339(),
+        "createdBy": created_by,
+        "comment": comment,
+    });
+
+    let response = client
+        .post(&url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Alertmanager silence request failed: {}", http_util::describe_error(&e)))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Alertmanager returned status: {}", response.status()));
+    }
+
+    let created: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse silence response: {}", e))?;
+
+    created["silenceID"]
+        .as_str()
+        .map(|id| id.to_string())
+        .ok_or_else(|| "Alertmanager did not return a silence ID".to_string())
+}
+
+/// Longest silence Kusanagi creates, from the dashboard or the assistant
+pub const MAX_SILENCE_HOURS: i64 = 24;
+
+/// Request to acknowledge an alert
+#[derive(Debug, Deserialize)]
+pub struct AckRequest {
+    pub fingerprint: String,
+    #[serde(default = "default_ack_hours")]
+    pub hours: i64,
+    #[serde(default = "default_ack_author")]
+    pub who: String,
+}
+
+fn default_ack_hours() -> i64 {
+    1
+}
+
+fn default_ack_author() -> String {
+    "kusanagi".to_string()
+}
+
+/// Check an acknowledgment duration: at least one hour, at most MAX_SILENCE_HOURS
+pub fn validate_ack_hours(hours: i64) -> Result<(), KusanagiError> {
+    if !(1..=MAX_SILENCE_HOURS).contains(&hours) {
+        return Err(KusanagiError::Parse(format!(
+            "Acknowledgment duration must be between 1 and {} hours",
+            MAX_SILENCE_HOURS
+        )));
+    }
+    Ok(())
+}
+
+/// Acknowledge an alert by silencing exactly its label set for a few hours
+/// Returns the ID of the created silence
+pub async fn ack_alert(fingerprint: &str, hours: i64, who: &str) -> Result<String, KusanagiError> {
+    validate_ack_hours(hours)?;
+
+    let alert = fetch_am_alerts()
+        .await
+        .map_err(KusanagiError::Upstream)?
+        .into_iter()
+        .find(|a| a.fingerprint == fingerprint)
+        .ok_or_else(|| KusanagiError::NotFound(format!("No active alert with fingerprint {}", fingerprint)))?;
+
+    let matchers = matchers_from_labels(&alert.labels);
+    create_silence(
+        &matchers,
+        chrono::Duration::hours(hours),
+        who,
+        &format!("Acknowledged from Kusanagi for {}h", hours),
+    )
+    .await
+    .map_err(KusanagiError::Upstream)
+}
+
+/// Exact-match matchers for every label of an alert, sorted by label name
+fn matchers_from_labels(labels: &std::collections::HashMap<String, String>) -> Vec<SilenceMatcher> {
+    let mut matchers: Vec<SilenceMatcher> = labels
+        .iter()
+        .map(|(name, value)| SilenceMatcher {
+            name: name.clone(),
+            value: value.clone(),
+            is_regex: false,
+            is_equal: true,
+        })
+        .collect();
+    matchers.sort_by(|a, b| a.name.cmp(&b.name));
+    matchers
+}
+
 /// Get alert counts summary
 pub async fn get_alert_counts() -> Result<(i32, i32, i32), String> {
-    let alerts = get_active_alerts().await?;
+    let alerts = get_active_alerts(None).await?;
     Ok((
         alerts.critical.len() as i32,
         alerts.warning.len() as i32,
         alerts.info.len() as i32,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+
+    #[test]
+    fn ack_hours_are_bounded() {
+        assert!(validate_ack_hours(1).is_ok());
+        assert!(validate_ack_hours(MAX_SILENCE_HOURS).is_ok());
+        for hours in [0, -3, MAX_SILENCE_HOURS + 1] {
+            let err = validate_ack_hours(hours).unwrap_err();
+            assert_eq!(err.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[test]
+    fn matchers_cover_every_label_exactly() {
+        let labels = [("severity", "critical"), ("alertname", "KubeNodeNotReady")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let matchers = matchers_from_labels(&labels);
+        let names: Vec<&str> = matchers.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["alertname", "severity"]);
+        assert!(matchers.iter().all(|m| m.is_equal && !m.is_regex));
+    }
+
+    #[test]
+    fn namespace_filter_excludes_other_and_unlabelled_alerts() {
+        let alert = |labels: serde_json::Value| -> AmAlert {
+            serde_json::from_value(serde_json::json!({
+                "labels": labels,
+                "annotations": {},
+                "startsAt": "2024-03-01T12:00:00Z",
+                "endsAt": "2024-03-01T13:00:00Z",
+                "fingerprint": "abc",
+                "status": { "state": "active" }
+            }))
+            .unwrap()
+        };
+        let in_prod = alert(serde_json::json!({"alertname": "PodCrashLooping", "namespace": "prod"}));
+        let in_dev = alert(serde_json::json!({"alertname": "PodCrashLooping", "namespace": "dev"}));
+        let cluster_wide = alert(serde_json::json!({"alertname": "KubeNodeNotReady"}));
+
+        assert!(in_namespace(&in_prod, Some("prod")));
+        assert!(!in_namespace(&in_dev, Some("prod")));
+        assert!(!in_namespace(&cluster_wide, Some("prod")));
+        for alert in [&in_prod, &in_dev, &cluster_wide] {
+            assert!(in_namespace(alert, None));
+        }
+    }
+
+    #[test]
+    fn alerts_order_by_severity_then_recency() {
+        let alert = |name: &str, severity: &str, started_at: &str| Alert {
+            name: name.to_string(),
+            severity: severity.to_string(),
+            state: "active".to_string(),
+            summary: String::new(),
+            description: None,
+            namespace: None,
+            pod: None,
+            started_at: started_at.parse().unwrap(),
+            fingerprint: name.to_string(),
+        };
+        let resp = AlertsResponse {
+            critical: vec![
+                alert("NodeDown", "critical", "2024-03-01T08:00:00Z"),
+                alert("EtcdNoLeader", "critical", "2024-03-01T10:00:00Z"),
+            ],
+            warning: vec![
+                alert("DiskFilling", "warning", "2024-03-01T07:00:00Z"),
+                alert("PodRestarts", "warning", "2024-03-01T11:00:00Z"),
+            ],
+            info: vec![alert("Watchdog", "info", "2024-03-01T12:00:00Z")],
+            total: 5,
+            firing: 5,
+            pending: 0,
+        };
+
+        let order: Vec<&str> = order_alerts(&resp).iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(order, vec!["EtcdNoLeader", "NodeDown", "PodRestarts", "DiskFilling", "Watchdog"]);
+    }
+}
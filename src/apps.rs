@@ -1,12 +1,12 @@
-use kube::{
-    api::{Api, ListParams},
-    Client,
-};
+use kube::api::{Api, ListParams};
 use k8s_openapi::api::core::v1::{Namespace, PersistentVolumeClaim, Pod, ResourceRequirements};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use tracing::info;
 
+use crate::{cluster, clusters, k8s_cache};
+
 /// Application with resource usage
 #[derive(Clone, Debug, Serialize)]
 pub struct AppInfo {
@@ -17,6 +17,7 @@ pub struct AppInfo {
     pub argocd_url: String,
     // Resource usage
     pub pod_count: usize,
+    pub cpu_request: String,
     pub ram_request: String,
     pub ram_limit: String,
     pub pvc_count: usize,
@@ -27,9 +28,66 @@ pub struct AppInfo {
 #[derive(Clone, Debug, Serialize)]
 pub struct AppsResponse {
     pub total_apps: usize,
+    pub has_more: bool,
     pub apps: Vec<AppInfo>,
 }
 
+/// Sort keys for the apps endpoint (an unknown key is rejected with 400)
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AppSort {
+    #[default]
+    Ram,
+    Cpu,
+    Pvc,
+    Name,
+}
+
+/// Query options for the apps endpoint
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct AppsQuery {
+    /// Cluster from KUSANAGI_CLUSTERS (current context when omitted)
+    pub cluster: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub sort: Option<AppSort>,
+    pub namespace: Option<String>,
+    pub health: Option<String>,
+}
+
+/// Keep only apps matching the namespace and health filters
+fn filter_apps(apps: &mut Vec<AppInfo>, namespace: Option<&str>, health: Option<&str>) {
+    if let Some(ns) = namespace {
+        apps.retain(|a| a.namespace == ns);
+    }
+    if let Some(health) = health {
+        apps.retain(|a| a.health_status.eq_ignore_ascii_case(health));
+    }
+}
+
+/// Sort apps by the requested key (resources descending, name ascending)
+fn sort_apps(apps: &mut [AppInfo], sort: AppSort) {
+    match sort {
+        AppSort::Ram => apps.sort_by_key(|a| Reverse(cluster::parse_capacity_to_bytes(&a.ram_limit))),
+        AppSort::Cpu => apps.sort_by_key(|a| Reverse(parse_cpu(&a.cpu_request))),
+        AppSort::Pvc => apps.sort_by_key(|a| Reverse(cluster::parse_capacity_to_bytes(&a.pvc_size))),
+        AppSort::Name => apps.sort_by(|a, b| a.name.cmp(&b.name)),
+    }
+}
+
+/// Slice a sorted app list into a page, returning the page and whether more apps follow
+fn paginate(apps: Vec<AppInfo>, offset: Option<usize>, limit: Option<usize>) -> (Vec<AppInfo>, bool) {
+    let total = apps.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let end = match limit {
+        Some(limit) => offset.saturating_add(limit).min(total),
+        None => total,
+    };
+    let has_more = end < total;
+    let page = apps.into_iter().skip(offset).take(end - offset).collect();
+    (page, has_more)
+}
+
 /// Format bytes to human-readable
 fn format_bytes(bytes: i64) -> String {
     if bytes >= 1024 * 1024 * 1024 * 1024 {
@@ -45,44 +103,30 @@ fn format_bytes(bytes: i64) -> String {
     }
 }
 
-/// Parse memory string to bytes
-fn parse_memory(mem: &str) -> i64 {
-    let mem = mem.trim();
-    if mem.is_empty() {
-        return 0;
-    }
-    
-    let (num_str, unit) = if mem.ends_with("Ki") {
-        (&mem[..mem.len()-2], 1024_i64)
-    } else if mem.ends_with("Mi") {
-        (&mem[..mem.len()-2], 1024_i64 * 1024)
-    } else if mem.ends_with("Gi") {
-        (&mem[..mem.len()-2], 1024_i64 * 1024 * 1024)
-    } else if mem.ends_with("Ti") {
-        (&mem[..mem.len()-2], 1024_i64 * 1024 * 1024 * 1024)
-    } else if mem.ends_with('K') || mem.ends_with('k') {
-        (&mem[..mem.len()-1], 1000_i64)
-    } else if mem.ends_with('M') || mem.ends_with('m') {
-        (&mem[..mem.len()-1], 1000_i64 * 1000)
-    } else if mem.ends_with('G') || mem.ends_with('g') {
-        (&mem[..mem.len()-1], 1000_i64 * 1000 * 1000)
+/// Parse CPU quantity (e.g. "250m", "2") to millicores
+pub fn parse_cpu(cpu: &str) -> i64 {
+    let cpu = cpu.trim();
+    if let Some(millis) = cpu.strip_suffix('m') {
+        millis.parse::<f64>().unwrap_or(0.0) as i64
     } else {
-        (mem, 1_i64)
-    };
-    
-    num_str.parse::<f64>().unwrap_or(0.0) as i64 * unit
+        (cpu.parse::<f64>().unwrap_or(0.0) * 1000.0) as i64
+    }
 }
 
-/// Parse capacity string to bytes
-fn parse_capacity(cap: &str) -> i64 {
-    parse_memory(cap)
+/// Format millicores to a CPU quantity
+fn format_cpu(millis: i64) -> String {
+    if millis >= 1000 {
+        format!("{:.1}", millis as f64 / 1000.0)
+    } else {
+        format!("{}m", millis)
+    }
 }
 
 /// Get all ArgoCD applications with resource usage
-pub async fn get_apps_with_resources() -> Result<AppsResponse, String> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| format!("Failed to create K8s client: {}", e))?;
+/// Without `limit`/`offset` the full list is returned
+pub async fn get_apps_with_resources(query: &AppsQuery) -> Result<AppsResponse, String> {
+    let cluster = query.cluster.as_deref();
+    let client = clusters::client(cluster).await.map_err(|e| e.to_string())?;
 
     info!("Fetching ArgoCD applications with resource usage");
 
@@ -103,30 +147,26 @@ pub async fn get_apps_with_resources() -> Result<AppsResponse, String> {
         .await
         .map_err(|e| format!("Failed to list applications: {}", e))?;
 
-    // Get all pods grouped by namespace
-    let pods_api: Api<Pod> = Api::all(client.clone());
-    let pods = pods_api
-        .list(&ListParams::default())
+    // Get pods grouped by namespace (only the requested namespace when filtering)
+    let pods = k8s_cache::list_pods(client.clone(), cluster, query.namespace.as_deref())
         .await
         .map_err(|e| format!("Failed to list pods: {}", e))?;
 
     // Build namespace -> pods map with memory
     let mut ns_pods: HashMap<String, Vec<&Pod>> = HashMap::new();
-    for pod in &pods.items {
+    for pod in pods.iter() {
         let ns = pod.metadata.namespace.as_deref().unwrap_or("default");
         ns_pods.entry(ns.to_string()).or_default().push(pod);
     }
 
-    // Get all PVCs grouped by namespace
-    let pvcs_api: Api<PersistentVolumeClaim> = Api::all(client.clone());
-    let pvcs = pvcs_api
-        .list(&ListParams::default())
+    // Get PVCs grouped by namespace
+    let pvcs = k8s_cache::list_pvcs(client.clone(), cluster, query.namespace.as_deref())
         .await
         .map_err(|e| format!("Failed to list PVCs: {}", e))?;
 
     // Build namespace -> PVCs map
     let mut ns_pvcs: HashMap<String, Vec<&PersistentVolumeClaim>> = HashMap::new();
-    for pvc in &pvcs.items {
+    for pvc in pvcs.iter() {
         let ns = pvc.metadata.namespace.as_deref().unwrap_or("default");
         ns_pvcs.entry(ns.to_string()).or_default().push(pvc);
     }
@@ -166,6 +206,7 @@ pub async fn get_apps_with_resources() -> Result<AppsResponse, String> {
         let namespace_pods = ns_pods.get(&dest_ns).map(|v| v.as_slice()).unwrap_or(&[]);
         let pod_count = namespace_pods.len();
         
+        let mut total_cpu_request: i64 = 0;
         let mut total_ram_request: i64 = 0;
         let mut total_ram_limit: i64 = 0;
 
@@ -174,13 +215,16 @@ pub async fn get_apps_with_resources() -> Result<AppsResponse, String> {
                 for container in &spec.containers {
                     if let Some(resources) = &container.resources {
                         if let Some(requests) = &resources.requests {
+                            if let Some(cpu) = requests.get("cpu") {
+                                total_cpu_request += parse_cpu(&cpu.0);
+                            }
                             if let Some(mem) = requests.get("memory") {
-                                total_ram_request += parse_memory(&mem.0);
+                                total_ram_request += cluster::parse_capacity_to_bytes(&mem.0);
                             }
                         }
                         if let Some(limits) = &resources.limits {
                             if let Some(mem) = limits.get("memory") {
-                                total_ram_limit += parse_memory(&mem.0);
+                                total_ram_limit += cluster::parse_capacity_to_bytes(&mem.0);
                             }
                         }
                     }
@@ -198,7 +242,7 @@ pub async fn get_apps_with_resources() -> Result<AppsResponse, String> {
                 if let Some(resources) = &spec.resources {
                     if let Some(requests) = &resources.requests {
                         if let Some(storage) = requests.get("storage") {
-                            total_pvc_size += parse_capacity(&storage.0);
+                            total_pvc_size += cluster::parse_capacity_to_bytes(&storage.0);
                         }
                     }
                 }
@@ -212,6 +256,7 @@ pub async fn get_apps_with_resources() -> Result<AppsResponse, String> {
             sync_status,
             argocd_url: format!("{}/applications/{}", argocd_base_url, name),
             pod_count,
+            cpu_request: format_cpu(total_cpu_request),
             ram_request: format_bytes(total_ram_request),
             ram_limit: format_bytes(total_ram_limit),
             pvc_count,
@@ -219,15 +264,110 @@ pub async fn get_apps_with_resources() -> Result<AppsResponse, String> {
         });
     }
 
-    // Sort by RAM limit descending
-    app_infos.sort_by(|a, b| {
-        let a_ram = parse_memory(&a.ram_limit);
-        let b_ram = parse_memory(&b.ram_limit);
-        b_ram.cmp(&a_ram)
-    });
+    // Filter first, then sort (RAM limit descending by default)
+    filter_apps(&mut app_infos, query.namespace.as_deref(), query.health.as_deref());
+    sort_apps(&mut app_infos, query.sort.unwrap_or_default());
+
+    let total_apps = app_infos.len();
+    let (apps, has_more) = paginate(app_infos, query.offset, query.limit);
 
     Ok(AppsResponse {
-        total_apps: app_infos.len(),
-        apps: app_infos,
+        total_apps,
+        has_more,
+        apps,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str) -> AppInfo {
+        AppInfo {
+            name: name.to_string(),
+            namespace: "default".to_string(),
+            health_status: "Healthy".to_string(),
+            sync_status: "Synced".to_string(),
+            argocd_url: String::new(),
+            pod_count: 1,
+            cpu_request: "0m".to_string(),
+            ram_request: "0Mi".to_string(),
+            ram_limit: "0Mi".to_string(),
+            pvc_count: 0,
+            pvc_size: "0".to_string(),
+        }
+    }
+
+    fn names(apps: &[AppInfo]) -> Vec<&str> {
+        apps.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    #[test]
+    fn paginate_slices_at_the_boundaries() {
+        let apps: Vec<AppInfo> = ["a", "b", "c", "d", "e"].into_iter().map(app).collect();
+
+        let (page, has_more) = paginate(apps.clone(), None, None);
+        assert_eq!((page.len(), has_more), (5, false));
+
+        let (page, has_more) = paginate(apps.clone(), Some(0), Some(2));
+        assert_eq!((names(&page), has_more), (vec!["a", "b"], true));
+
+        let (page, has_more) = paginate(apps.clone(), Some(3), Some(2));
+        assert_eq!((names(&page), has_more), (vec!["d", "e"], false));
+
+        let (page, has_more) = paginate(apps.clone(), Some(4), Some(10));
+        assert_eq!((names(&page), has_more), (vec!["e"], false));
+
+        let (page, has_more) = paginate(apps.clone(), Some(5), Some(2));
+        assert_eq!((page.len(), has_more), (0, false));
+
+        let (page, has_more) = paginate(apps, Some(usize::MAX), Some(usize::MAX));
+        assert_eq!((page.len(), has_more), (0, false));
+    }
+
+    fn sized(name: &str, namespace: &str, cpu: &str, ram: &str, pvc: &str) -> AppInfo {
+        AppInfo {
+            namespace: namespace.to_string(),
+            cpu_request: cpu.to_string(),
+            ram_limit: ram.to_string(),
+            pvc_size: pvc.to_string(),
+            ..app(name)
+        }
+    }
+
+    fn sample() -> Vec<AppInfo> {
+        vec![
+            sized("web", "prod", "500m", "1Gi", "10Gi"),
+            sized("api", "prod", "2", "512Mi", "0"),
+            sized("db", "data", "1", "4Gi", "100Gi"),
+        ]
+    }
+
+    #[test]
+    fn sort_keys_order_apps() {
+        let cases = [
+            (AppSort::Ram, vec!["db", "web", "api"]),
+            (AppSort::Cpu, vec!["api", "db", "web"]),
+            (AppSort::Pvc, vec!["db", "web", "api"]),
+            (AppSort::Name, vec!["api", "db", "web"]),
+        ];
+        for (sort, expected) in cases {
+            let mut apps = sample();
+            sort_apps(&mut apps, sort);
+            assert_eq!(names(&apps), expected, "{:?}", sort);
+        }
+    }
+
+    #[test]
+    fn namespace_and_health_filters_apply() {
+        let mut apps = sample();
+        apps[1].health_status = "Degraded".to_string();
+
+        let mut prod = apps.clone();
+        filter_apps(&mut prod, Some("prod"), None);
+        assert_eq!(names(&prod), vec!["web", "api"]);
+
+        filter_apps(&mut apps, Some("prod"), Some("degraded"));
+        assert_eq!(names(&apps), vec!["api"]);
+    }
+}
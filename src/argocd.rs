@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use kube::{
     api::{Api, ListParams, Patch, PatchParams},
     Client,
@@ -7,6 +8,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::info;
 
+use crate::clusters;
+use crate::error::KusanagiError;
+
 /// ArgoCD Application structure (simplified)
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -138,6 +142,8 @@ pub struct ArgoStatusResponse {
     pub unknown: usize,
     pub progressing: usize,
     pub upgrades_available: usize,
+    /// Apps (healthy ones included) not reconciled within ARGOCD_STALE_THRESHOLD_SECS
+    pub stale_apps: usize,
     pub apps_with_issues: Vec<AppIssue>,
     pub apps_with_upgrades: Vec<AppIssue>,
 }
@@ -149,6 +155,8 @@ pub struct AppIssue {
     pub health_status: String,
     pub sync_status: String,
     pub message: Option<String>,
+    /// Discrete factors behind the issue, e.g. "Health: Degraded", "Sync: OutOfSync"
+    pub reasons: Vec<String>,
     pub error_since: Option<String>,
     pub error_duration: Option<String>,
     pub category: IssueCategory,
@@ -157,12 +165,16 @@ pub struct AppIssue {
     pub is_helm_chart: bool,
     pub can_sync: bool,
     pub argocd_url: String,
+    /// `reconciledAt` is older than the stale threshold: the controller may be stuck
+    pub stale: bool,
 }
 
 /// Sync request
 #[derive(Clone, Debug, Deserialize)]
 pub struct SyncRequest {
     pub app_name: String,
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 /// Sync response
@@ -172,29 +184,43 @@ pub struct SyncResponse {
     pub message: String,
 }
 
-/// Get ArgoCD applications status
-pub async fn get_argocd_status() -> Result<ArgoStatusResponse, String> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+/// Request to sync every out-of-sync application
+#[derive(Clone, Debug, Deserialize)]
+pub struct SyncAllRequest {
+    /// Only report what would be synced (default)
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
 
-    // Use dynamic API to get ArgoCD Applications
-    let apps_api: Api<kube::core::DynamicObject> = Api::namespaced_with(
-        client,
-        "argocd",
-        &kube::discovery::ApiResource {
-            group: "argoproj.io".to_string(),
-            version: "v1alpha1".to_string(),
-            api_version: "argoproj.io/v1alpha1".to_string(),
-            kind: "Application".to_string(),
-            plural: "applications".to_string(),
-        },
-    );
+impl Default for SyncAllRequest {
+    fn default() -> Self {
+        Self { dry_run: true }
+    }
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+/// Outcome of one application in a bulk sync
+#[derive(Clone, Debug, Serialize)]
+pub struct AppSyncResult {
+    pub app: String,
+    #[serde(flatten)]
+    pub result: SyncResponse,
+}
 
-    let app_list = apps_api
+/// Syncs issued at once by a bulk sync, so it doesn't flood the API server
+const SYNC_ALL_CONCURRENCY: usize = 3;
+
+/// Get ArgoCD applications status
+pub async fn get_argocd_status(cluster: Option<&str>) -> Result<ArgoStatusResponse, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let app_list = applications_api(client)
         .list(&ListParams::default())
         .await
-        .map_err(|e| format!("Failed to list ArgoCD applications: {}", e))?;
+        .map_err(|e| KusanagiError::kube("Failed to list ArgoCD applications", e))?;
 
     let mut response = ArgoStatusResponse {
         total: app_list.items.len(),
@@ -205,6 +231,7 @@ pub async fn get_argocd_status() -> Result<ArgoStatusResponse, String> {
         unknown: 0,
         progressing: 0,
         upgrades_available: 0,
+        stale_apps: 0,
         apps_with_issues: Vec::new(),
         apps_with_upgrades: Vec::new(),
     };
@@ -212,54 +239,11 @@ pub async fn get_argocd_status() -> Result<ArgoStatusResponse, String> {
     let now = Utc::now();
 
     for app in app_list.items {
-        let name = app.metadata.name.clone().unwrap_or_default();
-
-        // Extract status from dynamic object data
-        let status: ApplicationStatus = app
-            .data
-            .get("status")
-            .and_then(|s| serde_json::from_value(s.clone()).ok())
-            .unwrap_or_default();
-
-        let spec: ApplicationSpec = app
-            .data
-            .get("spec")
-            .and_then(|s| serde_json::from_value(s.clone()).ok())
-            .unwrap_or(ApplicationSpec {
-                project: None,
-                source: None,
-                destination: None,
-            });
-
-        let dest_namespace = spec
-            .destination
-            .as_ref()
-            .and_then(|d| d.namespace.clone())
-            .unwrap_or_default();
-
-        let health_status = status
-            .health
-            .as_ref()
-            .and_then(|h| h.status.clone())
-            .unwrap_or_else(|| "Unknown".to_string());
-
-        let sync_status = status
-            .sync
-            .as_ref()
-            .and_then(|s| s.status.clone())
-            .unwrap_or_else(|| "Unknown".to_string());
-
-        // Check if this is a Helm chart
-        let is_helm_chart = spec.source.as_ref().and_then(|s| s.chart.clone()).is_some();
-        
-        // Get target revision (could be a version like "1.2.3" or "*" or "HEAD")
-        let target_revision = spec.source.as_ref().and_then(|s| s.target_revision.clone());
-        
-        // Get current synced revision
-        let current_revision = status.sync.as_ref().and_then(|s| s.revision.clone());
+        let (name, spec, status) = parse_application(&app);
+        let app_issue = build_app_issue(&name, &spec, &status, &now);
 
         // Count health statuses
-        match health_status.as_str() {
+        match app_issue.health_status.as_str() {
             "Healthy" => response.healthy += 1,
             "Progressing" => response.progressing += 1,
             "Unknown" => response.unknown += 1,
@@ -267,64 +251,18 @@ pub async fn get_argocd_status() -> Result<ArgoStatusResponse, String> {
         }
 
         // Count sync statuses
-        match sync_status.as_str() {
+        match app_issue.sync_status.as_str() {
             "Synced" => response.synced += 1,
             "OutOfSync" => response.out_of_sync += 1,
             _ => {}
         }
 
-        // Check if app has issues
-        let has_issue = health_status != "Healthy"
-            || sync_status == "OutOfSync"
-            || sync_status == "Unknown";
+        if app_issue.stale {
+            response.stale_apps += 1;
+        }
 
-        if has_issue {
-            let message = status
-                .health
-                .as_ref()
-                .and_then(|h| h.message.clone())
-                .or_else(|| {
-                    status
-                        .operation_state
-                        .as_ref()
-                        .and_then(|o| o.message.clone())
-                });
-
-            // Determine the category of the issue
-            let category = categorize_issue(
-                &health_status,
-                &sync_status,
-                &message,
-                is_helm_chart,
-                &target_revision,
-            );
-
-            // Try to determine when the error started
-            let (error_since, error_duration) = calculate_error_duration(&status, &now);
-
-            // Build ArgoCD URL
-            let argocd_url = format!(
-                "https://argocd.p.zacharie.org/applications/argocd/{}",
-                name
-            );
-
-            let app_issue = AppIssue {
-                name: name.clone(),
-                namespace: dest_namespace,
-                health_status: health_status.clone(),
-                sync_status: sync_status.clone(),
-                message,
-                error_since,
-                error_duration,
-                category: category.clone(),
-                target_revision,
-                current_revision,
-                is_helm_chart,
-                can_sync: health_status == "Healthy" || health_status == "Progressing",
-                argocd_url,
-            };
-
-            match category {
+        if has_issue(&app_issue) {
+            match app_issue.category {
                 IssueCategory::UpgradeAvailable => {
                     response.upgrades_available += 1;
                     response.apps_with_upgrades.push(app_issue);
@@ -347,6 +285,478 @@ pub async fn get_argocd_status() -> Result<ArgoStatusResponse, String> {
     Ok(response)
 }
 
+/// Applications of one ArgoCD project
+#[derive(Clone, Debug, Serialize)]
+pub struct ProjectApps {
+    pub project: String,
+    pub total: usize,
+    pub healthy: usize,
+    pub apps: Vec<AppIssue>,
+}
+
+/// Get all applications (healthy ones included) grouped by `spec.project`
+pub async fn get_apps_by_project(cluster: Option<&str>) -> Result<Vec<ProjectApps>, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let app_list = applications_api(client)
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list ArgoCD applications", e))?;
+
+    let now = Utc::now();
+    let apps = app_list.items.iter().map(|app| {
+        let (name, spec, status) = parse_application(app);
+        let issue = build_app_issue(&name, &spec, &status, &now);
+        (spec.project, issue)
+    });
+
+    let projects = group_by_project(apps);
+    info!("ArgoCD projects: {}", projects.len());
+    Ok(projects)
+}
+
+/// Latest sync operation of an application
+#[derive(Clone, Debug, Serialize)]
+pub struct OperationSummary {
+    pub app: String,
+    pub phase: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub message: Option<String>,
+    pub revision: Option<String>,
+}
+
+/// The last operation of every application, most recently finished first
+pub async fn get_recent_operations(cluster: Option<&str>) -> Result<Vec<OperationSummary>, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let app_list = applications_api(client)
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list ArgoCD applications", e))?;
+
+    let mut operations: Vec<OperationSummary> = app_list
+        .items
+        .iter()
+        .filter_map(|app| {
+            let (name, _, status) = parse_application(app);
+            let op = status.operation_state?;
+            Some(OperationSummary {
+                app: name,
+                phase: op.phase.unwrap_or_else(|| "Unknown".to_string()),
+                started_at: op.started_at,
+                finished_at: op.finished_at,
+                message: op.message,
+                revision: op.sync_result.and_then(|r| r.revision),
+            })
+        })
+        .collect();
+
+    sort_operations(&mut operations);
+    info!("ArgoCD operations: {}", operations.len());
+    Ok(operations)
+}
+
+/// Still-running operations first, then by finish time, newest first
+/// ArgoCD writes RFC 3339 UTC timestamps, so they order correctly as strings
+pub fn sort_operations(operations: &mut [OperationSummary]) {
+    operations.sort_by(|a, b| {
+        b.finished_at
+            .is_none()
+            .cmp(&a.finished_at.is_none())
+            .then_with(|| b.finished_at.cmp(&a.finished_at))
+    });
+}
+
+/// Group apps by project name (missing or empty projects go under "default"),
+/// projects sorted by name and apps by name within each project
+pub fn group_by_project(apps: impl IntoIterator<Item = (Option<String>, AppIssue)>) -> Vec<ProjectApps> {
+    let mut by_project: std::collections::BTreeMap<String, Vec<AppIssue>> = std::collections::BTreeMap::new();
+    for (project, app) in apps {
+        let project = project
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| "default".to_string());
+        by_project.entry(project).or_default().push(app);
+    }
+
+    by_project
+        .into_iter()
+        .map(|(project, mut apps)| {
+            apps.sort_by(|a, b| a.name.cmp(&b.name));
+            ProjectApps {
+                project,
+                total: apps.len(),
+                healthy: apps.iter().filter(|a| a.health_status == "Healthy").count(),
+                apps,
+            }
+        })
+        .collect()
+}
+
+/// ArgoCD ApplicationSet summary
+#[derive(Clone, Debug, Serialize)]
+pub struct AppSetInfo {
+    pub name: String,
+    pub namespace: String,
+    /// Generator types from `spec.generators` (e.g. "list", "git", "clusters")
+    pub generators: Vec<String>,
+    pub generated_apps: usize,
+}
+
+/// List ApplicationSets with their generator types and how many apps each generated
+pub async fn get_application_sets(cluster: Option<&str>) -> Result<Vec<AppSetInfo>, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let appsets_api: Api<kube::core::DynamicObject> = Api::namespaced_with(
+        client.clone(),
+        "argocd",
+        &kube::discovery::ApiResource {
+            group: "argoproj.io".to_string(),
+            version: "v1alpha1".to_string(),
+            api_version: "argoproj.io/v1alpha1".to_string(),
+            kind: "ApplicationSet".to_string(),
+            plural: "applicationsets".to_string(),
+        },
+    );
+
+    let appsets = appsets_api
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list ArgoCD ApplicationSets", e))?;
+
+    let apps = applications_api(client)
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list ArgoCD applications", e))?;
+
+    let mut infos: Vec<AppSetInfo> = appsets
+        .items
+        .iter()
+        .map(|set| {
+            let name = set.metadata.name.clone().unwrap_or_default();
+            AppSetInfo {
+                generated_apps: count_generated_apps(&name, &apps.items),
+                namespace: set.metadata.namespace.clone().unwrap_or_default(),
+                generators: generator_types(&set.data),
+                name,
+            }
+        })
+        .collect();
+    infos.sort_by(|a, b| a.name.cmp(&b.name));
+
+    info!("ArgoCD ApplicationSets: {}", infos.len());
+    Ok(infos)
+}
+
+/// Generator types of an ApplicationSet: the key of each `spec.generators` entry
+fn generator_types(data: &serde_json::Value) -> Vec<String> {
+    data.pointer("/spec/generators")
+        .and_then(|g| g.as_array())
+        .map(|generators| {
+            generators
+                .iter()
+                .filter_map(|g| g.as_object())
+                .flat_map(|g| g.keys().cloned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Count applications owned by the named ApplicationSet
+pub fn count_generated_apps(appset_name: &str, apps: &[kube::core::DynamicObject]) -> usize {
+    apps.iter()
+        .filter(|app| {
+            app.metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .any(|o| o.kind == "ApplicationSet" && o.name == appset_name)
+        })
+        .count()
+}
+
+/// Sync state of one managed resource
+#[derive(Clone, Debug, Serialize)]
+pub struct ResourceDiff {
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    pub group: Option<String>,
+    pub sync_status: String,
+    pub health_status: Option<String>,
+    pub requires_pruning: bool,
+}
+
+/// What a sync of the application would change
+#[derive(Clone, Debug, Serialize)]
+pub struct AppDiff {
+    pub app: String,
+    pub sync_status: String,
+    /// Revision the live state was last compared against
+    pub revision: Option<String>,
+    /// Desired source/destination used for the comparison
+    pub compared_to: Option<ComparedTo>,
+    pub out_of_sync: Vec<ResourceDiff>,
+}
+
+/// Get the out-of-sync resources of an application from `status.resources`
+pub async fn get_app_diff(app_name: &str) -> Result<AppDiff, KusanagiError> {
+    let client = Client::try_default()
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to create Kubernetes client", e))?;
+
+    let app = applications_api(client)
+        .get(app_name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get ArgoCD application {}", app_name), e))?;
+
+    let (name, _, status) = parse_application(&app);
+    let sync = status.sync.as_ref();
+
+    Ok(AppDiff {
+        app: name,
+        sync_status: sync
+            .and_then(|s| s.status.clone())
+            .unwrap_or_else(|| "Unknown".to_string()),
+        revision: sync.and_then(|s| s.revision.clone()),
+        compared_to: sync.and_then(|s| s.compared_to.clone()),
+        out_of_sync: out_of_sync_resources(status.resources.as_deref().unwrap_or_default()),
+    })
+}
+
+/// Full detail of one application: spec, status and its resource tree
+pub async fn get_application(app_name: &str) -> Result<Application, KusanagiError> {
+    let client = Client::try_default()
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to create Kubernetes client", e))?;
+
+    let app = applications_api(client)
+        .get(app_name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get ArgoCD application {}", app_name), e))?;
+
+    let (name, spec, status) = parse_application(&app);
+
+    Ok(Application {
+        metadata: ApplicationMetadata {
+            name: Some(name),
+            namespace: app.metadata.namespace.clone(),
+        },
+        spec,
+        status: Some(status),
+    })
+}
+
+/// Resources whose sync status isn't Synced, or that ArgoCD would prune
+pub fn out_of_sync_resources(resources: &[ResourceStatus]) -> Vec<ResourceDiff> {
+    resources
+        .iter()
+        .filter(|r| r.status.as_deref() != Some("Synced") || r.requires_pruning.unwrap_or(false))
+        .map(|r| ResourceDiff {
+            kind: r.kind.clone().unwrap_or_default(),
+            name: r.name.clone().unwrap_or_default(),
+            namespace: r.namespace.clone(),
+            group: r.group.clone().filter(|g| !g.is_empty()),
+            sync_status: r.status.clone().unwrap_or_else(|| "Unknown".to_string()),
+            health_status: r.health.as_ref().and_then(|h| h.status.clone()),
+            requires_pruning: r.requires_pruning.unwrap_or(false),
+        })
+        .collect()
+}
+
+/// Dynamic API for ArgoCD Applications in the argocd namespace
+fn applications_api(client: Client) -> Api<kube::core::DynamicObject> {
+    Api::namespaced_with(
+        client,
+        "argocd",
+        &kube::discovery::ApiResource {
+            group: "argoproj.io".to_string(),
+            version: "v1alpha1".to_string(),
+            api_version: "argoproj.io/v1alpha1".to_string(),
+            kind: "Application".to_string(),
+            plural: "applications".to_string(),
+        },
+    )
+}
+
+/// Extract name, spec and status from a dynamic Application object
+fn parse_application(app: &kube::core::DynamicObject) -> (String, ApplicationSpec, ApplicationStatus) {
+    let name = app.metadata.name.clone().unwrap_or_default();
+
+    let status: ApplicationStatus = app
+        .data
+        .get("status")
+        .and_then(|s| serde_json::from_value(s.clone()).ok())
+        .unwrap_or_default();
+
+    let spec: ApplicationSpec = app
+        .data
+        .get("spec")
+        .and_then(|s| serde_json::from_value(s.clone()).ok())
+        .unwrap_or(ApplicationSpec {
+            project: None,
+            source: None,
+            destination: None,
+        });
+
+    (name, spec, status)
+}
+
+/// An app needs attention when it isn't healthy or isn't known to be in sync
+fn has_issue(app: &AppIssue) -> bool {
+    app.health_status != "Healthy" || app.sync_status == "OutOfSync" || app.sync_status == "Unknown"
+}
+
+/// Build the API representation of an application
+fn build_app_issue(
+    name: &str,
+    spec: &ApplicationSpec,
+    status: &ApplicationStatus,
+    now: &DateTime<Utc>,
+) -> AppIssue {
+    let dest_namespace = spec
+        .destination
+        .as_ref()
+        .and_then(|d| d.namespace.clone())
+        .unwrap_or_default();
+
+    let health_status = status
+        .health
+        .as_ref()
+        .and_then(|h| h.status.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    let sync_status = status
+        .sync
+        .as_ref()
+        .and_then(|s| s.status.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    // Check if this is a Helm chart
+    let is_helm_chart = spec.source.as_ref().and_then(|s| s.chart.clone()).is_some();
+
+    // Get target revision (could be a version like "1.2.3" or "*" or "HEAD")
+    let target_revision = spec.source.as_ref().and_then(|s| s.target_revision.clone());
+
+    // Get current synced revision
+    let current_revision = status.sync.as_ref().and_then(|s| s.revision.clone());
+
+    let message = status
+        .health
+        .as_ref()
+        .and_then(|h| h.message.clone())
+        .or_else(|| {
+            status
+                .operation_state
+                .as_ref()
+                .and_then(|o| o.message.clone())
+        });
+
+    // Determine the category of the issue
+    let category = categorize_issue(
+        &health_status,
+        &sync_status,
+        &message,
+        is_helm_chart,
+        &target_revision,
+    );
+
+    let reasons = issue_reasons(&health_status, &sync_status, status);
+
+    // Try to determine when the error started
+    let (error_since, error_duration) = calculate_error_duration(status, now);
+    let stale = is_stale(status.reconciled_at.as_deref(), now, stale_threshold());
+
+    // Build ArgoCD URL
+    let argocd_url = format!(
+        "https://argocd.p.zacharie.org/applications/argocd/{}",
+        name
+    );
+
+    AppIssue {
+        name: name.to_string(),
+        namespace: dest_namespace,
+        can_sync: can_sync(&health_status, allow_sync_degraded()),
+        health_status,
+        sync_status,
+        message,
+        reasons,
+        error_since,
+        error_duration,
+        category,
+        target_revision,
+        current_revision,
+        is_helm_chart,
+        argocd_url,
+        stale,
+    }
+}
+
+/// Break an issue down into the factors that make the app unhealthy
+pub fn issue_reasons(health_status: &str, sync_status: &str, status: &ApplicationStatus) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    if health_status != "Healthy" {
+        reasons.push(format!("Health: {}", health_status));
+    }
+    if sync_status != "Synced" {
+        reasons.push(format!("Sync: {}", sync_status));
+    }
+
+    if let Some(phase) = status.operation_state.as_ref().and_then(|o| o.phase.as_deref()) {
+        if phase == "Failed" || phase == "Error" {
+            reasons.push(format!("Operation failed: {}", phase));
+        }
+    }
+
+    let pruning = status
+        .resources
+        .iter()
+        .flatten()
+        .filter(|r| r.requires_pruning == Some(true))
+        .count();
+    if pruning > 0 {
+        reasons.push(format!("{} resources require pruning", pruning));
+    }
+
+    reasons
+}
+
+/// Whether Degraded and Missing apps may be synced (ARGOCD_ALLOW_SYNC_DEGRADED, default false)
+fn allow_sync_degraded() -> bool {
+    std::env::var("ARGOCD_ALLOW_SYNC_DEGRADED")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Sync policy: Healthy and Progressing apps always, Degraded and Missing ones only when allowed,
+/// since a sync may be what fixes them
+pub fn can_sync(health_status: &str, allow_degraded: bool) -> bool {
+    match health_status {
+        "Healthy" | "Progressing" => true,
+        "Degraded" | "Missing" => allow_degraded,
+        _ => false,
+    }
+}
+
+/// How long an app may go without reconciling before it is flagged stale
+/// (ARGOCD_STALE_THRESHOLD_SECS, default 1h)
+fn stale_threshold() -> chrono::Duration {
+    let secs = std::env::var("ARGOCD_STALE_THRESHOLD_SECS")
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(3600);
+    chrono::Duration::seconds(secs)
+}
+
+/// True when `reconciled_at` is older than `threshold`
+/// Apps that never reconciled or carry an unparseable timestamp are not flagged
+pub fn is_stale(reconciled_at: Option<&str>, now: &DateTime<Utc>, threshold: chrono::Duration) -> bool {
+    reconciled_at
+        .and_then(|r| DateTime::parse_from_rfc3339(r).ok())
+        .is_some_and(|r| now.signed_duration_since(r.with_timezone(&Utc)) > threshold)
+}
+
 /// Categorize the type of issue
 fn categorize_issue(
     health_status: &str,
@@ -393,11 +803,53 @@ fn categorize_issue(
     IssueCategory::RealIssue
 }
 
-/// Trigger sync for an ArgoCD application
-pub async fn sync_application(app_name: &str) -> Result<SyncResponse, String> {
-    let client = Client::try_default()
+/// Check that an application exists and could be synced, without patching it
+async fn dry_run_sync(client: Client, app_name: &str) -> Result<SyncResponse, KusanagiError> {
+    let app = applications_api(client)
+        .get(app_name)
         .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get application {}", app_name), e))?;
+
+    let (name, spec, status) = parse_application(&app);
+    let issue = build_app_issue(&name, &spec, &status, &Utc::now());
+
+    if !issue.can_sync {
+        return Ok(SyncResponse {
+            success: false,
+            message: format!(
+                "Dry run: {} cannot be synced while its health is {}",
+                app_name, issue.health_status
+            ),
+        });
+    }
+
+    Ok(SyncResponse {
+        success: true,
+        message: format!(
+            "Dry run: would sync {} ({} / {}) to revision {}",
+            app_name,
+            issue.sync_status,
+            issue.health_status,
+            issue.target_revision.as_deref().unwrap_or("HEAD")
+        ),
+    })
+}
+
+/// Trigger sync for an ArgoCD application
+/// With `dry_run` the app is only checked to exist and be syncable; nothing is patched
+pub async fn sync_application(
+    cluster: Option<&str>,
+    app_name: &str,
+    dry_run: bool,
+) -> Result<SyncResponse, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+    sync_with_client(client, app_name, dry_run).await
+}
+
+async fn sync_with_client(client: Client, app_name: &str, dry_run: bool) -> Result<SyncResponse, KusanagiError> {
+    if dry_run {
+        return dry_run_sync(client, app_name).await;
+    }
 
     let apps_api: Api<kube::core::DynamicObject> = Api::namespaced_with(
         client,
@@ -434,7 +886,7 @@ pub async fn sync_application(app_name: &str) -> Result<SyncResponse, String> {
     apps_api
         .patch(app_name, &patch_params, &Patch::Merge(&patch))
         .await
-        .map_err(|e| format!("Failed to sync application {}: {}", app_name, e))?;
+        .map_err(|e| KusanagiError::kube(&format!("Failed to sync application {}", app_name), e))?;
 
     info!("Triggered sync for application: {}", app_name);
 
@@ -444,6 +896,59 @@ pub async fn sync_application(app_name: &str) -> Result<SyncResponse, String> {
     })
 }
 
+/// Apps a bulk sync acts on: out of sync and allowed to sync by the policy
+pub fn sync_candidates(apps: &[AppIssue]) -> Vec<&AppIssue> {
+    apps.iter()
+        .filter(|a| a.sync_status == "OutOfSync" && a.can_sync)
+        .collect()
+}
+
+/// Sync every out-of-sync application, a few at a time
+/// A failure on one app is reported in its result and doesn't stop the others
+pub async fn sync_all_out_of_sync(
+    cluster: Option<&str>,
+    dry_run: bool,
+) -> Result<Vec<AppSyncResult>, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let app_list = applications_api(client.clone())
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list ArgoCD applications", e))?;
+
+    let now = Utc::now();
+    let apps: Vec<AppIssue> = app_list
+        .items
+        .iter()
+        .map(|app| {
+            let (name, spec, status) = parse_application(app);
+            build_app_issue(&name, &spec, &status, &now)
+        })
+        .collect();
+
+    let names: Vec<String> = sync_candidates(&apps).into_iter().map(|a| a.name.clone()).collect();
+    info!("Bulk sync of {} out-of-sync applications (dry run: {})", names.len(), dry_run);
+
+    let results = futures::stream::iter(names)
+        .map(|app| {
+            let client = client.clone();
+            async move {
+                let result = sync_with_client(client, &app, dry_run)
+                    .await
+                    .unwrap_or_else(|e| SyncResponse {
+                        success: false,
+                        message: e.to_string(),
+                    });
+                AppSyncResult { app, result }
+            }
+        })
+        .buffer_unordered(SYNC_ALL_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    Ok(results)
+}
+
 fn calculate_error_duration(
     status: &ApplicationStatus,
     now: &DateTime<Utc>,
@@ -493,3 +998,285 @@ fn format_duration(duration: chrono::Duration) -> String {
         format!("{}s", total_seconds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_sync_follows_the_degraded_policy() {
+        for allow_degraded in [false, true] {
+            assert!(can_sync("Healthy", allow_degraded));
+            assert!(can_sync("Progressing", allow_degraded));
+            assert_eq!(can_sync("Degraded", allow_degraded), allow_degraded);
+            assert_eq!(can_sync("Missing", allow_degraded), allow_degraded);
+            assert!(!can_sync("Unknown", allow_degraded));
+        }
+    }
+
+    fn app(name: &str, sync_status: &str, can_sync: bool) -> AppIssue {
+        AppIssue {
+            name: name.to_string(),
+            namespace: "argocd".to_string(),
+            health_status: "Healthy".to_string(),
+            sync_status: sync_status.to_string(),
+            message: None,
+            reasons: Vec::new(),
+            error_since: None,
+            error_duration: None,
+            category: IssueCategory::RealIssue,
+            target_revision: None,
+            current_revision: None,
+            is_helm_chart: false,
+            can_sync,
+            argocd_url: String::new(),
+            stale: false,
+        }
+    }
+
+    #[test]
+    fn sync_candidates_are_out_of_sync_and_syncable() {
+        let apps = vec![
+            app("web", "OutOfSync", true),
+            app("db", "OutOfSync", false),
+            app("cache", "Synced", true),
+            app("queue", "Unknown", true),
+        ];
+        let names: Vec<&str> = sync_candidates(&apps).iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["web"]);
+    }
+
+    #[test]
+    fn apps_are_grouped_by_project() {
+        let mut degraded = app("api", "Synced", true);
+        degraded.health_status = "Degraded".to_string();
+        let groups = group_by_project(vec![
+            (Some("platform".to_string()), app("ingress", "Synced", true)),
+            (Some("team-a".to_string()), degraded),
+            (Some("team-a".to_string()), app("web", "Synced", true)),
+            (None, app("orphan", "Synced", true)),
+            (Some(String::new()), app("blank", "Synced", true)),
+        ]);
+
+        let summary: Vec<(&str, usize, usize)> = groups
+            .iter()
+            .map(|g| (g.project.as_str(), g.total, g.healthy))
+            .collect();
+        assert_eq!(summary, vec![("default", 2, 2), ("platform", 1, 1), ("team-a", 2, 1)]);
+
+        let default_apps: Vec<&str> = groups[0].apps.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(default_apps, vec!["blank", "orphan"]);
+    }
+
+    #[test]
+    fn generated_apps_are_counted_by_owner_reference() {
+        let application = |name: &str, owner: Option<(&str, &str)>| -> kube::core::DynamicObject {
+            let owners: Vec<serde_json::Value> = owner
+                .map(|(kind, owner_name)| serde_json::json!({
+                    "apiVersion": "argoproj.io/v1alpha1",
+                    "kind": kind,
+                    "name": owner_name,
+                    "uid": "1234",
+                }))
+                .into_iter()
+                .collect();
+            serde_json::from_value(serde_json::json!({
+                "apiVersion": "argoproj.io/v1alpha1",
+                "kind": "Application",
+                "metadata": { "name": name, "namespace": "argocd", "ownerReferences": owners },
+            }))
+            .unwrap()
+        };
+        let apps = vec![
+            application("web-dev", Some(("ApplicationSet", "web"))),
+            application("web-prod", Some(("ApplicationSet", "web"))),
+            application("db-prod", Some(("ApplicationSet", "db"))),
+            application("web", Some(("Application", "web"))),
+            application("standalone", None),
+        ];
+
+        assert_eq!(count_generated_apps("web", &apps), 2);
+        assert_eq!(count_generated_apps("db", &apps), 1);
+        assert_eq!(count_generated_apps("cache", &apps), 0);
+
+        let generators = generator_types(&serde_json::json!({
+            "spec": { "generators": [{ "list": {} }, { "git": {} }] }
+        }));
+        assert_eq!(generators, vec!["list", "git"]);
+    }
+
+    #[test]
+    fn diff_lists_out_of_sync_and_prunable_resources() {
+        let resources: Vec<ResourceStatus> = serde_json::from_value(serde_json::json!([
+            { "kind": "Deployment", "name": "web", "namespace": "shop", "group": "apps", "status": "OutOfSync",
+              "health": { "status": "Healthy" } },
+            { "kind": "Service", "name": "web", "namespace": "shop", "group": "", "status": "Synced" },
+            { "kind": "ConfigMap", "name": "legacy", "namespace": "shop", "status": "Synced", "requiresPruning": true },
+            { "kind": "Secret", "name": "creds", "namespace": "shop" }
+        ]))
+        .unwrap();
+
+        let diff = out_of_sync_resources(&resources);
+        let summary: Vec<(&str, &str, bool)> = diff
+            .iter()
+            .map(|r| (r.kind.as_str(), r.sync_status.as_str(), r.requires_pruning))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("Deployment", "OutOfSync", false), ("ConfigMap", "Synced", true), ("Secret", "Unknown", false)]
+        );
+        assert_eq!(diff[0].group.as_deref(), Some("apps"));
+        assert_eq!(diff[0].health_status.as_deref(), Some("Healthy"));
+    }
+
+    #[test]
+    fn apps_are_stale_past_the_threshold() {
+        let now: DateTime<Utc> = "2024-03-01T12:00:00Z".parse().unwrap();
+        let hour = chrono::Duration::hours(1);
+
+        assert!(is_stale(Some("2024-03-01T10:30:00Z"), &now, hour));
+        assert!(!is_stale(Some("2024-03-01T11:30:00Z"), &now, hour));
+        assert!(!is_stale(Some("2024-03-01T11:00:00Z"), &now, hour));
+        assert!(is_stale(Some("2024-03-01T11:30:00+00:00"), &now, chrono::Duration::minutes(15)));
+        assert!(!is_stale(None, &now, hour));
+        assert!(!is_stale(Some("yesterday"), &now, hour));
+    }
+
+    /// A Kubernetes API server answering every request with the `web` Application,
+    /// recording each request line
+    async fn mock_api_server() -> (Client, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        tokio::spawn(async move {
+            let body = json!({
+                "apiVersion": "argoproj.io/v1alpha1",
+                "kind": "Application",
+                "metadata": { "name": "web", "namespace": "argocd" },
+                "spec": { "project": "default", "source": { "targetRevision": "v1.2.0" } },
+                "status": { "health": { "status": "Healthy" }, "sync": { "status": "OutOfSync" } }
+            })
+            .to_string();
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 8192];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                recorded.lock().unwrap().push(request.lines().next().unwrap_or_default().to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let config = kube::Config::new(url.parse().unwrap());
+        (Client::try_from(config).unwrap(), seen)
+    }
+
+    #[tokio::test]
+    async fn dry_run_issues_no_patch() {
+        let (client, seen) = mock_api_server().await;
+        let response = sync_with_client(client, "web", true).await.unwrap();
+
+        assert!(response.success);
+        assert_eq!(response.message, "Dry run: would sync web (OutOfSync / Healthy) to revision v1.2.0");
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert!(seen[0].starts_with("GET /apis/argoproj.io/v1alpha1/namespaces/argocd/applications/web"), "{:?}", seen);
+    }
+
+    #[test]
+    fn degraded_out_of_sync_app_lists_each_reason() {
+        let status: ApplicationStatus = serde_json::from_value(json!({
+            "health": { "status": "Degraded" },
+            "sync": { "status": "OutOfSync" },
+            "operationState": { "phase": "Failed", "message": "one or more objects failed to apply" },
+            "resources": [
+                { "kind": "Deployment", "name": "api", "status": "OutOfSync" },
+                { "kind": "ConfigMap", "name": "old-config", "status": "OutOfSync", "requiresPruning": true },
+                { "kind": "Secret", "name": "old-creds", "status": "OutOfSync", "requiresPruning": true }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            issue_reasons("Degraded", "OutOfSync", &status),
+            vec![
+                "Health: Degraded",
+                "Sync: OutOfSync",
+                "Operation failed: Failed",
+                "2 resources require pruning"
+            ]
+        );
+        assert!(issue_reasons("Healthy", "Synced", &ApplicationStatus::default()).is_empty());
+    }
+
+    #[test]
+    fn application_resource_tree_deserializes() {
+        let app: kube::core::DynamicObject = serde_json::from_value(json!({
+            "apiVersion": "argoproj.io/v1alpha1",
+            "kind": "Application",
+            "metadata": { "name": "shop", "namespace": "argocd" },
+            "spec": {
+                "project": "default",
+                "source": { "repoURL": "https://github.com/example/shop", "path": "deploy", "targetRevision": "main" },
+                "destination": { "server": "https://kubernetes.default.svc", "namespace": "shop" }
+            },
+            "status": {
+                "health": { "status": "Healthy" },
+                "sync": { "status": "Synced", "revision": "abc123" },
+                "resources": [
+                    {
+                        "group": "apps",
+                        "version": "v1",
+                        "kind": "Deployment",
+                        "namespace": "shop",
+                        "name": "api",
+                        "status": "Synced",
+                        "health": { "status": "Healthy" }
+                    },
+                    { "version": "v1", "kind": "Service", "namespace": "shop", "name": "api", "status": "Synced" }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let (name, spec, status) = parse_application(&app);
+        assert_eq!(name, "shop");
+        assert_eq!(spec.project.as_deref(), Some("default"));
+        let resources = status.resources.unwrap();
+        assert_eq!(resources.len(), 2);
+        assert_eq!(resources[0].group.as_deref(), Some("apps"));
+        assert_eq!(resources[0].kind.as_deref(), Some("Deployment"));
+        assert_eq!(resources[0].health.as_ref().and_then(|h| h.status.as_deref()), Some("Healthy"));
+        assert_eq!(resources[1].group, None);
+        assert!(resources[1].health.is_none());
+    }
+
+    #[test]
+    fn operations_sort_running_first_then_newest_finish() {
+        let op = |app: &str, finished_at: Option<&str>| OperationSummary {
+            app: app.to_string(),
+            phase: if finished_at.is_some() { "Succeeded" } else { "Running" }.to_string(),
+            started_at: Some("2024-03-01T09:00:00Z".to_string()),
+            finished_at: finished_at.map(String::from),
+            message: None,
+            revision: None,
+        };
+        let mut operations = vec![
+            op("oldest", Some("2024-03-01T09:05:00Z")),
+            op("newest", Some("2024-03-01T11:30:00Z")),
+            op("running", None),
+            op("middle", Some("2024-03-01T10:00:00Z")),
+        ];
+
+        sort_operations(&mut operations);
+        let order: Vec<&str> = operations.iter().map(|o| o.app.as_str()).collect();
+        assert_eq!(order, vec!["running", "newest", "middle", "oldest"]);
+    }
+}
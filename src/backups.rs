@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
 use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::Pod;
 use kube::{
-    api::{Api, ListParams},
+    api::{Api, ListParams, LogParams},
     Client,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tracing::info;
 
 /// Backups response for the API
@@ -38,18 +39,76 @@ pub struct JobInfo {
     pub duration: Option<String>,
 }
 
+/// Which CronJobs count as backups
+/// Defaults come from BACKUP_LABEL_SELECTOR (e.g. `app.kubernetes.io/component=backup`)
+/// and BACKUP_NAME_CONTAINS; with neither set every CronJob is listed
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct BackupFilter {
+    pub label_selector: Option<String>,
+    pub name_contains: Option<String>,
+}
+
+impl BackupFilter {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|s| !s.trim().is_empty());
+        Self {
+            label_selector: var("BACKUP_LABEL_SELECTOR"),
+            name_contains: var("BACKUP_NAME_CONTAINS"),
+        }
+    }
+
+    /// Fill unset fields from the environment defaults
+    pub fn or_env(self) -> Self {
+        let env = Self::from_env();
+        Self {
+            label_selector: self.label_selector.or(env.label_selector),
+            name_contains: self.name_contains.or(env.name_contains),
+        }
+    }
+
+    fn list_params(&self) -> ListParams {
+        match &self.label_selector {
+            Some(selector) => ListParams::default().labels(selector),
+            None => ListParams::default(),
+        }
+    }
+
+    fn matches_name(&self, cronjob: &CronJob) -> bool {
+        match &self.name_contains {
+            Some(pattern) => cronjob
+                .metadata
+                .name
+                .as_deref()
+                .map(|name| name.to_lowercase().contains(&pattern.to_lowercase()))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// List the CronJobs selected by `filter`
+async fn list_backup_cronjobs(client: Client, filter: &BackupFilter) -> Result<Vec<CronJob>, String> {
+    let cronjobs_api: Api<CronJob> = Api::all(client);
+    let cronjobs = cronjobs_api
+        .list(&filter.list_params())
+        .await
+        .map_err(|e| format!("Failed to list CronJobs: {}", e))?;
+
+    Ok(cronjobs
+        .items
+        .into_iter()
+        .filter(|cj| filter.matches_name(cj))
+        .collect())
+}
+
 /// Get backup CronJobs and their recent Jobs
-pub async fn get_backups_status() -> Result<BackupsResponse, String> {
+pub async fn get_backups_status(filter: &BackupFilter) -> Result<BackupsResponse, String> {
     let client = Client::try_default()
         .await
         .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
 
-    // Get all CronJobs
-    let cronjobs_api: Api<CronJob> = Api::all(client.clone());
-    let cronjobs = cronjobs_api
-        .list(&ListParams::default())
-        .await
-        .map_err(|e| format!("Failed to list CronJobs: {}", e))?;
+    // Get backup CronJobs
+    let cronjobs = list_backup_cronjobs(client.clone(), filter).await?;
 
     // Get all Jobs
     let jobs_api: Api<Job> = Api::all(client);
@@ -62,7 +121,6 @@ pub async fn get_backups_status() -> Result<BackupsResponse, String> {
 
     // Process CronJobs
     let mut cronjob_infos: Vec<CronJobInfo> = cronjobs
-        .items
         .iter()
         .map(|cj| {
             let name = cj.metadata.name.clone().unwrap_or_default();
@@ -124,12 +182,24 @@ pub async fn get_backups_status() -> Result<BackupsResponse, String> {
         }
     });
 
-    // Calculate statistics from all jobs
+    // Calculate statistics from all jobs (only those of selected CronJobs when filtering)
+    let filtered = filter.label_selector.is_some() || filter.name_contains.is_some();
+    let counted_jobs: Vec<&Job> = jobs
+        .items
+        .iter()
+        .filter(|job| {
+            !filtered
+                || cronjob_infos
+                    .iter()
+                    .any(|cj| is_owned_by(job, &cj.name, &cj.namespace))
+        })
+        .collect();
+
     let mut active_count = 0;
     let mut succeeded_count = 0;
     let mut failed_count = 0;
 
-    for job in &jobs.items {
+    for job in &counted_jobs {
         let status = job.status.as_ref();
         if let Some(status) = status {
             if status.active.unwrap_or(0) > 0 {
@@ -145,7 +215,7 @@ pub async fn get_backups_status() -> Result<BackupsResponse, String> {
     info!(
         "Backups: {} CronJobs, {} Jobs ({} active, {} succeeded, {} failed)",
         cronjob_infos.len(),
-        jobs.items.len(),
+        counted_jobs.len(),
         active_count,
         succeeded_count,
         failed_count
@@ -169,26 +239,7 @@ fn get_jobs_for_cronjob(
 ) -> Vec<JobInfo> {
     let mut jobs: Vec<JobInfo> = all_jobs
         .iter()
-        .filter(|job| {
-            // Check if job belongs to this cronjob via owner reference
-            let job_ns = job
-                .metadata
-                .namespace
-                .as_ref()
-                .map(|s| s.as_str())
-                .unwrap_or("default");
-
-            if job_ns != namespace {
-                return false;
-            }
-
-            // Check owner references
-            if let Some(owners) = &job.metadata.owner_references {
-                return owners.iter().any(|o| o.kind == "CronJob" && o.name == cronjob_name);
-            }
-
-            false
-        })
+        .filter(|job| is_owned_by(job, cronjob_name, namespace))
         .map(|job| {
             let name = job.metadata.name.clone().unwrap_or_default();
             let status = job.status.as_ref();
@@ -234,6 +285,176 @@ fn get_jobs_for_cronjob(
     jobs
 }
 
+/// Check if a job belongs to a CronJob via its owner reference
+fn is_owned_by(job: &Job, cronjob_name: &str, namespace: &str) -> bool {
+    let job_ns = job
+        .metadata
+        .namespace
+        .as_ref()
+        .map(|s| s.as_str())
+        .unwrap_or("default");
+
+    if job_ns != namespace {
+        return false;
+    }
+
+    if let Some(owners) = &job.metadata.owner_references {
+        return owners.iter().any(|o| o.kind == "CronJob" && o.name == cronjob_name);
+    }
+
+    false
+}
+
+/// The newest pod created by `job_name`, matched by owner reference
+/// (a Job that retried has one pod per attempt; the latest one is usually the interesting one)
+pub fn latest_job_pod<'a>(pods: &'a [Pod], job_name: &str) -> Option<&'a Pod> {
+    pods.iter()
+        .filter(|pod| {
+            pod.metadata
+                .owner_references
+                .as_ref()
+                .is_some_and(|owners| owners.iter().any(|o| o.kind == "Job" && o.name == job_name))
+        })
+        .max_by_key(|pod| pod.metadata.creation_timestamp.as_ref().map(|t| t.0))
+}
+
+/// Last `tail_lines` log lines of the newest pod of a Job
+/// Jobs without a started pod yield an explanatory message rather than an error
+pub async fn get_job_logs(namespace: &str, job_name: &str, tail_lines: i64) -> Result<String, String> {
+    let client = Client::try_default()
+        .await
+        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+
+    let pods_api: Api<Pod> = Api::namespaced(client, namespace);
+    // The Job controller labels its pods with job-name; owner references make the final call
+    let pods = pods_api
+        .list(&ListParams::default().labels(&format!("job-name={}", job_name)))
+        .await
+        .map_err(|e| format!("Failed to list pods for job {}: {}", job_name, e))?;
+
+    let Some(pod) = latest_job_pod(&pods.items, job_name) else {
+        return Ok(format!("Job {}/{} has no pods (not scheduled yet or already cleaned up)", namespace, job_name));
+    };
+    let pod_name = pod.metadata.name.clone().unwrap_or_default();
+
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref()).unwrap_or("Unknown");
+    if phase == "Pending" {
+        return Ok(format!("Pod {} of job {} is still pending; no logs yet", pod_name, job_name));
+    }
+
+    let params = LogParams {
+        container: pod
+            .spec
+            .as_ref()
+            .and_then(|s| s.containers.first())
+            .map(|c| c.name.clone()),
+        tail_lines: Some(tail_lines),
+        ..Default::default()
+    };
+
+    let logs = pods_api
+        .logs(&pod_name, &params)
+        .await
+        .map_err(|e| format!("Failed to get logs for pod {}: {}", pod_name, e))?;
+
+    info!("Fetched logs of job {}/{} (pod {})", namespace, job_name, pod_name);
+    Ok(logs)
+}
+
+/// Success statistics for one CronJob over a time window
+#[derive(Clone, Debug, Serialize)]
+pub struct BackupHistory {
+    pub name: String,
+    pub namespace: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Percentage of finished jobs that succeeded; None when no job finished in the window
+    pub success_rate: Option<f64>,
+    pub last_success: Option<String>,
+}
+
+/// Percentage of finished jobs that succeeded, rounded to one decimal
+pub fn success_rate(succeeded: usize, failed: usize) -> Option<f64> {
+    let finished = succeeded + failed;
+    if finished == 0 {
+        return None;
+    }
+    Some((succeeded as f64 / finished as f64 * 1000.0).round() / 10.0)
+}
+
+/// Per-CronJob success rate over the last `days` days
+/// Limited to the Jobs Kubernetes still retains (see the CronJob's
+/// successfulJobsHistoryLimit / failedJobsHistoryLimit)
+pub async fn get_backup_history(days: i64, filter: &BackupFilter) -> Result<Vec<BackupHistory>, String> {
+    let client = Client::try_default()
+        .await
+        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+
+    let cronjobs = list_backup_cronjobs(client.clone(), filter).await?;
+
+    let jobs_api: Api<Job> = Api::all(client);
+    let jobs = jobs_api
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| format!("Failed to list Jobs: {}", e))?;
+
+    let cutoff = Utc::now() - chrono::Duration::days(days);
+
+    let mut history: Vec<BackupHistory> = cronjobs
+        .iter()
+        .map(|cj| {
+            let name = cj.metadata.name.clone().unwrap_or_default();
+            let namespace = cj
+                .metadata
+                .namespace
+                .clone()
+                .unwrap_or_else(|| "default".to_string());
+
+            let mut succeeded = 0;
+            let mut failed = 0;
+            let mut last_success: Option<DateTime<Utc>> = None;
+
+            for job in jobs.items.iter().filter(|job| is_owned_by(job, &name, &namespace)) {
+                let status = match job.status.as_ref() {
+                    Some(status) => status,
+                    None => continue,
+                };
+                let started = status
+                    .start_time
+                    .as_ref()
+                    .or(job.metadata.creation_timestamp.as_ref())
+                    .map(|t| t.0);
+                if !matches!(started, Some(ts) if ts >= cutoff) {
+                    continue;
+                }
+
+                if status.succeeded.unwrap_or(0) > 0 {
+                    succeeded += 1;
+                    let finished = status.completion_time.as_ref().map(|t| t.0).or(started);
+                    last_success = last_success.max(finished);
+                } else if status.failed.unwrap_or(0) > 0 && status.active.unwrap_or(0) == 0 {
+                    failed += 1;
+                }
+            }
+
+            BackupHistory {
+                name,
+                namespace,
+                succeeded,
+                failed,
+                success_rate: success_rate(succeeded, failed),
+                last_success: last_success.map(|t| t.to_rfc3339()),
+            }
+        })
+        .collect();
+
+    history.sort_by(|a, b| a.namespace.cmp(&b.namespace).then(a.name.cmp(&b.name)));
+
+    info!("Backup history: {} CronJobs over the last {} days", history.len(), days);
+
+    Ok(history)
+}
+
 fn calculate_job_duration(
     status: Option<&k8s_openapi::api::batch::v1::JobStatus>,
     now: &DateTime<Utc>,
@@ -275,3 +496,77 @@ fn format_duration(duration: chrono::Duration) -> String {
         format!("{}s", seconds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn success_rate_over_finished_jobs() {
+        assert_eq!(success_rate(3, 1), Some(75.0));
+        assert_eq!(success_rate(2, 1), Some(66.7));
+        assert_eq!(success_rate(5, 0), Some(100.0));
+        assert_eq!(success_rate(0, 4), Some(0.0));
+        assert_eq!(success_rate(0, 0), None);
+    }
+
+    #[test]
+    fn label_selector_reaches_list_params() {
+        let filter = BackupFilter {
+            label_selector: Some("app.kubernetes.io/component=backup".to_string()),
+            name_contains: None,
+        };
+        assert_eq!(
+            filter.list_params().label_selector.as_deref(),
+            Some("app.kubernetes.io/component=backup")
+        );
+        assert_eq!(BackupFilter::default().list_params().label_selector, None);
+    }
+
+    #[test]
+    fn name_filter_is_case_insensitive() {
+        let cronjob = |name: &str| CronJob {
+            metadata: kube::api::ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let filter = BackupFilter {
+            label_selector: None,
+            name_contains: Some("Backup".to_string()),
+        };
+        assert!(filter.matches_name(&cronjob("postgres-backup-nightly")));
+        assert!(!filter.matches_name(&cronjob("cert-renewal")));
+        assert!(BackupFilter::default().matches_name(&cronjob("cert-renewal")));
+    }
+
+    #[test]
+    fn job_pod_is_resolved_by_owner_reference() {
+        let pod = |name: &str, owner_kind: &str, owner: &str, created: &str| -> Pod {
+            serde_json::from_value(serde_json::json!({
+                "metadata": {
+                    "name": name,
+                    "creationTimestamp": created,
+                    "ownerReferences": [{
+                        "apiVersion": "batch/v1",
+                        "kind": owner_kind,
+                        "name": owner,
+                        "uid": "uid"
+                    }]
+                }
+            }))
+            .unwrap()
+        };
+        let pods = vec![
+            pod("backup-1-first", "Job", "backup-1", "2024-05-01T02:00:00Z"),
+            pod("backup-1-retry", "Job", "backup-1", "2024-05-01T02:05:00Z"),
+            pod("backup-2-abcde", "Job", "backup-2", "2024-05-01T03:00:00Z"),
+            pod("web-abcde", "ReplicaSet", "backup-1", "2024-05-01T04:00:00Z"),
+        ];
+
+        let latest = latest_job_pod(&pods, "backup-1").unwrap();
+        assert_eq!(latest.metadata.name.as_deref(), Some("backup-1-retry"));
+        assert!(latest_job_pod(&pods, "backup-3").is_none());
+    }
+}
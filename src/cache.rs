@@ -0,0 +1,175 @@
+//! Short-lived in-memory caches for expensive reads
+//! The dashboard, the WebSocket poller and the chat all re-read the same
+//! cluster-wide data within a few seconds; a small TTL lets them share one snapshot.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Cache TTL from KUSANAGI_CACHE_TTL_SECS (default 5s, 0 disables caching)
+pub fn cache_ttl() -> Duration {
+    let secs = std::env::var("KUSANAGI_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(5);
+    Duration::from_secs(secs)
+}
+
+/// A single value cached for a fixed time-to-live
+pub struct TtlCache<T> {
+    ttl: Duration,
+    entry: RwLock<Option<(Instant, Arc<T>)>>,
+}
+
+impl<T> TtlCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: RwLock::new(None),
+        }
+    }
+
+    /// Return the cached value if still fresh, otherwise fetch and store a new one.
+    /// Concurrent callers that miss wait for a single fetch instead of each issuing their own.
+    pub async fn get_or_fetch<F, Fut, E>(&self, fetch: F) -> Result<Arc<T>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if self.ttl.is_zero() {
+            return fetch().await.map(Arc::new);
+        }
+
+        if let Some(value) = self.fresh(self.entry.read().await.as_ref()) {
+            return Ok(value);
+        }
+
+        let mut entry = self.entry.write().await;
+        // Another caller may have refreshed the value while we waited for the lock
+        if let Some(value) = self.fresh(entry.as_ref()) {
+            return Ok(value);
+        }
+
+        let value = Arc::new(fetch().await?);
+        *entry = Some((Instant::now(), value.clone()));
+        Ok(value)
+    }
+
+    fn fresh(&self, entry: Option<&(Instant, Arc<T>)>) -> Option<Arc<T>> {
+        entry
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// Values cached per key for a fixed time-to-live
+pub struct TtlMap<V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, V)>>,
+}
+
+impl<V: Clone> TtlMap<V> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cached value for `key` if still fresh (always None when the TTL is zero)
+    pub fn get(&self, key: &str) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Store a value, dropping expired entries so the map stays small
+    pub fn insert(&self, key: &str, value: V) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        entries.insert(key.to_string(), (Instant::now(), value));
+    }
+
+    /// Forget `key`, so the next `get` misses
+    pub fn remove(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+
+    /// Remove `key` and return its value if it was still fresh
+    pub fn take(&self, key: &str) -> Option<V> {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn counted_fetch(calls: &AtomicUsize) -> Result<usize, String> {
+        Ok(calls.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    #[tokio::test]
+    async fn calls_within_ttl_fetch_once() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+
+        let first = cache.get_or_fetch(|| counted_fetch(&calls)).await.unwrap();
+        let second = cache.get_or_fetch(|| counted_fetch(&calls)).await.unwrap();
+        assert_eq!((*first, *second), (1, 1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_or_disabled_cache_fetches_again() {
+        let calls = AtomicUsize::new(0);
+        let cache = TtlCache::new(Duration::from_millis(20));
+        cache.get_or_fetch(|| counted_fetch(&calls)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(*cache.get_or_fetch(|| counted_fetch(&calls)).await.unwrap(), 2);
+
+        let disabled = TtlCache::new(Duration::ZERO);
+        disabled.get_or_fetch(|| counted_fetch(&calls)).await.unwrap();
+        disabled.get_or_fetch(|| counted_fetch(&calls)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_cached() {
+        let cache: TtlCache<usize> = TtlCache::new(Duration::from_secs(60));
+        assert!(cache.get_or_fetch(|| async { Err::<usize, _>("down") }).await.is_err());
+        assert_eq!(*cache.get_or_fetch(|| async { Ok::<_, String>(7) }).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn concurrent_consumers_share_one_list() {
+        // Nodes, apps and storage reading pods at the same time during a report
+        let cache = TtlCache::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+        let slow_list = || async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            counted_fetch(&calls).await
+        };
+
+        let (nodes, apps, storage) = tokio::join!(
+            cache.get_or_fetch(slow_list),
+            cache.get_or_fetch(slow_list),
+            cache.get_or_fetch(slow_list)
+        );
+        assert_eq!((*nodes.unwrap(), *apps.unwrap(), *storage.unwrap()), (1, 1, 1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
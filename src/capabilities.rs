@@ -0,0 +1,168 @@
+//! Optional cluster add-ons detected through API discovery
+//! Endpoints that depend on metrics-server, ArgoCD or Cilium CRDs can check here
+//! and explain what is missing instead of surfacing a raw API error.
+
+use kube::Client;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::error::KusanagiError;
+
+/// API group served by metrics-server
+const METRICS_GROUP: &str = "metrics.k8s.io";
+const ARGOCD_GROUP: &str = "argoproj.io";
+const CILIUM_GROUP: &str = "cilium.io";
+
+/// Cache key of the default (in-cluster or kubeconfig) cluster
+const DEFAULT_CLUSTER: &str = "";
+
+lazy_static::lazy_static! {
+    /// API groups served by each cluster, discovered once per cluster name;
+    /// a failed discovery is retried next call
+    static ref API_GROUPS: Mutex<HashMap<String, Arc<OnceCell<Vec<String>>>>> = Mutex::new(HashMap::new());
+}
+
+/// Optional components available in the cluster
+#[derive(Clone, Debug, Serialize)]
+pub struct Capabilities {
+    pub metrics_server: bool,
+    pub argocd: bool,
+    pub cilium: bool,
+}
+
+async fn api_groups(cluster: Option<&str>, client: &Client) -> Result<Vec<String>, kube::Error> {
+    let cell = API_GROUPS
+        .lock()
+        .unwrap()
+        .entry(cluster.unwrap_or(DEFAULT_CLUSTER).to_string())
+        .or_default()
+        .clone();
+    let groups = cell
+        .get_or_try_init(|| async {
+            let groups: Vec<String> = client
+                .list_api_groups()
+                .await?
+                .groups
+                .into_iter()
+                .map(|g| g.name)
+                .collect();
+            info!("Discovered {} API groups on {}", groups.len(), cluster.unwrap_or("the default cluster"));
+            Ok::<_, kube::Error>(groups)
+        })
+        .await?;
+    Ok(groups.clone())
+}
+
+/// Whether `group` is among the discovered API groups
+pub fn group_served(groups: &[String], group: &str) -> bool {
+    groups.iter().any(|g| g == group)
+}
+
+async fn has_group(cluster: Option<&str>, client: &Client, group: &str) -> bool {
+    match api_groups(cluster, client).await {
+        Ok(groups) => group_served(&groups, group),
+        Err(e) => {
+            warn!("API discovery failed, assuming {} is not installed: {}", group, e);
+            false
+        }
+    }
+}
+
+/// Whether metrics-server (the `metrics.k8s.io` API) is installed
+pub async fn has_metrics_server(cluster: Option<&str>, client: &Client) -> bool {
+    has_group(cluster, client, METRICS_GROUP).await
+}
+
+/// Fail with a 501 explaining that metrics-server is missing, for endpoints that need it
+pub async fn require_metrics_server(cluster: Option<&str>, client: &Client) -> Result<(), KusanagiError> {
+    if has_metrics_server(cluster, client).await {
+        return Ok(());
+    }
+    Err(KusanagiError::NotImplemented(format!(
+        "metrics-server not installed: the {} API is not served by this cluster",
+        METRICS_GROUP
+    )))
+}
+
+/// Detect the optional components the dashboard can use
+pub async fn get_capabilities(cluster: Option<&str>, client: &Client) -> Capabilities {
+    Capabilities {
+        metrics_server: has_metrics_server(cluster, client).await,
+        argocd: has_group(cluster, client, ARGOCD_GROUP).await,
+        cilium: has_group(cluster, client, CILIUM_GROUP).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// API server whose discovery lists `groups`
+    async fn mock_api_server(groups: &[&str]) -> Client {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let groups: Vec<serde_json::Value> = groups
+            .iter()
+            .map(|name| {
+                serde_json::json!({
+                    "name": name,
+                    "versions": [{ "groupVersion": format!("{}/v1beta1", name), "version": "v1beta1" }]
+                })
+            })
+            .collect();
+        let body = serde_json::json!({ "kind": "APIGroupList", "apiVersion": "v1", "groups": groups }).to_string();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        Client::try_from(kube::Config::new(url.parse().unwrap())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn metrics_server_is_detected_from_discovered_groups() {
+        let client = mock_api_server(&["apps", METRICS_GROUP, ARGOCD_GROUP]).await;
+
+        assert!(has_metrics_server(Some("with-metrics"), &client).await);
+        assert!(require_metrics_server(Some("with-metrics"), &client).await.is_ok());
+        let capabilities = get_capabilities(Some("with-metrics"), &client).await;
+        assert!(capabilities.metrics_server);
+        assert!(capabilities.argocd);
+        assert!(!capabilities.cilium);
+    }
+
+    #[tokio::test]
+    async fn discovery_is_cached_per_cluster() {
+        let bare = mock_api_server(&["apps"]).await;
+        let full = mock_api_server(&["apps", METRICS_GROUP]).await;
+
+        assert!(!has_metrics_server(Some("bare"), &bare).await);
+        assert!(has_metrics_server(Some("full"), &full).await);
+        // The first answer for a cluster sticks, whatever client asks next
+        assert!(!has_metrics_server(Some("bare"), &full).await);
+
+        let err = require_metrics_server(Some("bare"), &bare).await.unwrap_err();
+        assert_eq!(err.status_code(), actix_web::http::StatusCode::NOT_IMPLEMENTED);
+        assert!(err.message().starts_with("metrics-server not installed"), "{}", err);
+    }
+
+    #[test]
+    fn group_must_match_exactly() {
+        let groups = vec!["metrics.k8s.io".to_string(), "apps".to_string()];
+        assert!(group_served(&groups, METRICS_GROUP));
+        assert!(!group_served(&groups, "custom.metrics.k8s.io"));
+        assert!(!group_served(&[], METRICS_GROUP));
+    }
+}
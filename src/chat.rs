@@ -1,12 +1,57 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
 use tracing::{info, warn};
 
-use crate::{argocd, cluster, events, nodes, backups, chat_storage, mcp};
+use crate::cache::TtlMap;
+use crate::{alertmanager, argocd, cluster, events, ingress, nodes, pods, backups, chat_storage, http_util, mcp, prometheus, services, storage};
 
 /// Chat message request
 #[derive(Clone, Debug, Deserialize)]
 pub struct ChatRequest {
     pub message: String,
+    /// An action previously proposed by the assistant that the user has confirmed;
+    /// only runs when it matches the action proposed to the same session
+    #[serde(default)]
+    pub confirm: Option<ToolAction>,
+    /// Conversation ID; the server remembers recent turns per session
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Recent (user, assistant) turns supplied by the client, used instead of the stored session
+    #[serde(default)]
+    pub history: Option<Vec<(String, String)>>,
+    /// Assistant language ("fr" or "en"), defaults to KUSANAGI_CHAT_LANG then French
+    #[serde(default)]
+    pub lang: Option<String>,
+}
+
+/// Language used for the assistant prompts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatLang {
+    Fr,
+    En,
+}
+
+impl ChatLang {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        if value.starts_with("fr") {
+            Some(ChatLang::Fr)
+        } else if value.starts_with("en") {
+            Some(ChatLang::En)
+        } else {
+            None
+        }
+    }
+
+    /// Requested language, else KUSANAGI_CHAT_LANG, else French
+    fn resolve(requested: Option<&str>) -> Self {
+        requested
+            .and_then(ChatLang::parse)
+            .or_else(|| std::env::var("KUSANAGI_CHAT_LANG").ok().as_deref().and_then(ChatLang::parse))
+            .unwrap_or(ChatLang::Fr)
+    }
 }
 
 /// Chat response
@@ -17,8 +62,25 @@ pub struct ChatResponse {
     pub data: Option<serde_json::Value>,
 }
 
+/// Conversation memory limits
+const MAX_HISTORY_TURNS: usize = 6;
+const MAX_SESSIONS: usize = 100;
+const MAX_TURN_CHARS: usize = 1000;
+
+/// Recent (user, assistant) turns of one session, with the time it was last used
+type SessionTurns = (Instant, VecDeque<(String, String)>);
+
+/// How long a proposed action can be confirmed
+const PENDING_ACTION_TTL: std::time::Duration = std::time::Duration::from_secs(600);
+
+lazy_static::lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<String, SessionTurns>> = Mutex::new(HashMap::new());
+    /// The last action proposed to each session, waiting for the user's confirmation
+    static ref PENDING_ACTIONS: TtlMap<ToolAction> = TtlMap::new(PENDING_ACTION_TTL);
+}
+
 /// Ollama configuration
-const OLLAMA_URL: &str = "http://192.168.0.52:11434/api/generate";
+pub const OLLAMA_URL: &str = "http://192.168.0.52:11434/api/generate";
 const OLLAMA_MODEL: &str = "ministral-3:14b";
 
 /// Ollama request structure
@@ -48,30 +110,61 @@ const HELP_TEXT: &str = r#"**Kusanagi Chat Commands** 🤖
 - `/backups` - Show backup jobs status
 - `/namespaces` - Show namespace count
 - `/pvcs` - Show PVC summary
+- `/storage` - Show top PVCs by usage
+- `/services` - Show LoadBalancer services
+- `/ingress` - Show ingress hosts
+- `/alerts` - Show firing alerts, most severe first
 
 **MCP Commands (AI-Powered):**
 - `/k8s` - Show Kubernetes resources via MCP
 - `/cilium` - Show Cilium network policies
+- `/policies [namespace]` - Show Cilium network policies, optionally for one namespace
 - `/trivy` - Show security vulnerabilities
-- `/query <sql>` - Execute Steampipe SQL query
+- `/sql <query>` - Run a read-only Steampipe query (SELECT only)
+- `/query <sql>` - Alias for `/sql`
+
+**Actions (AI):**
+Ask me to sync an ArgoCD app, force delete a pod or silence an alert.
+Destructive actions need your confirmation first.
 
 Or just ask me anything in natural language! I'm powered by Ollama AI."#;
 
 /// Process chat message and return response
 pub async fn process_message(request: ChatRequest) -> ChatResponse {
     let message = request.message.trim();
-    let message_lower = message.to_lowercase();
     
     info!("Chat message received: {}", message);
 
     // Handle commands
-    if message_lower.starts_with('/') {
-        return handle_command(&message_lower).await;
+    if message.starts_with('/') {
+        let response = handle_command(message).await;
+        if let Some(session_id) = &request.session_id {
+            remember_turn(session_id, message, &response.response);
+        }
+        return response;
     }
 
-    // Handle natural language queries with Ollama
-    // Handle natural language queries with Ollama
-    let response = handle_query_with_ollama(message).await;
+    let history = match (&request.history, &request.session_id) {
+        (Some(history), _) => recent_turns(history),
+        (None, Some(session_id)) => session_history(session_id),
+        (None, None) => Vec::new(),
+    };
+
+    let lang = ChatLang::resolve(request.lang.as_deref());
+
+    // Run an action the user confirmed, otherwise handle natural language queries with Ollama
+    let session_id = request.session_id.as_deref();
+    let response = match &request.confirm {
+        Some(confirmed) => match take_pending_action(&PENDING_ACTIONS, session_id, confirmed) {
+            Some(action) => run_action(message, &action, lang).await,
+            None => unknown_confirmation_response(),
+        },
+        None => handle_query_with_ollama(message, &history, lang, session_id).await,
+    };
+
+    if let Some(session_id) = &request.session_id {
+        remember_turn(session_id, message, &response.response);
+    }
 
     // Store chat in background (fire and forget for now, or spawn)
     let user_msg = message.to_string();
@@ -87,7 +180,10 @@ pub async fn process_message(request: ChatRequest) -> ChatResponse {
     response
 }
 
-async fn handle_command(command: &str) -> ChatResponse {
+async fn handle_command(message: &str) -> ChatResponse {
+    let lower = message.to_lowercase();
+    let command = lower.as_str();
+
     match command {
         "/help" => ChatResponse {
             response: HELP_TEXT.to_string(),
@@ -103,13 +199,22 @@ async fn handle_command(command: &str) -> ChatResponse {
         "/backups" => get_backups_summary().await,
         "/namespaces" => get_namespaces_summary().await,
         "/pvcs" => get_pvcs_summary().await,
+        "/storage" => get_storage_summary().await,
+        "/services" => get_services_summary().await,
+        "/ingress" => get_ingress_summary().await,
+        "/alerts" => get_alerts_summary().await,
         
         // MCP Commands
         "/k8s" => get_mcp_k8s_resources().await,
-        "/cilium" => get_mcp_cilium_policies().await,
+        "/cilium" => get_mcp_cilium_policies(None).await,
+        cmd if cmd == "/policies" || cmd.starts_with("/policies ") => {
+            let namespace = cmd.strip_prefix("/policies").map(str::trim).filter(|ns| !ns.is_empty());
+            get_mcp_cilium_policies(namespace).await
+        }
         "/trivy" => get_mcp_trivy_vulns().await,
-        cmd if cmd.starts_with("/query ") => {
-            let sql = cmd.strip_prefix("/query ").unwrap_or("");
+        // SQL keeps its original case so string literals still match
+        cmd if cmd.starts_with("/query ") || cmd == "/sql" || cmd.starts_with("/sql ") => {
+            let sql = message.split_once(' ').map(|(_, sql)| sql).unwrap_or("");
             get_steampipe_query(sql).await
         }
         
@@ -122,28 +227,25 @@ async fn handle_command(command: &str) -> ChatResponse {
 }
 
 /// Query Ollama with context about the Kubernetes cluster
-async fn handle_query_with_ollama(query: &str) -> ChatResponse {
+/// An action in the reply is remembered for `session_id` and only proposed, never run
+async fn handle_query_with_ollama(
+    query: &str,
+    history: &[(String, String)],
+    lang: ChatLang,
+    session_id: Option<&str>,
+) -> ChatResponse {
     // Build context from cluster state
     let context = build_cluster_context().await;
-    
-    let system_prompt = format!(
-        r#"Tu es Kusanagi, un assistant IA pour la gestion d'un cluster Kubernetes K3s. 
-Tu es inspiré par Ghost in the Shell et tu as un style cyberpunk.
-Voici l'état actuel du cluster:
-
-{}
-
-L'utilisateur te pose une question. Réponds de manière concise et utile.
-Si la question concerne l'état du cluster, utilise les données ci-dessus.
-Question: {}"#,
-        context, query
-    );
+    let system_prompt = build_prompt(&context, history, query, lang);
 
     match query_ollama(&system_prompt).await {
-        Ok(response) => ChatResponse {
-            response,
-            response_type: "ai".to_string(),
-            data: None,
+        Ok(response) => match parse_action(&response) {
+            Some(action) => propose_action(&PENDING_ACTIONS, session_id, action),
+            None => ChatResponse {
+                response,
+                response_type: "ai".to_string(),
+                data: None,
+            },
         },
         Err(e) => {
             warn!("Ollama query failed: {}", e);
@@ -160,54 +262,401 @@ Question: {}"#,
     }
 }
 
+/// Build the Ollama prompt from cluster context, recent turns and the question
+fn build_prompt(context: &str, history: &[(String, String)], query: &str, lang: ChatLang) -> String {
+    let (user_label, recent_label) = match lang {
+        ChatLang::Fr => ("Utilisateur", "Conversation récente"),
+        ChatLang::En => ("User", "Recent conversation"),
+    };
+
+    let conversation = if history.is_empty() {
+        String::new()
+    } else {
+        let turns: Vec<String> = history
+            .iter()
+            .map(|(user, assistant)| format!("{}: {}\nKusanagi: {}", user_label, user, assistant))
+            .collect();
+        format!("{}:\n{}\n\n", recent_label, turns.join("\n"))
+    };
+
+    match lang {
+        ChatLang::Fr => format!(
+            r#"Tu es Kusanagi, un assistant IA pour la gestion d'un cluster Kubernetes K3s. 
+Tu es inspiré par Ghost in the Shell et tu as un style cyberpunk.
+Voici l'état actuel du cluster:
+
+{}
+
+{}
+
+{}L'utilisateur te pose une question. Réponds de manière concise et utile.
+Si la question concerne l'état du cluster, utilise les données ci-dessus.
+Question: {}"#,
+            context, TOOLS_PROMPT_FR, conversation, query
+        ),
+        ChatLang::En => format!(
+            r#"You are Kusanagi, an AI assistant managing a K3s Kubernetes cluster.
+You are inspired by Ghost in the Shell and speak with a cyberpunk style.
+Here is the current state of the cluster:
+
+{}
+
+{}
+
+{}The user is asking you a question. Answer concisely and helpfully, in English.
+If the question is about the cluster state, use the data above.
+Question: {}"#,
+            context, TOOLS_PROMPT_EN, conversation, query
+        ),
+    }
+}
+
+// ============================================================================
+// Conversation Memory
+// ============================================================================
+
+/// Keep only the last MAX_HISTORY_TURNS turns, each truncated to bound the prompt size
+fn recent_turns(history: &[(String, String)]) -> Vec<(String, String)> {
+    let skip = history.len().saturating_sub(MAX_HISTORY_TURNS);
+    history
+        .iter()
+        .skip(skip)
+        .map(|(user, assistant)| (truncate_turn(user), truncate_turn(assistant)))
+        .collect()
+}
+
+fn truncate_turn(text: &str) -> String {
+    if text.chars().count() > MAX_TURN_CHARS {
+        format!("{}…", text.chars().take(MAX_TURN_CHARS).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Turns stored for a session, oldest first
+fn session_history(session_id: &str) -> Vec<(String, String)> {
+    let sessions = SESSIONS.lock().unwrap();
+    sessions
+        .get(session_id)
+        .map(|(_, turns)| turns.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Append a turn to a session, evicting the least recently used session when full
+fn remember_turn(session_id: &str, user: &str, assistant: &str) {
+    let mut sessions = SESSIONS.lock().unwrap();
+
+    if !sessions.contains_key(session_id) && sessions.len() >= MAX_SESSIONS {
+        let oldest = sessions
+            .iter()
+            .min_by_key(|(_, (last_used, _))| *last_used)
+            .map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            sessions.remove(&oldest);
+        }
+    }
+
+    let (last_used, turns) = sessions
+        .entry(session_id.to_string())
+        .or_insert_with(|| (Instant::now(), VecDeque::new()));
+    *last_used = Instant::now();
+    turns.push_back((truncate_turn(user), truncate_turn(assistant)));
+    while turns.len() > MAX_HISTORY_TURNS {
+        turns.pop_front();
+    }
+}
+
+// ============================================================================
+// AI Tool Calling
+// ============================================================================
+
+/// Tool descriptions appended to the Ollama prompt
+const TOOLS_PROMPT_FR: &str = r#"Tu peux agir sur le cluster avec ces outils:
+- sync_app: synchronise une application ArgoCD. args: {"app_name": "<nom>"}
+- force_delete_pod: supprime de force un pod bloqué. args: {"namespace": "<ns>", "pod_name": "<pod>"}
+- silence_alert: met une alerte Alertmanager en silence. args: {"alertname": "<nom>", "hours": <1-24>}
+
+Si l'utilisateur demande une de ces actions, réponds UNIQUEMENT avec un objet JSON, par exemple:
+{"action": "sync_app", "args": {"app_name": "grafana"}}
+Sinon, réponds normalement en texte."#;
+
+const TOOLS_PROMPT_EN: &str = r#"You can act on the cluster with these tools:
+- sync_app: sync an ArgoCD application. args: {"app_name": "<name>"}
+- force_delete_pod: force delete a stuck pod. args: {"namespace": "<ns>", "pod_name": "<pod>"}
+- silence_alert: silence an Alertmanager alert. args: {"alertname": "<name>", "hours": <1-24>}
+
+If the user asks for one of these actions, reply ONLY with a JSON object, for example:
+{"action": "sync_app", "args": {"app_name": "grafana"}}
+Otherwise, answer normally in text."#;
+
+fn default_silence_hours() -> i64 {
+    1
+}
+
+/// An action the model can ask Kusanagi to execute
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "args", rename_all = "snake_case")]
+pub enum ToolAction {
+    SyncApp {
+        app_name: String,
+    },
+    ForceDeletePod {
+        namespace: String,
+        pod_name: String,
+    },
+    SilenceAlert {
+        alertname: String,
+        #[serde(default = "default_silence_hours")]
+        hours: i64,
+    },
+}
+
+/// Every action changes the cluster (a sync is a real, not dry-run, sync),
+/// so each one is proposed to the user and only runs once they confirm it
+impl ToolAction {
+    fn describe(&self) -> String {
+        match self {
+            ToolAction::SyncApp { app_name } => format!("Sync ArgoCD application `{}`", app_name),
+            ToolAction::ForceDeletePod { namespace, pod_name } => {
+                format!("Force delete pod `{}/{}`", namespace, pod_name)
+            }
+            ToolAction::SilenceAlert { alertname, hours } => {
+                format!("Silence alert `{}` for {}h", alertname, silence_hours(*hours))
+            }
+        }
+    }
+}
+
+fn silence_hours(hours: i64) -> i64 {
+    hours.clamp(1, alertmanager::MAX_SILENCE_HOURS)
+}
+
+/// Extract a tool call from the model output: either the whole reply is a JSON object,
+/// or it contains a fenced code block holding one. Braces in prose are never parsed.
+fn parse_action(response: &str) -> Option<ToolAction> {
+    let response = response.trim();
+    let candidate = if response.starts_with('{') {
+        response
+    } else {
+        fenced_block(response)?
+    };
+    serde_json::from_str(candidate).ok()
+}
+
+/// Contents of the first ``` fence, without its `json` language tag
+fn fenced_block(text: &str) -> Option<&str> {
+    let start = text.find("```")? + 3;
+    let rest = &text[start..];
+    let rest = rest
+        .strip_prefix("json")
+        .or_else(|| rest.strip_prefix("JSON"))
+        .unwrap_or(rest);
+    let end = rest.find("```")?;
+    Some(rest[..end].trim())
+}
+
+/// Remember `action` as the one `session_id` may confirm and ask the user to confirm it;
+/// without a session there is nothing to bind the confirmation to, so it can't be proposed
+fn propose_action(pending: &TtlMap<ToolAction>, session_id: Option<&str>, action: ToolAction) -> ChatResponse {
+    let Some(session_id) = session_id else {
+        return ChatResponse {
+            response: format!(
+                "⚠️ I can't propose **{}** without a `session_id` to confirm it in.",
+                action.describe()
+            ),
+            response_type: "error".to_string(),
+            data: None,
+        };
+    };
+    pending.insert(session_id, action.clone());
+    confirmation_response(action)
+}
+
+/// The action pending for `session_id` if it is the one the user confirmed
+/// The pending action is consumed either way, so a confirmation can't be replayed
+fn take_pending_action(
+    pending: &TtlMap<ToolAction>,
+    session_id: Option<&str>,
+    confirmed: &ToolAction,
+) -> Option<ToolAction> {
+    pending.take(session_id?).filter(|action| action == confirmed)
+}
+
+fn unknown_confirmation_response() -> ChatResponse {
+    ChatResponse {
+        response: "⚠️ There is no pending action matching this confirmation; ask again.".to_string(),
+        response_type: "error".to_string(),
+        data: None,
+    }
+}
+
+/// Ask the user to confirm an action before running it
+fn confirmation_response(action: ToolAction) -> ChatResponse {
+    let description = action.describe();
+    ChatResponse {
+        response: format!(
+            "## ⚠️ Confirmation Required\n\nI'm about to: **{}**\n\nConfirm to proceed.",
+            description
+        ),
+        response_type: "confirm".to_string(),
+        data: Some(serde_json::json!({
+            "action": action,
+            "description": description
+        })),
+    }
+}
+
+/// Execute an action with the existing handlers
+async fn execute_action(action: &ToolAction) -> Result<String, String> {
+    match action {
+        ToolAction::SyncApp { app_name } => argocd::sync_application(None, app_name, false)
+            .await
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+        ToolAction::ForceDeletePod { namespace, pod_name } => pods::force_delete_pod(namespace, pod_name)
+            .await
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+        ToolAction::SilenceAlert { alertname, hours } => {
+            let hours = silence_hours(*hours);
+            let matchers = [alertmanager::SilenceMatcher {
+                name: "alertname".to_string(),
+                value: alertname.clone(),
+                is_regex: false,
+                is_equal: true,
+            }];
+            alertmanager::create_silence(
+                &matchers,
+                chrono::Duration::hours(hours),
+                "kusanagi-chat",
+                "Silenced from Kusanagi chat",
+            )
+            .await
+            .map(|id| format!("Alert {} silenced for {}h (silence {})", alertname, hours, id))
+        }
+    }
+}
+
+/// Execute an action and let the model phrase the outcome for the user
+async fn run_action(query: &str, action: &ToolAction, lang: ChatLang) -> ChatResponse {
+    info!("Chat executing action: {}", action.describe());
+
+    let (success, outcome) = match execute_action(action).await {
+        Ok(message) => (true, message),
+        Err(e) => {
+            warn!("Chat action failed: {}", e);
+            (false, e)
+        }
+    };
+
+    let prompt = match lang {
+        ChatLang::Fr => format!(
+            r#"Tu es Kusanagi, un assistant IA pour un cluster Kubernetes K3s.
+L'utilisateur a demandé: {}
+Action exécutée: {}
+Résultat ({}): {}
+
+Explique le résultat à l'utilisateur en une ou deux phrases."#,
+            query,
+            action.describe(),
+            if success { "succès" } else { "échec" },
+            outcome
+        ),
+        ChatLang::En => format!(
+            r#"You are Kusanagi, an AI assistant for a K3s Kubernetes cluster.
+The user asked: {}
+Action executed: {}
+Result ({}): {}
+
+Explain the result to the user in one or two sentences."#,
+            query,
+            action.describe(),
+            if success { "success" } else { "failure" },
+            outcome
+        ),
+    };
+
+    let response = match query_ollama(&prompt).await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!("Ollama follow-up failed: {}", e);
+            format!("{}: {}", action.describe(), outcome)
+        }
+    };
+
+    ChatResponse {
+        response,
+        response_type: if success { "action" } else { "error" }.to_string(),
+        data: Some(serde_json::json!({
+            "action": action,
+            "success": success,
+            "result": outcome
+        })),
+    }
+}
+
 /// Build context string from cluster state
 async fn build_cluster_context() -> String {
     let mut context_parts = vec![];
 
-    if let Ok(nodes) = nodes::get_nodes_status().await {
+    if let Ok(nodes) = nodes::get_nodes_status(None).await {
         context_parts.push(format!(
             "Nodes: {} total, {} ready, {} not ready",
             nodes.total_nodes, nodes.ready_nodes, nodes.not_ready_nodes
         ));
     }
 
-    if let Ok(overview) = cluster::get_cluster_overview().await {
+    if let Ok(overview) = cluster::get_cluster_overview(None, None).await {
         context_parts.push(format!(
             "Namespaces: {}, PVCs: {} ({})",
             overview.namespace_count, overview.pvc_count, overview.pvc_total_capacity
         ));
     }
 
-    if let Ok(events) = events::get_events(None).await {
+    if let Ok(events) = events::get_events(None, None).await {
         context_parts.push(format!(
             "Events (1h): {} total, {} warnings",
             events.total_events, events.warning_count
         ));
     }
 
-    if let Ok(argocd) = argocd::get_argocd_status().await {
+    if let Ok(argocd) = argocd::get_argocd_status(None).await {
         context_parts.push(format!(
             "ArgoCD: {}/{} healthy, {} issues",
             argocd.healthy, argocd.total, argocd.apps_with_issues.len()
         ));
     }
 
-    if let Ok(backups) = backups::get_backups_status().await {
+    if let Ok(backups) = backups::get_backups_status(&backups::BackupFilter::from_env()).await {
         context_parts.push(format!(
             "Backups: {} CronJobs, {} active, {} succeeded, {} failed",
             backups.total_cronjobs, backups.active_jobs, backups.succeeded_jobs, backups.failed_jobs
         ));
     }
 
+    if let Ok(metrics) = prometheus::get_cluster_metrics().await {
+        if let Some(line) = metrics_context_line(&metrics) {
+            context_parts.push(line);
+        }
+    }
+
     context_parts.join("\n")
 }
 
+/// One-line utilization summary for the prompt; None when Prometheus is unreachable
+fn metrics_context_line(metrics: &prometheus::PrometheusMetrics) -> Option<String> {
+    if !metrics.available {
+        return None;
+    }
+    Some(format!(
+        "Utilization: CPU {:.0}%, memory {:.0}%, {} alerts firing",
+        metrics.cpu_usage_percent, metrics.memory_usage_percent, metrics.alerts_firing
+    ))
+}
+
 /// Query Ollama API
 async fn query_ollama(prompt: &str) -> Result<String, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let client = http_util::http_client(http_util::timeout_from_env("OLLAMA_TIMEOUT_SECS", 60))?;
 
     let request = OllamaRequest {
         model: OLLAMA_MODEL.to_string(),
@@ -220,7 +669,7 @@ async fn query_ollama(prompt: &str) -> Result<String, String> {
         .json(&request)
         .send()
         .await
-        .map_err(|e| format!("Ollama request failed: {}", e))?;
+        .map_err(|e| format!("Ollama request failed: {}", http_util::describe_error(&e)))?;
 
     if !response.status().is_success() {
         return Err(format!("Ollama returned status: {}", response.status()));
@@ -238,7 +687,7 @@ async fn get_cluster_status() -> ChatResponse {
     let mut status_lines = vec!["## 📊 Cluster Status\n".to_string()];
 
     // Get nodes
-    if let Ok(nodes) = nodes::get_nodes_status().await {
+    if let Ok(nodes) = nodes::get_nodes_status(None).await {
         status_lines.push(format!(
             "**Nodes:** {} total ({} ready, {} not ready)",
             nodes.total_nodes, nodes.ready_nodes, nodes.not_ready_nodes
@@ -246,7 +695,7 @@ async fn get_cluster_status() -> ChatResponse {
     }
 
     // Get cluster overview
-    if let Ok(overview) = cluster::get_cluster_overview().await {
+    if let Ok(overview) = cluster::get_cluster_overview(None, None).await {
         status_lines.push(format!("**Namespaces:** {}", overview.namespace_count));
         status_lines.push(format!(
             "**PVCs:** {} ({})",
@@ -255,7 +704,7 @@ async fn get_cluster_status() -> ChatResponse {
     }
 
     // Get events
-    if let Ok(events) = events::get_events(None).await {
+    if let Ok(events) = events::get_events(None, None).await {
         status_lines.push(format!(
             "**Events (1h):** {} ({} warnings)",
             events.total_events, events.warning_count
@@ -263,7 +712,7 @@ async fn get_cluster_status() -> ChatResponse {
     }
 
     // Get ArgoCD
-    if let Ok(argocd) = argocd::get_argocd_status().await {
+    if let Ok(argocd) = argocd::get_argocd_status(None).await {
         status_lines.push(format!(
             "**ArgoCD:** {}/{} healthy ({} issues)",
             argocd.healthy, argocd.total, argocd.apps_with_issues.len()
@@ -278,7 +727,7 @@ async fn get_cluster_status() -> ChatResponse {
 }
 
 async fn get_nodes_summary() -> ChatResponse {
-    match nodes::get_nodes_status().await {
+    match nodes::get_nodes_status(None).await {
         Ok(nodes) => {
             let mut lines = vec![format!(
                 "## 🖥️ Nodes Status\n\n**Total:** {} ({} ready)\n",
@@ -303,6 +752,9 @@ async fn get_nodes_summary() -> ChatResponse {
                     node.pod_count,
                     error_info
                 ));
+                if let Some(reason) = &node.not_ready_reason {
+                    lines.push(format!("   ↳ {}", reason));
+                }
             }
 
             ChatResponse {
@@ -323,17 +775,9 @@ async fn get_nodes_summary() -> ChatResponse {
 }
 
 async fn get_error_pods() -> ChatResponse {
-    match nodes::get_nodes_status().await {
-        Ok(nodes) => {
-            let mut error_pods: Vec<(String, String)> = vec![];
-            
-            for node in &nodes.nodes {
-                for pod in &node.error_pod_names {
-                    error_pods.push((pod.clone(), node.name.clone()));
-                }
-            }
-
-            if error_pods.is_empty() {
+    match pods::get_pods_status(None, None).await {
+        Ok(status) => {
+            if status.pods_in_error.is_empty() {
                 return ChatResponse {
                     response: "## ✅ No Pods in Error\n\nAll pods are running healthy!".to_string(),
                     response_type: "pods".to_string(),
@@ -342,22 +786,39 @@ async fn get_error_pods() -> ChatResponse {
             }
 
             let mut lines = vec![format!(
-                "## ⚠️ Pods in Error\n\n**Total:** {} pods\n",
-                error_pods.len()
+                "## ⚠️ Pods in Error\n\n**Total:** {} pods | **💥 OOMKilled:** {} | **BestEffort:** {}\n",
+                status.error_pods, status.oom_killed_pods, status.best_effort_error_pods
             )];
 
-            for (pod, node) in error_pods.iter().take(15) {
-                lines.push(format!("- `{}` on **{}**", pod, node));
+            for pod in status.pods_in_error.iter().take(15) {
+                let marker = if pod.oom_killed { "💥 " } else { "" };
+                let qos = if pods::is_best_effort(pod.qos_class.as_deref()) {
+                    " | BestEffort (evicted first)"
+                } else {
+                    ""
+                };
+                lines.push(format!(
+                    "- {}`{}` on **{}** | {}{}",
+                    marker,
+                    pod.name,
+                    pod.node.as_deref().unwrap_or("-"),
+                    pod.reason.as_deref().unwrap_or(&pod.status),
+                    qos
+                ));
             }
 
-            if error_pods.len() > 15 {
-                lines.push(format!("\n... and {} more", error_pods.len() - 15));
+            if status.pods_in_error.len() > 15 {
+                lines.push(format!("\n... and {} more", status.pods_in_error.len() - 15));
             }
 
             ChatResponse {
                 response: lines.join("\n"),
                 response_type: "pods".to_string(),
-                data: Some(serde_json::json!({ "count": error_pods.len() })),
+                data: Some(serde_json::json!({
+                    "count": status.error_pods,
+                    "oom_killed": status.oom_killed_pods,
+                    "best_effort": status.best_effort_error_pods
+                })),
             }
         }
         Err(e) => ChatResponse {
@@ -369,7 +830,7 @@ async fn get_error_pods() -> ChatResponse {
 }
 
 async fn get_warning_events() -> ChatResponse {
-    match events::get_events(None).await {
+    match events::get_events(None, None).await {
         Ok(events) => {
             let warnings: Vec<_> = events.events.iter()
                 .filter(|e| e.event_type == "Warning")
@@ -414,7 +875,7 @@ async fn get_warning_events() -> ChatResponse {
 }
 
 async fn get_argocd_summary() -> ChatResponse {
-    match argocd::get_argocd_status().await {
+    match argocd::get_argocd_status(None).await {
         Ok(status) => {
             let mut lines = vec![format!(
                 "## 🚀 ArgoCD Status\n\n**Total Apps:** {} | **Healthy:** {} | **Issues:** {}\n",
@@ -455,7 +916,7 @@ async fn get_argocd_summary() -> ChatResponse {
 }
 
 async fn get_backups_summary() -> ChatResponse {
-    match backups::get_backups_status().await {
+    match backups::get_backups_status(&backups::BackupFilter::from_env()).await {
         Ok(status) => {
             let mut lines = vec![format!(
                 "## 📦 Backup Jobs Status\n\n**CronJobs:** {} | **Active:** {} | **Succeeded:** {} | **Failed:** {}\n",
@@ -505,7 +966,7 @@ async fn get_backups_summary() -> ChatResponse {
 }
 
 async fn get_namespaces_summary() -> ChatResponse {
-    match cluster::get_cluster_overview().await {
+    match cluster::get_cluster_overview(None, None).await {
         Ok(overview) => {
             let mut lines = vec![format!(
                 "## 📁 Namespaces\n\n**Total:** {}\n",
@@ -535,7 +996,7 @@ async fn get_namespaces_summary() -> ChatResponse {
 }
 
 async fn get_pvcs_summary() -> ChatResponse {
-    match cluster::get_cluster_overview().await {
+    match cluster::get_cluster_overview(None, None).await {
         Ok(overview) => {
             let mut lines = vec![format!(
                 "## 💾 PVC Summary\n\n**Total:** {} | **Capacity:** {}\n",
@@ -571,6 +1032,224 @@ async fn get_pvcs_summary() -> ChatResponse {
     }
 }
 
+async fn get_storage_summary() -> ChatResponse {
+    match storage::get_storage_status(None).await {
+        Ok(status) => storage_summary(&status),
+        Err(e) => ChatResponse {
+            response: format!("Failed to get storage usage: {}", e),
+            response_type: "error".to_string(),
+            data: None,
+        },
+    }
+}
+
+/// Top PVCs by usage, then any ReadWriteOnce volume mounted from several nodes
+fn storage_summary(status: &storage::StorageStatusResponse) -> ChatResponse {
+    let mut lines = vec![format!(
+        "## 💽 Storage Usage\n\n**PVCs:** {} | **Used:** {} / {}\n",
+        status.pvc_count,
+        cluster::format_bytes(status.pvc_total_usage_bytes as i64),
+        cluster::format_bytes(status.pvc_total_capacity_bytes as i64)
+    )];
+
+    // Show top 10 by usage percentage (PVCs without stats last)
+    let mut pvcs: Vec<_> = status.pvcs.iter().collect();
+    pvcs.sort_by(|a, b| {
+        b.usage_percent
+            .unwrap_or(-1.0)
+            .partial_cmp(&a.usage_percent.unwrap_or(-1.0))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    lines.push("**Top PVCs by usage:**\n".to_string());
+    for pvc in pvcs.iter().take(10) {
+        let usage = match pvc.usage_percent {
+            Some(p) => format!("{:.0}%", p),
+            None => "-".to_string(),
+        };
+        let emoji = match pvc.usage_percent {
+            Some(p) if p >= 90.0 => "🔴",
+            Some(p) if p >= 75.0 => "🟡",
+            _ => "🟢",
+        };
+        lines.push(format!(
+            "{} `{}` ({}) | {} of {}",
+            emoji, pvc.name, pvc.namespace, usage, pvc.capacity
+        ));
+    }
+
+    let conflicts: Vec<_> = status.pvcs.iter().filter(|p| p.multi_attach_conflict).collect();
+    if !conflicts.is_empty() {
+        lines.push("\n**⚠️ ReadWriteOnce volumes used from several nodes (Multi-Attach):**\n".to_string());
+        for pvc in &conflicts {
+            lines.push(format!(
+                "- `{}` ({}) | pods: {}",
+                pvc.name,
+                pvc.namespace,
+                pvc.pods_using.join(", ")
+            ));
+        }
+    }
+
+    ChatResponse {
+        response: lines.join("\n"),
+        response_type: "storage".to_string(),
+        data: Some(serde_json::json!({
+            "count": status.pvc_count,
+            "multi_attach_conflicts": conflicts.len(),
+            "used_bytes": status.pvc_total_usage_bytes,
+            "capacity_bytes": status.pvc_total_capacity_bytes
+        })),
+    }
+}
+
+async fn get_services_summary() -> ChatResponse {
+    match services::get_services().await {
+        Ok(services) => services_summary(&services),
+        Err(e) => ChatResponse {
+            response: format!("Failed to get services: {}", e),
+            response_type: "error".to_string(),
+            data: None,
+        },
+    }
+}
+
+/// LoadBalancer services, then services whose selector matches no pod
+fn services_summary(services: &[services::ServiceInfo]) -> ChatResponse {
+    let load_balancers: Vec<_> = services
+        .iter()
+        .filter(|s| s.type_ == "LoadBalancer")
+        .collect();
+
+    let mut lines = vec![format!(
+        "## 🔌 Services\n\n**Total:** {} | **LoadBalancers:** {}\n",
+        services.len(),
+        load_balancers.len()
+    )];
+
+    if load_balancers.is_empty() {
+        lines.push("No LoadBalancer services found.".to_string());
+    } else {
+        for svc in load_balancers.iter().take(15) {
+            lines.push(format!(
+                "- `{}` ({}) | {} | {}",
+                svc.name,
+                svc.namespace,
+                svc.external_ip.as_deref().unwrap_or("pending"),
+                svc.ports
+            ));
+        }
+    }
+
+    let unmatched: Vec<_> = services.iter().filter(|s| s.no_matching_pods).collect();
+    if !unmatched.is_empty() {
+        lines.push(format!("\n### ⚠️ Selector matches no pods ({})", unmatched.len()));
+        for svc in unmatched.iter().take(15) {
+            lines.push(format!("- `{}` ({})", svc.name, svc.namespace));
+        }
+    }
+
+    ChatResponse {
+        response: lines.join("\n"),
+        response_type: "services".to_string(),
+        data: Some(serde_json::json!({
+            "total": services.len(),
+            "load_balancers": load_balancers.len(),
+            "no_matching_pods": unmatched.len()
+        })),
+    }
+}
+
+async fn get_ingress_summary() -> ChatResponse {
+    match ingress::get_ingresses().await {
+        Ok(ingresses) => ingress_summary(&ingresses),
+        Err(e) => ChatResponse {
+            response: format!("Failed to get ingresses: {}", e),
+            response_type: "error".to_string(),
+            data: None,
+        },
+    }
+}
+
+/// Ingress hosts with the health of their backends
+fn ingress_summary(ingresses: &[ingress::IngressInfo]) -> ChatResponse {
+    let mut lines = vec![format!(
+        "## 🌐 Ingresses\n\n**Total:** {}\n",
+        ingresses.len()
+    )];
+
+    for ing in ingresses.iter().take(15) {
+        let healthy = ing.backends.iter().all(|b| b.backend_healthy);
+        lines.push(format!(
+            "- {} `{}` ({}) | {}",
+            if healthy { "✅" } else { "⚠️" },
+            ing.name,
+            ing.namespace,
+            if ing.rules.is_empty() { "-".to_string() } else { ing.rules.join(", ") }
+        ));
+    }
+
+    if ingresses.len() > 15 {
+        lines.push(format!("\n... and {} more", ingresses.len() - 15));
+    }
+
+    ChatResponse {
+        response: lines.join("\n"),
+        response_type: "ingress".to_string(),
+        data: Some(serde_json::json!({ "count": ingresses.len() })),
+    }
+}
+
+async fn get_alerts_summary() -> ChatResponse {
+    match alertmanager::get_active_alerts(None).await {
+        Ok(alerts) => {
+            let mut lines = vec![format!(
+                "## 🚨 Alerts\n\n**Total:** {} ({} critical, {} warning, {} info)\n",
+                alerts.total,
+                alerts.critical.len(),
+                alerts.warning.len(),
+                alerts.info.len()
+            )];
+
+            let ordered = alertmanager::order_alerts(&alerts);
+            for alert in ordered.iter().take(15) {
+                let icon = match alert.severity.as_str() {
+                    "critical" => "🔴",
+                    "warning" => "🟠",
+                    _ => "🔵",
+                };
+                lines.push(format!(
+                    "- {} **{}**{} | {} (since {})",
+                    icon,
+                    alert.name,
+                    alert.namespace.as_deref().map(|ns| format!(" ({})", ns)).unwrap_or_default(),
+                    alert.summary,
+                    alert.started_at.format("%Y-%m-%d %H:%M")
+                ));
+            }
+
+            if ordered.len() > 15 {
+                lines.push(format!("\n... and {} more", ordered.len() - 15));
+            }
+
+            ChatResponse {
+                response: lines.join("\n"),
+                response_type: "alerts".to_string(),
+                data: Some(serde_json::json!({
+                    "critical": alerts.critical.len(),
+                    "warning": alerts.warning.len(),
+                    "info": alerts.info.len()
+                })),
+            }
+        }
+        Err(e) => ChatResponse {
+            response: format!("Failed to get alerts: {}", e),
+            response_type: "error".to_string(),
+            data: None,
+        },
+    }
+}
+
 // ============================================================================
 // MCP Command Handlers
 // ============================================================================
@@ -593,13 +1272,13 @@ async fn get_mcp_k8s_resources() -> ChatResponse {
     }
 }
 
-async fn get_mcp_cilium_policies() -> ChatResponse {
-    match mcp::get_cilium_policies(None).await {
+async fn get_mcp_cilium_policies(namespace: Option<&str>) -> ChatResponse {
+    match mcp::get_cilium_policies(namespace).await {
         Ok(summary) => {
             let response = mcp::format_cilium_policies(&summary);
             ChatResponse {
                 response,
-                response_type: "cilium".to_string(),
+                response_type: if summary.available { "cilium" } else { "error" }.to_string(),
                 data: Some(serde_json::to_value(&summary).unwrap_or_default()),
             }
         }
@@ -632,7 +1311,7 @@ async fn get_mcp_trivy_vulns() -> ChatResponse {
 async fn get_steampipe_query(sql: &str) -> ChatResponse {
     if sql.trim().is_empty() {
         return ChatResponse {
-            response: "Usage: `/query SELECT * FROM ...`\n\nPlease provide a valid SQL query.".to_string(),
+            response: "Usage: `/sql SELECT * FROM ...`\n\nPlease provide a read-only SQL query.".to_string(),
             response_type: "error".to_string(),
             data: None,
         };
@@ -654,3 +1333,183 @@ async fn get_steampipe_query(sql: &str) -> ChatResponse {
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sync(app: &str) -> ToolAction {
+        ToolAction::SyncApp { app_name: app.to_string() }
+    }
+
+    #[test]
+    fn parses_top_level_object() {
+        let reply = r#"  {"action": "sync_app", "args": {"app_name": "grafana"}}  "#;
+        assert_eq!(parse_action(reply), Some(sync("grafana")));
+    }
+
+    #[test]
+    fn parses_fenced_block() {
+        let reply = "Sure, here it is:\n```json\n{\"action\": \"sync_app\", \"args\": {\"app_name\": \"grafana\"}}\n```\nDone.";
+        assert_eq!(parse_action(reply), Some(sync("grafana")));
+        let untagged = "```\n{\"action\": \"silence_alert\", \"args\": {\"alertname\": \"Watchdog\"}}\n```";
+        assert_eq!(
+            parse_action(untagged),
+            Some(ToolAction::SilenceAlert { alertname: "Watchdog".to_string(), hours: 1 })
+        );
+    }
+
+    #[test]
+    fn ignores_braces_in_prose() {
+        let reply = r#"You could run {"action": "sync_app", "args": {"app_name": "grafana"}} but I won't."#;
+        assert_eq!(parse_action(reply), None);
+        let two = r#"{"action": "sync_app", "args": {"app_name": "a"}} {"action": "sync_app", "args": {"app_name": "b"}}"#;
+        assert_eq!(parse_action(two), None);
+        assert_eq!(parse_action("The pod uses a {placeholder} value"), None);
+    }
+
+    #[test]
+    fn proposed_actions_wait_for_confirmation() {
+        let pending = TtlMap::new(std::time::Duration::from_secs(60));
+        for action in [
+            sync("grafana"),
+            ToolAction::ForceDeletePod { namespace: "default".to_string(), pod_name: "web".to_string() },
+            ToolAction::SilenceAlert { alertname: "Watchdog".to_string(), hours: 2 },
+        ] {
+            let response = propose_action(&pending, Some("s1"), action.clone());
+            assert_eq!(response.response_type, "confirm");
+            assert_eq!(pending.get("s1"), Some(action));
+        }
+
+        let response = propose_action(&pending, None, sync("grafana"));
+        assert_eq!(response.response_type, "error");
+    }
+
+    #[test]
+    fn only_the_proposed_action_can_be_confirmed() {
+        let pending = TtlMap::new(std::time::Duration::from_secs(60));
+        propose_action(&pending, Some("s1"), sync("grafana"));
+
+        // Another session, or no session, can't confirm it
+        assert_eq!(take_pending_action(&pending, Some("s2"), &sync("grafana")), None);
+        assert_eq!(take_pending_action(&pending, None, &sync("grafana")), None);
+        assert_eq!(take_pending_action(&pending, Some("s1"), &sync("grafana")), Some(sync("grafana")));
+        // Confirmed once only
+        assert_eq!(take_pending_action(&pending, Some("s1"), &sync("grafana")), None);
+
+        // A different action than the one proposed is rejected and the proposal dropped
+        propose_action(&pending, Some("s1"), sync("grafana"));
+        assert_eq!(take_pending_action(&pending, Some("s1"), &sync("vault")), None);
+        assert_eq!(take_pending_action(&pending, Some("s1"), &sync("grafana")), None);
+    }
+
+    #[test]
+    fn silence_hours_are_clamped() {
+        assert_eq!(silence_hours(0), 1);
+        assert_eq!(silence_hours(1000), alertmanager::MAX_SILENCE_HOURS);
+    }
+
+    fn turns(n: usize) -> Vec<(String, String)> {
+        (1..=n).map(|i| (format!("question {}", i), format!("answer {}", i))).collect()
+    }
+
+    #[test]
+    fn history_appears_in_the_prompt() {
+        let prompt = build_prompt("3 nodes ready", &turns(2), "and now?", ChatLang::En);
+        assert!(prompt.contains("Recent conversation:\nUser: question 1\nKusanagi: answer 1\nUser: question 2"));
+        assert!(prompt.ends_with("Question: and now?"));
+
+        let without = build_prompt("3 nodes ready", &[], "and now?", ChatLang::En);
+        assert!(!without.contains("Recent conversation"));
+    }
+
+    #[test]
+    fn only_recent_truncated_turns_are_kept() {
+        let recent = recent_turns(&turns(MAX_HISTORY_TURNS + 2));
+        assert_eq!(recent.len(), MAX_HISTORY_TURNS);
+        assert_eq!(recent[0].0, "question 3");
+
+        let long = vec![("x".repeat(MAX_TURN_CHARS + 10), "ok".to_string())];
+        assert_eq!(recent_turns(&long)[0].0.chars().count(), MAX_TURN_CHARS + 1);
+    }
+
+    #[test]
+    fn new_commands_return_their_response_type() {
+        let storage = storage::StorageStatusResponse {
+            pvc_count: 0,
+            pvc_total_capacity_bytes: 0,
+            pvc_total_usage_bytes: 0,
+            pvcs: Vec::new(),
+        };
+        assert_eq!(storage_summary(&storage).response_type, "storage");
+
+        let service = services::ServiceInfo {
+            name: "traefik".to_string(),
+            namespace: "kube-system".to_string(),
+            type_: "LoadBalancer".to_string(),
+            cluster_ip: "10.43.0.10".to_string(),
+            external_ip: Some("192.168.0.200".to_string()),
+            ports: "80/TCP".to_string(),
+            selector: Default::default(),
+            matched_pods: 1,
+            no_matching_pods: false,
+            age: "3d".to_string(),
+        };
+        let services = services_summary(&[service]);
+        assert_eq!(services.response_type, "services");
+        assert!(services.response.contains("`traefik` (kube-system) | 192.168.0.200 | 80/TCP"));
+
+        let ingress = ingress::IngressInfo {
+            name: "grafana".to_string(),
+            namespace: "monitoring".to_string(),
+            load_balancer: None,
+            rules: vec!["grafana.example.com".to_string()],
+            backends: Vec::new(),
+            missing_services: Vec::new(),
+            age: "1d".to_string(),
+        };
+        let ingresses = ingress_summary(&[ingress]);
+        assert_eq!(ingresses.response_type, "ingress");
+        assert!(ingresses.response.contains("`grafana` (monitoring) | grafana.example.com"));
+
+        for command in ["/storage", "/services", "/ingress"] {
+            assert!(HELP_TEXT.contains(command), "{} missing from /help", command);
+        }
+    }
+
+    #[test]
+    fn lang_en_yields_the_english_template() {
+        assert_eq!(ChatLang::resolve(Some("en")), ChatLang::En);
+        assert_eq!(ChatLang::resolve(Some("en-US")), ChatLang::En);
+        assert_eq!(ChatLang::resolve(Some("FR")), ChatLang::Fr);
+
+        let english = build_prompt("ctx", &turns(1), "status?", ChatLang::En);
+        assert!(english.starts_with("You are Kusanagi"));
+        assert!(english.contains("User: question 1"));
+        let french = build_prompt("ctx", &turns(1), "status?", ChatLang::Fr);
+        assert!(french.starts_with("Tu es Kusanagi"));
+        assert!(french.contains("Utilisateur: question 1"));
+    }
+
+    #[test]
+    fn context_has_a_cpu_line_when_metrics_are_available() {
+        let mut metrics = prometheus::PrometheusMetrics {
+            cpu_usage_percent: 71.6,
+            memory_usage_percent: 48.2,
+            memory_usage_bytes: 0,
+            pod_count: 120,
+            node_count: 3,
+            container_count: 180,
+            alerts_firing: 2,
+            alerts_pending: 0,
+            available: true,
+        };
+        assert_eq!(
+            metrics_context_line(&metrics).as_deref(),
+            Some("Utilization: CPU 72%, memory 48%, 2 alerts firing")
+        );
+
+        metrics.available = false;
+        assert_eq!(metrics_context_line(&metrics), None);
+    }
+}
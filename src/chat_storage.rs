@@ -1,12 +1,20 @@
-use aws_sdk_s3::{Client, config::Region};
-use aws_config::BehaviorVersion;
-use serde::Serialize;
-use tracing::{info, error};
+use aws_sdk_s3::{Client, config::{Credentials, Region}};
+use aws_config::{timeout::TimeoutConfig, BehaviorVersion};
+use chrono::Datelike;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use tracing::{info, error, warn};
+
+use crate::http_util;
 
 const MINIO_ENDPOINT: &str = "http://192.168.0.170";
 const BUCKET_NAME: &str = "kusanagi-chat-history";
 
-#[derive(Serialize)]
+/// Set once the bucket is known to exist, so later writes skip the check
+static BUCKET_READY: OnceCell<()> = OnceCell::const_new();
+
+#[derive(Serialize, Deserialize)]
 pub struct ChatMessage {
     pub timestamp: String,
     pub user_message: String,
@@ -14,18 +22,134 @@ pub struct ChatMessage {
     pub response_type: String,
 }
 
-pub async fn store_chat_message(user_msg: &str, ai_response: &str, response_type: &str) -> Result<(), String> {
-    let config = aws_config::defaults(BehaviorVersion::latest())
-        .region(Region::new("us-east-1")) // MinIO defaults
+/// Chat messages matching a search, newest first
+#[derive(Serialize)]
+pub struct ChatSearchResults {
+    pub messages: Vec<ChatMessage>,
+    /// Number of stored messages that were read
+    pub scanned: usize,
+    /// True when older messages were not searched because of CHAT_SEARCH_MAX_OBJECTS
+    pub truncated: bool,
+}
+
+/// How many stored messages a search reads at most (CHAT_SEARCH_MAX_OBJECTS, default 500)
+fn search_max_objects() -> usize {
+    std::env::var("CHAT_SEARCH_MAX_OBJECTS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(500)
+}
+
+/// How many stored messages a search fetches at once
+const CHAT_SEARCH_CONCURRENCY: usize = 8;
+
+/// Whether a missing bucket is created on first write (CHAT_AUTO_CREATE_BUCKET, default true)
+fn auto_create_bucket() -> bool {
+    std::env::var("CHAT_AUTO_CREATE_BUCKET")
+        .map(|v| !matches!(v.to_lowercase().as_str(), "0" | "false" | "no"))
+        .unwrap_or(true)
+}
+
+/// Check the bucket exists and create it if missing
+/// A concurrent creator winning the race ("already owned by you") counts as success
+async fn create_bucket_if_missing(client: &Client) -> Result<(), String> {
+    match client.head_bucket().bucket(BUCKET_NAME).send().await {
+        Ok(_) => return Ok(()),
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {}
+        Err(e) => return Err(format!("Failed to check bucket {}: {}", BUCKET_NAME, e)),
+    }
+
+    match client.create_bucket().bucket(BUCKET_NAME).send().await {
+        Ok(_) => {
+            info!("Created chat history bucket {}", BUCKET_NAME);
+            Ok(())
+        }
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_bucket_already_owned_by_you()) => Ok(()),
+        Err(e) => Err(format!("Failed to create bucket {}: {}", BUCKET_NAME, e)),
+    }
+}
+
+/// Make sure the bucket exists before the first write; failures are retried on the next write
+async fn ensure_bucket(client: &Client) -> Result<(), String> {
+    if !auto_create_bucket() {
+        return Ok(());
+    }
+    BUCKET_READY
+        .get_or_try_init(|| create_bucket_if_missing(client))
+        .await
+        .map(|_| ())
+}
+
+/// MinIO connection settings read from the environment
+pub struct MinioSettings {
+    /// MINIO_ACCESS_KEY / MINIO_SECRET_KEY; the default AWS credential chain is used when unset
+    pub credentials: Option<(String, String)>,
+    /// MINIO_REGION (default us-east-1, which MinIO accepts out of the box)
+    pub region: String,
+    /// MINIO_FORCE_PATH_STYLE (default true): MinIO is usually addressed by IP or a
+    /// single hostname, where virtual-hosted bucket URLs don't resolve
+    pub force_path_style: bool,
+}
+
+impl MinioSettings {
+    pub fn from_env() -> Self {
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let credentials = match (env("MINIO_ACCESS_KEY"), env("MINIO_SECRET_KEY")) {
+            (Some(access_key), Some(secret_key)) => Some((access_key, secret_key)),
+            (None, None) => None,
+            _ => {
+                warn!("Only one of MINIO_ACCESS_KEY/MINIO_SECRET_KEY is set; using the default credential chain");
+                None
+            }
+        };
+        Self {
+            credentials,
+            region: env("MINIO_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+            force_path_style: env("MINIO_FORCE_PATH_STYLE")
+                .map(|v| !matches!(v.to_lowercase().as_str(), "0" | "false" | "no"))
+                .unwrap_or(true),
+        }
+    }
+}
+
+/// S3 client config: the shared AWS config with the MinIO settings applied on top
+pub fn s3_config(shared: &aws_config::SdkConfig, settings: &MinioSettings) -> aws_sdk_s3::Config {
+    let mut builder = aws_sdk_s3::config::Builder::from(shared)
+        .region(Region::new(settings.region.clone()))
+        .force_path_style(settings.force_path_style);
+    if let Some((access_key, secret_key)) = &settings.credentials {
+        builder = builder.credentials_provider(Credentials::new(
+            access_key,
+            secret_key,
+            None,
+            None,
+            "kusanagi-minio-env",
+        ));
+    }
+    builder.build()
+}
+
+async fn s3_client() -> Client {
+    let settings = MinioSettings::from_env();
+    let shared = aws_config::defaults(BehaviorVersion::latest())
+        .region(Region::new(settings.region.clone()))
         .endpoint_url(MINIO_ENDPOINT)
+        .timeout_config(
+            TimeoutConfig::builder()
+                .operation_timeout(http_util::timeout_from_env("MINIO_TIMEOUT_SECS", 10))
+                .build(),
+        )
         .load()
         .await;
 
-    let client = Client::new(&config);
+    Client::from_conf(s3_config(&shared, &settings))
+}
+
+pub async fn store_chat_message(user_msg: &str, ai_response: &str, response_type: &str) -> Result<(), String> {
+    let client = s3_client().await;
+    ensure_bucket(&client).await?;
 
-    // Ensure bucket exists (simplified, assuming bucket might exist or we just try to upload)
-    // For robust prod code we might check/create, but for now we assume it exists or we fail.
-    
     let timestamp = chrono::Utc::now().to_rfc3339();
     let message = ChatMessage {
         timestamp: timestamp.clone(),
@@ -51,3 +175,317 @@ pub async fn store_chat_message(user_msg: &str, ai_response: &str, response_type
     info!("Stored chat message to S3: {}", key);
     Ok(())
 }
+
+/// Case-insensitive substring match on the user message or the AI response
+/// `query` must already be lowercased
+pub fn message_matches(message: &ChatMessage, query: &str) -> bool {
+    message.user_message.to_lowercase().contains(query)
+        || message.ai_response.to_lowercase().contains(query)
+}
+
+/// Chat object keys under one prefix
+async fn list_chat_keys(client: &Client, prefix: &str) -> Result<Vec<String>, String> {
+    let mut keys = Vec::new();
+    let mut pages = client
+        .list_objects_v2()
+        .bucket(BUCKET_NAME)
+        .prefix(prefix)
+        .into_paginator()
+        .send();
+
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|e| format!("Failed to list chat history: {}", e))?;
+        keys.extend(page.contents().iter().filter_map(|o| o.key().map(str::to_string)));
+    }
+    Ok(keys)
+}
+
+/// The oldest chat key; S3 lists keys in ascending order, so it's the first one
+async fn oldest_chat_key(client: &Client) -> Result<Option<String>, String> {
+    let page = client
+        .list_objects_v2()
+        .bucket(BUCKET_NAME)
+        .prefix("chat-")
+        .max_keys(1)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to list chat history: {}", e))?;
+    Ok(page.contents().first().and_then(|o| o.key().map(str::to_string)))
+}
+
+/// Year and month of a `chat-<RFC 3339 timestamp>.json` key
+fn key_month(key: &str) -> Option<(i32, u32)> {
+    let date = key.strip_prefix("chat-")?;
+    let year = date.get(..4)?.parse().ok()?;
+    let month = date.get(5..7)?.parse().ok().filter(|m| (1..=12).contains(m))?;
+    Some((year, month))
+}
+
+/// Key prefixes for each month from `newest` back to `oldest`, e.g. "chat-2024-03"
+fn month_prefixes(newest: (i32, u32), oldest: (i32, u32)) -> Vec<String> {
+    let mut prefixes = Vec::new();
+    let (mut year, mut month) = newest;
+    while (year, month) >= oldest {
+        prefixes.push(format!("chat-{:04}-{:02}", year, month));
+        if month == 1 {
+            year -= 1;
+            month = 12;
+        } else {
+            month -= 1;
+        }
+    }
+    prefixes
+}
+
+/// The newest chat keys, newest first, listing at most `max_objects` of them (plus the rest of the
+/// last month listed); the bool is true when older keys were left out
+/// Keys embed an RFC 3339 timestamp, so listing month by month from now backwards reaches the
+/// newest messages without paging through the whole bucket
+async fn newest_chat_keys(client: &Client, max_objects: usize) -> Result<(Vec<String>, bool), String> {
+    let Some(oldest) = oldest_chat_key(client).await? else {
+        return Ok((Vec::new(), false));
+    };
+    let now = chrono::Utc::now();
+    let newest = (now.year(), now.month());
+    let oldest = key_month(&oldest).unwrap_or(newest);
+
+    let prefixes = month_prefixes(newest, oldest);
+    let mut keys = Vec::new();
+    let mut listed_months = 0;
+    for prefix in &prefixes {
+        if keys.len() >= max_objects {
+            break;
+        }
+        let mut month_keys = list_chat_keys(client, prefix).await?;
+        month_keys.sort_unstable_by(|a, b| b.cmp(a));
+        keys.extend(month_keys);
+        listed_months += 1;
+    }
+
+    let truncated = keys.len() > max_objects || listed_months < prefixes.len();
+    keys.truncate(max_objects);
+    Ok((keys, truncated))
+}
+
+async fn fetch_chat_message(client: &Client, key: &str) -> Result<ChatMessage, String> {
+    let object = client
+        .get_object()
+        .bucket(BUCKET_NAME)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch {}: {}", key, e))?;
+    let bytes = object
+        .body
+        .collect()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", key, e))?
+        .into_bytes();
+    serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse {}: {}", key, e))
+}
+
+/// Search stored chat messages for `query` (case-insensitive), newest first
+/// Only the newest CHAT_SEARCH_MAX_OBJECTS messages are read; `truncated` says when older ones were skipped
+pub async fn search_chat_messages(query: &str, limit: usize) -> Result<ChatSearchResults, String> {
+    let client = s3_client().await;
+    let query = query.to_lowercase();
+
+    let (keys, truncated) = newest_chat_keys(&client, search_max_objects()).await?;
+
+    // Fetch a few objects at a time, in key order, and stop once `limit` matches are found
+    let mut fetches = futures::stream::iter(&keys)
+        .map(|key| fetch_chat_message(&client, key))
+        .buffered(CHAT_SEARCH_CONCURRENCY);
+
+    let mut messages = Vec::new();
+    let mut scanned = 0;
+    while messages.len() < limit {
+        let Some(fetched) = fetches.next().await else {
+            break;
+        };
+        scanned += 1;
+        match fetched {
+            Ok(message) if message_matches(&message, &query) => messages.push(message),
+            Ok(_) => {}
+            Err(e) => warn!("Skipping chat history object: {}", e),
+        }
+    }
+
+    Ok(ChatSearchResults {
+        messages,
+        scanned,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    /// Request lines seen by the mock endpoint, with their Authorization header
+    type Seen = Arc<Mutex<Vec<(String, String)>>>;
+
+    /// A minimal S3 endpoint on localhost: answers each request with `respond(request line)`
+    /// and records the requests it saw
+    async fn mock_s3_endpoint(respond: fn(&str) -> (u16, &'static str)) -> (String, Seen) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let seen = seen.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    loop {
+                        let mut request_line = String::new();
+                        if stream.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let mut content_length = 0;
+                        let mut authorization = String::new();
+                        loop {
+                            let mut header = String::new();
+                            stream.read_line(&mut header).await.unwrap();
+                            if header.trim().is_empty() {
+                                break;
+                            }
+                            if let Some((name, value)) = header.split_once(':') {
+                                if name.eq_ignore_ascii_case("content-length") {
+                                    content_length = value.trim().parse().unwrap();
+                                } else if name.eq_ignore_ascii_case("authorization") {
+                                    authorization = value.trim().to_string();
+                                }
+                            }
+                        }
+                        let mut body = vec![0; content_length];
+                        stream.read_exact(&mut body).await.unwrap();
+
+                        // Some SDK versions address the bucket as "/bucket/", others as "/bucket"
+                        let request = request_line.split_whitespace().take(2).collect::<Vec<_>>().join(" ");
+                        let request = request.trim_end_matches('/').to_string();
+                        let (status, body) = respond(&request);
+                        seen.lock().unwrap().push((request, authorization));
+                        let response = format!(
+                            "HTTP/1.1 {} Mock\r\nContent-Type: application/xml\r\nContent-Length: {}\r\n\r\n{}",
+                            status,
+                            body.len(),
+                            body
+                        );
+                        stream.get_mut().write_all(response.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        (endpoint, requests)
+    }
+
+    async fn mock_bucket_client(respond: fn(&str) -> (u16, &'static str)) -> (Client, Seen) {
+        let (endpoint, requests) = mock_s3_endpoint(respond).await;
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .endpoint_url(endpoint)
+            .force_path_style(true)
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .build();
+        (Client::from_conf(config), requests)
+    }
+
+    fn requests(seen: &Seen) -> Vec<String> {
+        seen.lock().unwrap().iter().map(|(request, _)| request.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn missing_bucket_is_created() {
+        let (client, seen) = mock_bucket_client(|request| match request.split(' ').next() {
+            Some("HEAD") => (404, ""),
+            _ => (200, ""),
+        })
+        .await;
+        create_bucket_if_missing(&client).await.unwrap();
+        assert_eq!(
+            requests(&seen),
+            vec!["HEAD /kusanagi-chat-history", "PUT /kusanagi-chat-history"]
+        );
+    }
+
+    #[tokio::test]
+    async fn existing_bucket_is_left_alone() {
+        let (client, seen) = mock_bucket_client(|_| (200, "")).await;
+        create_bucket_if_missing(&client).await.unwrap();
+        assert_eq!(requests(&seen), vec!["HEAD /kusanagi-chat-history"]);
+    }
+
+    #[tokio::test]
+    async fn bucket_created_concurrently_counts_as_success() {
+        let (client, _) = mock_bucket_client(|request| match request.split(' ').next() {
+            Some("HEAD") => (404, ""),
+            _ => (
+                409,
+                "<Error><Code>BucketAlreadyOwnedByYou</Code><Message>Already owned</Message></Error>",
+            ),
+        })
+        .await;
+        assert!(create_bucket_if_missing(&client).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn other_create_errors_are_reported() {
+        let (client, _) = mock_bucket_client(|request| match request.split(' ').next() {
+            Some("HEAD") => (404, ""),
+            _ => (403, "<Error><Code>AccessDenied</Code><Message>Denied</Message></Error>"),
+        })
+        .await;
+        let err = create_bucket_if_missing(&client).await.unwrap_err();
+        assert!(err.starts_with("Failed to create bucket kusanagi-chat-history"), "{}", err);
+    }
+
+    #[test]
+    fn key_month_reads_the_timestamp() {
+        assert_eq!(key_month("chat-2024-03-05T10:00:00+00:00.json"), Some((2024, 3)));
+        assert_eq!(key_month("chat-2024-13-05T10:00:00+00:00.json"), None);
+        assert_eq!(key_month("report-2024-03.json"), None);
+    }
+
+    #[test]
+    fn month_prefixes_walk_back_across_years() {
+        assert_eq!(
+            month_prefixes((2024, 2), (2023, 11)),
+            vec!["chat-2024-02", "chat-2024-01", "chat-2023-12", "chat-2023-11"]
+        );
+        assert_eq!(month_prefixes((2024, 2), (2024, 2)), vec!["chat-2024-02"]);
+        assert!(month_prefixes((2024, 2), (2024, 3)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn explicit_credentials_are_applied_to_the_config() {
+        let (endpoint, seen) = mock_s3_endpoint(|_| (200, "")).await;
+        let shared = aws_config::SdkConfig::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(endpoint)
+            .build();
+        let settings = MinioSettings {
+            credentials: Some(("minio-access".to_string(), "minio-secret".to_string())),
+            region: "eu-west-3".to_string(),
+            force_path_style: true,
+        };
+
+        let client = Client::from_conf(s3_config(&shared, &settings));
+        client.head_bucket().bucket(BUCKET_NAME).send().await.unwrap();
+
+        let seen = seen.lock().unwrap().clone();
+        let (request, authorization) = &seen[0];
+        // Path-style addressing puts the bucket in the path rather than the host
+        assert_eq!(request, "HEAD /kusanagi-chat-history");
+        assert!(
+            authorization.contains("Credential=minio-access/") && authorization.contains("/eu-west-3/s3/"),
+            "{}",
+            authorization
+        );
+    }
+}
@@ -6,13 +6,189 @@
 //! Each function is instrumented with timing spans.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tracing::{info, warn, error, debug};
-use kube::{Api, Client, api::ListParams};
+use kube::{Api, Client, api::{DynamicObject, ListParams}};
 use k8s_openapi::api::core::v1::{Service, Namespace};
 use crate::telemetry;
 
-/// Hubble Relay configuration
-const HUBBLE_RELAY_URL: &str = "http://hubble-relay.kube-system.svc.cluster.local:4245";
+/// Default Hubble Relay address, overridable with HUBBLE_RELAY_URL
+const DEFAULT_HUBBLE_RELAY_URL: &str = "http://hubble-relay.kube-system.svc.cluster.local:4245";
+
+/// Hubble Relay address from HUBBLE_RELAY_URL, falling back to the kube-system default
+pub fn hubble_relay_url() -> String {
+    relay_url_or_default(std::env::var("HUBBLE_RELAY_URL").ok().as_deref())
+}
+
+/// `configured` unless it is unset or blank, else the kube-system default
+fn relay_url_or_default(configured: Option<&str>) -> String {
+    configured
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(DEFAULT_HUBBLE_RELAY_URL)
+        .to_string()
+}
+
+/// Service name and namespace from an in-cluster relay URL
+/// (`http://<service>.<namespace>.svc...`); None for bare hosts or IPs
+pub fn relay_service(url: &str) -> Option<(String, String)> {
+    let host = url.split("://").nth(1).unwrap_or(url);
+    let host = host.split(['/', ':']).next()?;
+    let mut labels = host.split('.');
+    let service = labels.next().filter(|s| !s.is_empty())?;
+    let namespace = labels.next().filter(|s| !s.is_empty())?;
+    if service.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((service.to_string(), namespace.to_string()))
+}
+
+/// `host:port` to dial for a relay URL, with the scheme's default port when none is given
+fn relay_address(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+    let authority = rest.split('/').next().filter(|a| !a.is_empty())?;
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    if has_port {
+        return Some(authority.to_string());
+    }
+    let port = if scheme.eq_ignore_ascii_case("https") { 443 } else { 80 };
+    Some(format!("{}:{}", authority, port))
+}
+
+/// How long a direct TCP probe of the relay may take
+const HUBBLE_RELAY_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Open (and drop) a TCP connection to the relay address
+async fn probe_relay(relay_url: &str) -> Result<(), String> {
+    let address = relay_address(relay_url).ok_or_else(|| format!("Invalid Hubble Relay URL: {}", relay_url))?;
+    match tokio::time::timeout(HUBBLE_RELAY_PROBE_TIMEOUT, tokio::net::TcpStream::connect(&address)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("Hubble Relay {} unreachable: {}", address, e)),
+        Err(_) => Err(format!("Hubble Relay {} timed out", address)),
+    }
+}
+
+/// First delay before reconnecting to Hubble Relay after the stream drops
+const HUBBLE_INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Ceiling for the reconnect delay (HUBBLE_MAX_BACKOFF_SECS, default 30s)
+pub fn hubble_max_backoff() -> std::time::Duration {
+    let secs = std::env::var("HUBBLE_MAX_BACKOFF_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|s| *s > 0)
+        .unwrap_or(30);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Delay before reconnect attempt `attempt` (0-based): doubles from 500ms up to `max`
+pub fn hubble_reconnect_delay(attempt: u32, max: std::time::Duration) -> std::time::Duration {
+    HUBBLE_INITIAL_BACKOFF
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(max, |delay| delay.min(max))
+}
+
+/// How often a reachable relay is checked again
+const HUBBLE_RELAY_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Connection state of Hubble Relay, as seen by `watch_hubble_relay`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum RelayStatus {
+    /// Not checked yet
+    #[default]
+    Unknown,
+    Connected,
+    /// Unreachable; `attempt` counts consecutive failures
+    Reconnecting {
+        attempt: u32,
+        retry_in_secs: u64,
+        error: String,
+    },
+}
+
+lazy_static::lazy_static! {
+    /// Latest relay status; receivers are woken only when it changes
+    static ref RELAY_STATUS: tokio::sync::watch::Sender<RelayStatus> =
+        tokio::sync::watch::channel(RelayStatus::Unknown).0;
+}
+
+/// Current Hubble Relay status
+pub fn relay_status() -> RelayStatus {
+    RELAY_STATUS.borrow().clone()
+}
+
+/// Follow Hubble Relay status changes, e.g. to tell WebSocket clients it is reconnecting
+pub fn subscribe_relay_status() -> tokio::sync::watch::Receiver<RelayStatus> {
+    RELAY_STATUS.subscribe()
+}
+
+fn set_relay_status(status: RelayStatus) {
+    RELAY_STATUS.send_if_modified(|current| {
+        let changed = *current != status;
+        *current = status;
+        changed
+    });
+}
+
+/// Reach Hubble Relay at `relay_url`: find its Service for in-cluster URLs,
+/// otherwise probe the address directly since there is no Service to look up
+async fn connect_relay(client: Option<&Client>, relay_url: &str) -> Result<(), String> {
+    let Some((relay_name, relay_namespace)) = relay_service(relay_url) else {
+        return probe_relay(relay_url).await;
+    };
+    let client = client.ok_or_else(|| "No Kubernetes client to look up the Hubble Relay service".to_string())?;
+    let services: Api<Service> = Api::namespaced(client.clone(), &relay_namespace);
+    services
+        .get(&relay_name)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Hubble Relay {}/{} not found: {}", relay_namespace, relay_name, e))
+}
+
+/// Keep checking Hubble Relay in the background, reconnecting with exponential backoff
+/// (capped by HUBBLE_MAX_BACKOFF_SECS) while it is down
+pub fn watch_hubble_relay() {
+    tokio::spawn(async {
+        let relay_url = hubble_relay_url();
+        // One client for every check; only needed when the relay is looked up as a Service
+        let client = match Client::try_default().await {
+            Ok(client) => Some(client),
+            Err(e) => {
+                warn!(error = %e, "Failed to create Kubernetes client for Hubble Relay checks");
+                None
+            }
+        };
+        let mut attempt: u32 = 0;
+        loop {
+            match connect_relay(client.as_ref(), &relay_url).await {
+                Ok(()) => {
+                    if attempt > 0 {
+                        info!(attempts = attempt, relay = %relay_url, "✅ Hubble Relay reconnected");
+                    } else if relay_status() == RelayStatus::Unknown {
+                        info!(relay = %relay_url, "✅ Hubble Relay found");
+                    }
+                    attempt = 0;
+                    set_relay_status(RelayStatus::Connected);
+                    tokio::time::sleep(HUBBLE_RELAY_CHECK_INTERVAL).await;
+                }
+                Err(e) => {
+                    let delay = hubble_reconnect_delay(attempt, hubble_max_backoff());
+                    attempt = attempt.saturating_add(1);
+                    warn!(attempt, retry_in_ms = delay.as_millis() as u64, error = %e, "⚠️ Hubble Relay unreachable, reconnecting");
+                    set_relay_status(RelayStatus::Reconnecting {
+                        attempt,
+                        retry_in_secs: delay.as_secs(),
+                        error: e,
+                    });
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    });
+}
 
 // ============================================================================
 // Namespace Fetching (Pre-filter for performance)
@@ -77,11 +253,36 @@ pub struct NetworkFlow {
     pub destination_port: u16,
     pub protocol: String,
     pub verdict: String, // "FORWARDED", "DROPPED", "AUDIT"
+    /// Hubble drop reason (e.g. "POLICY_DENIED"), set for DROPPED flows
+    #[serde(default)]
+    pub drop_reason: Option<String>,
+    /// DNS query name from Hubble L7 DNS records
+    #[serde(default)]
+    pub dns_query: Option<String>,
+    /// DNS response code (e.g. "NOERROR", "NXDOMAIN")
+    #[serde(default)]
+    pub dns_rcode: Option<String>,
     pub bytes_sent: u64,
     pub bytes_received: u64,
     pub last_seen: String,
 }
 
+impl NetworkFlow {
+    /// Sort key for `last_seen`; None when it isn't valid RFC 3339
+    fn seen_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(&self.last_seen)
+            .ok()
+            .map(|t| t.with_timezone(&chrono::Utc))
+    }
+}
+
+/// Keep the `limit` most recently seen flows, newest first
+/// Flows with an unparseable timestamp sort last
+pub fn truncate_newest(flows: &mut Vec<NetworkFlow>, limit: usize) {
+    flows.sort_by_key(|f| std::cmp::Reverse(f.seen_at()));
+    flows.truncate(limit);
+}
+
 /// Flow matrix entry (aggregated flows between namespaces/services)
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FlowMatrixEntry {
@@ -92,16 +293,240 @@ pub struct FlowMatrixEntry {
     pub flow_count: u64,
     pub bytes_total: u64,
     pub verdict: String,
+    /// Rates over the query window; 0 when the window is unknown
+    #[serde(default)]
+    pub flows_per_sec: f64,
+    #[serde(default)]
+    pub bytes_per_sec: f64,
+}
+
+/// `count` spread over `window`, or 0 when the window is unknown or empty
+pub fn rate_per_sec(count: u64, window: Option<std::time::Duration>) -> f64 {
+    match window.map(|w| w.as_secs_f64()) {
+        Some(secs) if secs > 0.0 => count as f64 / secs,
+        _ => 0.0,
+    }
+}
+
+/// Parse a flow window such as `30s`, `5m` or `1h` (`?since=`); a bare number is seconds
+pub fn parse_since(value: &str) -> Result<std::time::Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid window '{}': expected e.g. 30s, 5m or 1h", value);
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len()));
+    let count: u64 = number.parse().map_err(|_| invalid())?;
+    let unit_secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(invalid()),
+    };
+    match count.checked_mul(unit_secs) {
+        Some(secs) if secs > 0 => Ok(std::time::Duration::from_secs(secs)),
+        _ => Err(invalid()),
+    }
 }
 
 /// Hubble flows response
 #[derive(Serialize, Deserialize, Debug)]
 pub struct HubbleFlowsResponse {
     pub total_flows: u64,
+    /// Flows per verdict; other verdicts (ERROR, REDIRECTED, ...) are only in total_flows
+    #[serde(default)]
+    pub forwarded: u64,
+    #[serde(default)]
+    pub dropped: u64,
+    #[serde(default)]
+    pub audit: u64,
     pub flows: Vec<NetworkFlow>,
     pub matrix: Vec<FlowMatrixEntry>,
     pub namespaces: Vec<String>,
     pub timestamp: String,
+    /// Hubble Relay connection state when the flows were fetched
+    #[serde(default)]
+    pub relay_status: RelayStatus,
+}
+
+impl HubbleFlowsResponse {
+    /// `window` is the time span the flows were observed over, used for the matrix rates
+    fn new(
+        flows: Vec<NetworkFlow>,
+        mut matrix: Vec<FlowMatrixEntry>,
+        namespaces: Vec<String>,
+        window: Option<std::time::Duration>,
+    ) -> Self {
+        for entry in &mut matrix {
+            entry.flows_per_sec = rate_per_sec(entry.flow_count, window);
+            entry.bytes_per_sec = rate_per_sec(entry.bytes_total, window);
+        }
+        let mut response = Self {
+            total_flows: 0,
+            forwarded: 0,
+            dropped: 0,
+            audit: 0,
+            flows,
+            matrix,
+            namespaces,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            relay_status: relay_status(),
+        };
+        response.recount();
+        response
+    }
+
+    /// Keep the flows seen within `window`, aggregate them into the matrix with rates
+    /// over that window, then keep the `limit` newest
+    pub fn keep_window(&mut self, window: std::time::Duration, limit: usize) {
+        let cutoff = chrono::Duration::from_std(window)
+            .ok()
+            .and_then(|w| chrono::Utc::now().checked_sub_signed(w));
+        if let Some(cutoff) = cutoff {
+            self.flows.retain(|f| f.seen_at().is_some_and(|t| t >= cutoff));
+        }
+        self.rebuild_matrix(Some(window));
+        truncate_newest(&mut self.flows, limit);
+        self.recount();
+    }
+
+    /// Rebuild the matrix from `flows` after they were filtered, with rates over `window`
+    pub fn rebuild_matrix(&mut self, window: Option<std::time::Duration>) {
+        self.matrix = build_matrix(&self.flows);
+        for entry in &mut self.matrix {
+            entry.flows_per_sec = rate_per_sec(entry.flow_count, window);
+            entry.bytes_per_sec = rate_per_sec(entry.bytes_total, window);
+        }
+    }
+
+    /// Recompute the total and per-verdict counts after `flows` changed
+    pub fn recount(&mut self) {
+        self.total_flows = self.flows.len() as u64;
+        self.forwarded = 0;
+        self.dropped = 0;
+        self.audit = 0;
+        for flow in &self.flows {
+            match flow.verdict.as_str() {
+                "FORWARDED" => self.forwarded += 1,
+                "DROPPED" => self.dropped += 1,
+                "AUDIT" => self.audit += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Aggregate flows per source, destination, protocol, port and verdict
+/// Bytes count both directions; rates are left at 0 for the caller to fill in
+pub fn build_matrix(flows: &[NetworkFlow]) -> Vec<FlowMatrixEntry> {
+    let mut entries: BTreeMap<(String, String, String, u16, String), FlowMatrixEntry> = BTreeMap::new();
+    for flow in flows {
+        let source = format!("{}/{}", flow.source_namespace, flow.source_pod);
+        let destination = format!("{}/{}", flow.destination_namespace, flow.destination_pod);
+        let key = (
+            source.clone(),
+            destination.clone(),
+            flow.protocol.clone(),
+            flow.destination_port,
+            flow.verdict.clone(),
+        );
+        let entry = entries.entry(key).or_insert_with(|| FlowMatrixEntry {
+            source,
+            destination,
+            protocol: flow.protocol.clone(),
+            port: flow.destination_port,
+            flow_count: 0,
+            bytes_total: 0,
+            verdict: flow.verdict.clone(),
+            flows_per_sec: 0.0,
+            bytes_per_sec: 0.0,
+        });
+        entry.flow_count += 1;
+        entry.bytes_total += flow.bytes_sent + flow.bytes_received;
+    }
+    entries.into_values().collect()
+}
+
+/// L7 protocol filter for flow queries (`?l7=dns`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum L7Filter {
+    Dns,
+}
+
+impl L7Filter {
+    fn matches(&self, flow: &NetworkFlow) -> bool {
+        match self {
+            L7Filter::Dns => flow.dns_query.is_some(),
+        }
+    }
+}
+
+/// Pod label filter for flow queries (`?label=app=argocd-server`, or `?label=app` for any value)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelFilter {
+    key: String,
+    value: Option<String>,
+}
+
+impl LabelFilter {
+    pub fn parse(selector: &str) -> Result<Self, String> {
+        let (key, value) = match selector.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (selector.trim(), None),
+        };
+        if key.is_empty() {
+            return Err(format!("Invalid label filter '{}': expected key=value", selector));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value,
+        })
+    }
+
+    /// Whether one `key=value` label matches; Hubble's source prefix (`k8s:app=...`) is ignored
+    fn matches_label(&self, label: &str) -> bool {
+        let (key, value) = label.split_once('=').unwrap_or((label, ""));
+        let key = key.split_once(':').map_or(key, |(_, k)| k);
+        key == self.key && self.value.as_deref().is_none_or(|v| v == value)
+    }
+
+    /// True when the source or the destination carries the label
+    pub fn matches(&self, flow: &NetworkFlow) -> bool {
+        flow.source_labels
+            .iter()
+            .chain(&flow.destination_labels)
+            .any(|label| self.matches_label(label))
+    }
+}
+
+/// Graph of endpoints and the traffic between them, for force-directed rendering
+#[derive(Serialize, Debug)]
+pub struct Topology {
+    pub nodes: Vec<TopologyNode>,
+    pub edges: Vec<TopologyEdge>,
+}
+
+/// One endpoint (`namespace/pod`) in the topology graph
+#[derive(Serialize, Debug)]
+pub struct TopologyNode {
+    pub id: String,
+    pub namespace: String,
+    pub labels: Vec<String>,
+}
+
+/// Aggregated traffic from one endpoint to another
+#[derive(Serialize, Debug)]
+pub struct TopologyEdge {
+    pub source: String,
+    pub target: String,
+    pub bytes: u64,
+    pub verdict: String,
+}
+
+/// Dropped flows with a count per drop reason
+#[derive(Serialize, Debug)]
+pub struct DroppedFlowsResponse {
+    pub total_dropped: usize,
+    pub by_reason: BTreeMap<String, u64>,
+    pub flows: Vec<NetworkFlow>,
 }
 
 /// Bandwidth metrics per service
@@ -148,64 +573,56 @@ pub struct ExportOptions {
 // ============================================================================
 
 /// Fetch network flows from Hubble Relay
-pub async fn get_hubble_flows(namespace: Option<&str>, limit: usize) -> Result<HubbleFlowsResponse, String> {
+/// With `since`, only flows from that window are kept and the matrix carries rates over it
+pub async fn get_hubble_flows(
+    namespace: Option<&str>,
+    limit: usize,
+    since: Option<std::time::Duration>,
+) -> Result<HubbleFlowsResponse, String> {
     let span = telemetry::start_span("cilium.get_hubble_flows")
         .with_namespace(namespace)
         .with_endpoint("/api/cilium/flows");
     
     debug!(namespace = ?namespace, limit = limit, "🔍 Fetching Hubble flows");
-    
-    // Track K8s client creation time
-    let client_start = std::time::Instant::now();
-    let client = match Client::try_default().await {
-        Ok(c) => {
-            debug!(duration_ms = client_start.elapsed().as_millis(), "K8s client created");
-            c
-        },
-        Err(e) => {
-            warn!(error = %e, "Failed to create K8s client for Hubble");
-            let result = get_mock_flows(namespace, limit);
-            if let Ok(ref flows) = result {
-                span.record("mock_fallback", Some(flows.flows.len() as u64));
-            }
-            return result;
+
+    // The relay is tracked by `watch_hubble_relay`; only check it here before its first result
+    let connected = match relay_status() {
+        RelayStatus::Unknown => {
+            let discovery_start = std::time::Instant::now();
+            let client = Client::try_default().await.ok();
+            let result = connect_relay(client.as_ref(), &hubble_relay_url()).await;
+            debug!(discovery_ms = discovery_start.elapsed().as_millis(), ok = result.is_ok(), "Hubble Relay checked");
+            result.is_ok()
         }
+        status => status == RelayStatus::Connected,
     };
 
-    // Track Hubble Relay discovery time
-    let discovery_start = std::time::Instant::now();
-    let services: Api<Service> = Api::namespaced(client.clone(), "kube-system");
-    match services.get("hubble-relay").await {
-        Ok(_) => {
-            info!(
-                discovery_ms = discovery_start.elapsed().as_millis(),
-                "✅ Hubble Relay service found"
-            );
-            // TODO: Implement actual Hubble gRPC client
-            // For now, return mock data structure
-            let result = get_mock_flows(namespace, limit);
-            if let Ok(ref flows) = result {
-                span.record("success", Some(flows.flows.len() as u64));
-            }
-            result
-        }
-        Err(e) => {
-            warn!(
-                error = %e,
-                discovery_ms = discovery_start.elapsed().as_millis(),
-                "⚠️ Hubble Relay not found, using mock data"
-            );
-            let result = get_mock_flows(namespace, limit);
-            if let Ok(ref flows) = result {
-                span.record("mock_fallback", Some(flows.flows.len() as u64));
-            }
-            result
-        }
+    // TODO: Stream flows from `hubble_relay_url()` over gRPC once the client lands.
+    // For now, return mock data structure
+    let mut result = get_mock_flows(namespace, if since.is_some() { usize::MAX } else { limit });
+    if let (Ok(flows), Some(window)) = (result.as_mut(), since) {
+        flows.keep_window(window, limit);
     }
+    if let Ok(ref flows) = result {
+        let outcome = if connected { "success" } else { "mock_fallback" };
+        span.record(outcome, Some(flows.flows.len() as u64));
+    }
+    result
+}
+
+/// Whether mock data includes sample dropped and DNS flows (HUBBLE_MOCK_FLOWS=true)
+/// Off by default so invented drop reasons and lookups never look like real Hubble data
+fn hubble_mock_flows() -> bool {
+    std::env::var("HUBBLE_MOCK_FLOWS").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
 }
 
 /// Generate mock flows for demonstration
 fn get_mock_flows(namespace: Option<&str>, limit: usize) -> Result<HubbleFlowsResponse, String> {
+    Ok(mock_flows(namespace, limit, hubble_mock_flows()))
+}
+
+/// Sample forwarded flows, plus dropped and DNS ones when `samples` is set
+fn mock_flows(namespace: Option<&str>, limit: usize, samples: bool) -> HubbleFlowsResponse {
     let namespaces = vec![
         "default", "kube-system", "argocd", "monitoring", 
         "kusanagi", "n8n", "paperless", "minio"
@@ -235,6 +652,9 @@ fn get_mock_flows(namespace: Option<&str>, limit: usize) -> Result<HubbleFlowsRe
                 destination_port: *port,
                 protocol: proto.to_string(),
                 verdict: "FORWARDED".to_string(),
+                drop_reason: None,
+                dns_query: None,
+                dns_rcode: None,
                 bytes_sent: *bytes as u64,
                 bytes_received: (*bytes / 2) as u64,
                 last_seen: chrono::Utc::now().to_rfc3339(),
@@ -248,34 +668,176 @@ fn get_mock_flows(namespace: Option<&str>, limit: usize) -> Result<HubbleFlowsRe
                 flow_count: 100,
                 bytes_total: *bytes as u64 * 100,
                 verdict: "FORWARDED".to_string(),
+                flows_per_sec: 0.0,
+                bytes_per_sec: 0.0,
             });
         }
     }
 
-    flows.truncate(limit);
+    // Sample flows denied by network policy
+    let sample_drops: &[_] = if samples {
+        &[
+            ("default", "debug-shell", "minio", "minio-api", 9000, "TCP", "POLICY_DENIED"),
+            ("n8n", "n8n-worker", "kube-system", "kube-apiserver", 6443, "TCP", "POLICY_DENIED"),
+            ("paperless", "paperless-web", "argocd", "argocd-repo-server", 8081, "TCP", "STALE_OR_UNROUTABLE_IP"),
+        ]
+    } else {
+        &[]
+    };
+
+    for (src_ns, src_pod, dst_ns, dst_pod, port, proto, reason) in sample_drops.iter() {
+        if namespace.map(|n| n == *src_ns || n == *dst_ns).unwrap_or(true) {
+            flows.push(NetworkFlow {
+                source_namespace: src_ns.to_string(),
+                source_pod: src_pod.to_string(),
+                source_labels: vec![format!("app={}", src_pod)],
+                destination_namespace: dst_ns.to_string(),
+                destination_pod: dst_pod.to_string(),
+                destination_labels: vec![format!("app={}", dst_pod)],
+                destination_port: *port,
+                protocol: proto.to_string(),
+                verdict: "DROPPED".to_string(),
+                drop_reason: Some(reason.to_string()),
+                dns_query: None,
+                dns_rcode: None,
+                bytes_sent: 0,
+                bytes_received: 0,
+                last_seen: chrono::Utc::now().to_rfc3339(),
+            });
+
+            matrix.push(FlowMatrixEntry {
+                source: format!("{}/{}", src_ns, src_pod),
+                destination: format!("{}/{}", dst_ns, dst_pod),
+                protocol: proto.to_string(),
+                port: *port,
+                flow_count: 10,
+                bytes_total: 0,
+                verdict: "DROPPED".to_string(),
+                flows_per_sec: 0.0,
+                bytes_per_sec: 0.0,
+            });
+        }
+    }
+
+    // Sample DNS lookups seen by Hubble's L7 visibility
+    let sample_dns: &[_] = if samples {
+        &[
+            ("default", "nginx", "minio-api.minio.svc.cluster.local.", "NOERROR"),
+            ("paperless", "paperless-web", "redis.paperless.svc.cluster.local.", "NXDOMAIN"),
+        ]
+    } else {
+        &[]
+    };
+
+    for (src_ns, src_pod, query, rcode) in sample_dns.iter() {
+        if namespace.map(|n| n == *src_ns || n == "kube-system").unwrap_or(true) {
+            flows.push(NetworkFlow {
+                source_namespace: src_ns.to_string(),
+                source_pod: src_pod.to_string(),
+                source_labels: vec![format!("app={}", src_pod)],
+                destination_namespace: "kube-system".to_string(),
+                destination_pod: "coredns".to_string(),
+                destination_labels: vec!["k8s-app=kube-dns".to_string()],
+                destination_port: 53,
+                protocol: "UDP".to_string(),
+                verdict: "FORWARDED".to_string(),
+                drop_reason: None,
+                dns_query: Some(query.to_string()),
+                dns_rcode: Some(rcode.to_string()),
+                bytes_sent: 64,
+                bytes_received: 128,
+                last_seen: chrono::Utc::now().to_rfc3339(),
+            });
+        }
+    }
+
+    truncate_newest(&mut flows, limit);
     
-    Ok(HubbleFlowsResponse {
-        total_flows: flows.len() as u64,
+    // Mock counts aren't tied to an observation window, so no rates
+    HubbleFlowsResponse::new(
         flows,
         matrix,
-        namespaces: namespaces.iter().map(|s| s.to_string()).collect(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
-    })
+        namespaces.iter().map(|s| s.to_string()).collect(),
+        None,
+    )
+}
+
+/// Fetch flows, optionally keeping only one L7 protocol and/or flows touching a labeled pod
+/// Filters are applied before the limit so matching flows aren't cut off
+pub async fn get_flows(
+    namespace: Option<&str>,
+    limit: usize,
+    l7: Option<L7Filter>,
+    label: Option<&LabelFilter>,
+    since: Option<std::time::Duration>,
+) -> Result<HubbleFlowsResponse, String> {
+    if l7.is_none() && label.is_none() {
+        return get_hubble_flows(namespace, limit, since).await;
+    }
+
+    let mut response = get_hubble_flows(namespace, usize::MAX, since).await?;
+    response.flows.retain(|f| {
+        l7.is_none_or(|l7| l7.matches(f)) && label.is_none_or(|label| label.matches(f))
+    });
+    response.rebuild_matrix(since);
+    truncate_newest(&mut response.flows, limit);
+    response.recount();
+    Ok(response)
+}
+
+// ============================================================================
+// Dropped Traffic
+// ============================================================================
+
+/// Fetch only DROPPED flows, e.g. to debug a new NetworkPolicy
+pub async fn get_dropped_flows(namespace: Option<&str>, limit: usize) -> Result<Vec<NetworkFlow>, String> {
+    let span = telemetry::start_span("cilium.get_dropped_flows")
+        .with_namespace(namespace)
+        .with_endpoint("/api/cilium/drops");
+
+    debug!(namespace = ?namespace, limit = limit, "🔍 Fetching dropped flows");
+
+    // Filter before applying the limit so forwarded flows don't crowd out drops
+    let response = get_hubble_flows(namespace, usize::MAX, None).await?;
+    let mut dropped = dropped_only(response.flows);
+    truncate_newest(&mut dropped, limit);
+
+    info!(dropped = dropped.len(), "✅ Dropped flows fetched");
+    span.record("success", Some(dropped.len() as u64));
+
+    Ok(dropped)
+}
+
+fn dropped_only(flows: Vec<NetworkFlow>) -> Vec<NetworkFlow> {
+    flows.into_iter().filter(|f| f.verdict == "DROPPED").collect()
+}
+
+/// Count flows per drop reason ("UNKNOWN" when Hubble gave none)
+pub fn count_drop_reasons(flows: &[NetworkFlow]) -> BTreeMap<String, u64> {
+    let mut by_reason = BTreeMap::new();
+    for flow in flows {
+        let reason = flow.drop_reason.clone().unwrap_or_else(|| "UNKNOWN".to_string());
+        *by_reason.entry(reason).or_insert(0) += 1;
+    }
+    by_reason
 }
 
 // ============================================================================
 // Flow Matrix Generation
 // ============================================================================
 
-/// Generate flow matrix for visualization
-pub async fn get_flow_matrix(namespace: Option<&str>) -> Result<Vec<FlowMatrixEntry>, String> {
+/// Generate flow matrix for visualization, with rates when `since` gives the window
+pub async fn get_flow_matrix(
+    namespace: Option<&str>,
+    since: Option<std::time::Duration>,
+) -> Result<Vec<FlowMatrixEntry>, String> {
     let span = telemetry::start_span("cilium.get_flow_matrix")
         .with_namespace(namespace)
         .with_endpoint("/api/cilium/matrix");
     
     debug!(namespace = ?namespace, "🔍 Generating flow matrix");
     
-    let response = get_hubble_flows(namespace, 1000).await?;
+    let response = get_hubble_flows(namespace, 1000, since).await?;
     let matrix_len = response.matrix.len();
     
     info!(matrix_entries = matrix_len, "✅ Flow matrix generated");
@@ -284,6 +846,58 @@ pub async fn get_flow_matrix(namespace: Option<&str>) -> Result<Vec<FlowMatrixEn
     Ok(response.matrix)
 }
 
+/// Get the flow topology as deduplicated nodes and edges
+pub async fn get_topology(namespace: Option<&str>) -> Result<Topology, String> {
+    let span = telemetry::start_span("cilium.get_topology")
+        .with_namespace(namespace)
+        .with_endpoint("/api/cilium/topology");
+
+    let response = get_hubble_flows(namespace, 1000, None).await?;
+    let topology = build_topology(&response.matrix, &response.flows);
+
+    info!(nodes = topology.nodes.len(), edges = topology.edges.len(), "✅ Flow topology generated");
+    span.record("success", Some(topology.nodes.len() as u64));
+
+    Ok(topology)
+}
+
+/// Turn matrix entries into a graph; each `namespace/pod` endpoint becomes one node,
+/// labelled from the flows it appears in
+pub fn build_topology(matrix: &[FlowMatrixEntry], flows: &[NetworkFlow]) -> Topology {
+    let mut labels: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for flow in flows {
+        labels
+            .entry(format!("{}/{}", flow.source_namespace, flow.source_pod))
+            .or_insert_with(|| flow.source_labels.clone());
+        labels
+            .entry(format!("{}/{}", flow.destination_namespace, flow.destination_pod))
+            .or_insert_with(|| flow.destination_labels.clone());
+    }
+
+    let mut nodes: BTreeMap<String, TopologyNode> = BTreeMap::new();
+    let mut edges = Vec::with_capacity(matrix.len());
+    for entry in matrix {
+        for id in [&entry.source, &entry.destination] {
+            nodes.entry(id.clone()).or_insert_with(|| TopologyNode {
+                id: id.clone(),
+                namespace: id.split('/').next().unwrap_or_default().to_string(),
+                labels: labels.get(id).cloned().unwrap_or_default(),
+            });
+        }
+        edges.push(TopologyEdge {
+            source: entry.source.clone(),
+            target: entry.destination.clone(),
+            bytes: entry.bytes_total,
+            verdict: entry.verdict.clone(),
+        });
+    }
+
+    Topology {
+        nodes: nodes.into_values().collect(),
+        edges,
+    }
+}
+
 // ============================================================================
 // Bandwidth Metrics
 // ============================================================================
@@ -377,6 +991,124 @@ pub async fn detect_anomalies(namespace: Option<&str>) -> Result<Vec<NetworkAnom
     Ok(result)
 }
 
+// ============================================================================
+// Network Policies
+// ============================================================================
+
+fn cilium_resource(kind: &str, plural: &str) -> kube::discovery::ApiResource {
+    kube::discovery::ApiResource {
+        group: "cilium.io".to_string(),
+        version: "v2".to_string(),
+        api_version: "cilium.io/v2".to_string(),
+        kind: kind.to_string(),
+        plural: plural.to_string(),
+    }
+}
+
+fn dynamic_api(client: Client, namespace: Option<&str>, resource: &kube::discovery::ApiResource) -> Api<DynamicObject> {
+    match namespace {
+        Some(ns) => Api::namespaced_with(client, ns, resource),
+        None => Api::all_with(client, resource),
+    }
+}
+
+/// Namespace and security-identity labels (`k8s:app=foo`) of a CiliumEndpoint
+fn endpoint_labels(endpoint: &DynamicObject) -> (String, Vec<String>) {
+    let labels = endpoint
+        .data
+        .pointer("/status/identity/labels")
+        .and_then(|l| l.as_array())
+        .map(|l| l.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    (endpoint.metadata.namespace.clone().unwrap_or_default(), labels)
+}
+
+/// Whether identity labels satisfy an endpointSelector's matchLabels
+/// Selector keys may carry a source prefix (`k8s:app`, `any:app`); identity labels always do
+/// matchExpressions are not evaluated, so the count is an upper bound for selectors using them
+pub fn endpoint_selector_matches(match_labels: &serde_json::Map<String, serde_json::Value>, labels: &[String]) -> bool {
+    match_labels.iter().all(|(key, value)| {
+        let key = key.split_once(':').map_or(key.as_str(), |(_, k)| k);
+        let Some(value) = value.as_str() else { return false };
+        labels.iter().any(|label| {
+            let label = label.split_once(':').map_or(label.as_str(), |(_, l)| l);
+            label.split_once('=') == Some((key, value))
+        })
+    })
+}
+
+/// Convert a CiliumNetworkPolicy object, counting the endpoints its endpointSelector matches
+/// A policy counts as enabled unless a node reports it failed to apply (`status.nodes.*.ok = false`)
+pub fn parse_network_policy(policy: &DynamicObject, endpoints: &[(String, Vec<String>)]) -> CiliumNetworkPolicy {
+    let namespace = policy.metadata.namespace.clone().unwrap_or_default();
+    let spec = policy.data.get("spec").cloned().unwrap_or(serde_json::Value::Null);
+
+    let empty = serde_json::Map::new();
+    let match_labels = spec
+        .pointer("/endpointSelector/matchLabels")
+        .and_then(|m| m.as_object())
+        .unwrap_or(&empty);
+    let endpoints_matched = endpoints
+        .iter()
+        .filter(|(ns, labels)| *ns == namespace && endpoint_selector_matches(match_labels, labels))
+        .count() as i32;
+
+    let enabled = policy
+        .data
+        .pointer("/status/nodes")
+        .and_then(|n| n.as_object())
+        .is_none_or(|nodes| nodes.values().all(|n| n.get("ok").and_then(|ok| ok.as_bool()) != Some(false)));
+
+    CiliumNetworkPolicy {
+        name: policy.metadata.name.clone().unwrap_or_default(),
+        namespace,
+        spec_json: spec.to_string(),
+        endpoints_matched,
+        enabled,
+    }
+}
+
+/// List CiliumNetworkPolicy resources straight from the cluster (no MCP server involved)
+/// When CiliumEndpoints can't be listed, `endpoints_matched` is reported as 0
+pub async fn get_network_policies(namespace: Option<&str>) -> Result<Vec<CiliumNetworkPolicy>, String> {
+    let span = telemetry::start_span("cilium.get_network_policies")
+        .with_namespace(namespace)
+        .with_endpoint("/api/cilium/policies");
+
+    let client = Client::try_default()
+        .await
+        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+
+    let policies = dynamic_api(client.clone(), namespace, &cilium_resource("CiliumNetworkPolicy", "ciliumnetworkpolicies"))
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| format!("Failed to list CiliumNetworkPolicies: {}", e))?;
+
+    let endpoints: Vec<(String, Vec<String>)> =
+        match dynamic_api(client, namespace, &cilium_resource("CiliumEndpoint", "ciliumendpoints"))
+            .list(&ListParams::default())
+            .await
+        {
+            Ok(list) => list.items.iter().map(endpoint_labels).collect(),
+            Err(e) => {
+                warn!(error = %e, "Failed to list CiliumEndpoints, policy endpoint counts unavailable");
+                Vec::new()
+            }
+        };
+
+    let mut result: Vec<CiliumNetworkPolicy> = policies
+        .items
+        .iter()
+        .map(|policy| parse_network_policy(policy, &endpoints))
+        .collect();
+    result.sort_by(|a, b| a.namespace.cmp(&b.namespace).then(a.name.cmp(&b.name)));
+
+    info!(policies = result.len(), "✅ Cilium network policies fetched");
+    span.record("success", Some(result.len() as u64));
+
+    Ok(result)
+}
+
 // ============================================================================
 // Export Functions
 // ============================================================================
@@ -386,26 +1118,74 @@ pub fn export_flows_json(flows: &HubbleFlowsResponse) -> String {
     serde_json::to_string_pretty(flows).unwrap_or_else(|_| "{}".to_string())
 }
 
-/// Export flows as CSV
-pub fn export_flows_csv(flows: &HubbleFlowsResponse) -> String {
-    let mut csv = String::from("source_namespace,source_pod,destination_namespace,destination_pod,port,protocol,verdict,bytes_sent,bytes_received\n");
-    
-    for flow in &flows.flows {
-        csv.push_str(&format!(
-            "{},{},{},{},{},{},{},{},{}\n",
-            flow.source_namespace,
-            flow.source_pod,
-            flow.destination_namespace,
-            flow.destination_pod,
-            flow.destination_port,
-            flow.protocol,
-            flow.verdict,
-            flow.bytes_sent,
-            flow.bytes_received
+const FLOWS_CSV_HEADER: &str = "source_namespace,source_pod,destination_namespace,destination_pod,port,protocol,verdict,drop_reason,dns_query,dns_rcode,bytes_sent,bytes_received\n";
+
+/// One CSV line for a flow, newline included
+pub fn flow_csv_row(flow: &NetworkFlow) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+        csv_field(&flow.source_namespace),
+        csv_field(&flow.source_pod),
+        csv_field(&flow.destination_namespace),
+        csv_field(&flow.destination_pod),
+        flow.destination_port,
+        csv_field(&flow.protocol),
+        csv_field(&flow.verdict),
+        csv_field(flow.drop_reason.as_deref().unwrap_or("")),
+        csv_field(flow.dns_query.as_deref().unwrap_or("")),
+        csv_field(flow.dns_rcode.as_deref().unwrap_or("")),
+        flow.bytes_sent,
+        flow.bytes_received
+    )
+}
+
+/// Quote a CSV field when it holds a comma, quote or newline, doubling inner quotes
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+/// Export flows as CSV, one chunk per row so large exports are never held as a single string
+pub fn export_flows_csv_stream(
+    flows: HubbleFlowsResponse,
+) -> impl futures::Stream<Item = Result<actix_web::web::Bytes, std::convert::Infallible>> {
+    let rows = std::iter::once(FLOWS_CSV_HEADER.to_string())
+        .chain(flows.flows.into_iter().map(|flow| flow_csv_row(&flow)));
+    futures::stream::iter(rows.map(|row| Ok(actix_web::web::Bytes::from(row))))
+}
+
+/// Export the flow matrix as a GraphViz DOT digraph (render with `dot -Tpng`)
+/// Nodes are namespace/pod, edges are labeled port/protocol and colored by verdict
+pub fn export_flows_dot(flows: &HubbleFlowsResponse) -> String {
+    let mut dot = String::from("digraph cilium_flows {\n");
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=box, style=rounded];\n");
+
+    for entry in &flows.matrix {
+        let color = match entry.verdict.as_str() {
+            "FORWARDED" => "green",
+            "DROPPED" => "red",
+            _ => "gray",
+        };
+        dot.push_str(&format!(
+            "    \"{}\" -> \"{}\" [label=\"{}/{}\", color={}];\n",
+            dot_escape(&entry.source),
+            dot_escape(&entry.destination),
+            entry.port,
+            dot_escape(&entry.protocol),
+            color
         ));
     }
-    
-    csv
+
+    dot.push_str("}\n");
+    dot
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
 }
 
 /// Export matrix as CSV
@@ -427,3 +1207,324 @@ pub fn export_matrix_csv(matrix: &[FlowMatrixEntry]) -> String {
     
     csv
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_ceiling() {
+        let max = Duration::from_secs(30);
+        let schedule: Vec<u64> = (0..8).map(|n| hubble_reconnect_delay(n, max).as_millis() as u64).collect();
+        assert_eq!(schedule, vec![500, 1000, 2000, 4000, 8000, 16000, 30000, 30000]);
+        assert_eq!(hubble_reconnect_delay(u32::MAX, max), max);
+        assert_eq!(hubble_reconnect_delay(3, Duration::from_secs(1)), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn sample_drops_only_with_mock_flag() {
+        let plain = mock_flows(None, usize::MAX, false);
+        assert_eq!(plain.dropped, 0);
+        assert!(plain.flows.iter().all(|f| f.drop_reason.is_none()));
+
+        let samples = mock_flows(None, usize::MAX, true);
+        assert_eq!(samples.dropped, 3);
+    }
+
+    #[test]
+    fn drops_are_filtered_and_counted_by_reason() {
+        let mut flows = mock_flows(None, usize::MAX, true).flows;
+        flows[0].verdict = "DROPPED".to_string();
+        flows[0].drop_reason = None;
+        let dropped = dropped_only(flows);
+        assert_eq!(dropped.len(), 4);
+        assert!(dropped.iter().all(|f| f.verdict == "DROPPED"));
+
+        let by_reason = count_drop_reasons(&dropped);
+        assert_eq!(by_reason.get("POLICY_DENIED"), Some(&2));
+        assert_eq!(by_reason.get("STALE_OR_UNROUTABLE_IP"), Some(&1));
+        assert_eq!(by_reason.get("UNKNOWN"), Some(&1));
+    }
+
+    #[test]
+    fn dns_filter_keeps_only_dns_flows() {
+        let response = mock_flows(None, usize::MAX, true);
+        let dns: Vec<&NetworkFlow> = response.flows.iter().filter(|f| L7Filter::Dns.matches(f)).collect();
+        assert_eq!(dns.len(), 2);
+        assert!(dns.iter().all(|f| f.dns_query.is_some()));
+        assert!(mock_flows(None, usize::MAX, false).flows.iter().all(|f| f.dns_query.is_none()));
+    }
+
+    #[test]
+    fn csv_rows_include_dns_columns() {
+        let response = mock_flows(Some("paperless"), usize::MAX, true);
+        let flow = response.flows.iter().find(|f| f.dns_rcode.as_deref() == Some("NXDOMAIN")).unwrap();
+        assert_eq!(
+            flow_csv_row(flow),
+            "paperless,paperless-web,kube-system,coredns,53,UDP,FORWARDED,,redis.paperless.svc.cluster.local.,NXDOMAIN,64,128\n"
+        );
+        assert!(FLOWS_CSV_HEADER.contains(",drop_reason,dns_query,dns_rcode,"));
+    }
+
+    #[test]
+    fn matrix_is_rebuilt_from_retained_flows() {
+        let mut response = mock_flows(None, usize::MAX, true);
+        response.flows.retain(|f| L7Filter::Dns.matches(f));
+        response.recount();
+        response.rebuild_matrix(Some(Duration::from_secs(2)));
+
+        assert_eq!(response.matrix.len(), 2);
+        let entry = &response.matrix[0];
+        assert_eq!(entry.source, "default/nginx");
+        assert_eq!(entry.destination, "kube-system/coredns");
+        assert_eq!(entry.flow_count, 1);
+        assert_eq!(entry.bytes_total, 192);
+        assert_eq!(entry.flows_per_sec, 0.5);
+    }
+
+    #[test]
+    fn rate_from_known_count_and_window() {
+        assert_eq!(rate_per_sec(300, Some(Duration::from_secs(60))), 5.0);
+        assert_eq!(rate_per_sec(300, None), 0.0);
+        assert_eq!(rate_per_sec(300, Some(Duration::ZERO)), 0.0);
+    }
+
+    #[test]
+    fn parse_since_accepts_units() {
+        assert_eq!(parse_since("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_since("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_since("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_since("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_since("0s").is_err());
+        assert!(parse_since("5d").is_err());
+        assert!(parse_since("m").is_err());
+    }
+
+    #[test]
+    fn window_drops_old_flows_and_sets_rates() {
+        let mut response = mock_flows(Some("argocd"), usize::MAX, false);
+        let mut old = response.flows[0].clone();
+        old.last_seen = (chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        response.flows.push(old);
+        let recent = response.flows[0].clone();
+        response.flows.push(recent);
+
+        response.keep_window(Duration::from_secs(10), usize::MAX);
+        assert_eq!(response.total_flows, 2);
+        assert_eq!(response.matrix.len(), 1);
+        assert_eq!(response.matrix[0].flow_count, 2);
+        assert_eq!(response.matrix[0].flows_per_sec, 0.2);
+        assert_eq!(response.matrix[0].bytes_per_sec, 2.0 * 1536.0 / 10.0);
+    }
+
+    #[test]
+    fn truncation_keeps_the_newest_flows() {
+        let base = mock_flows(None, usize::MAX, false).flows[0].clone();
+        let flow = |port: u16, last_seen: &str| NetworkFlow {
+            destination_port: port,
+            last_seen: last_seen.to_string(),
+            ..base.clone()
+        };
+        let mut flows = vec![
+            flow(1, "2024-01-01T10:00:00Z"),
+            flow(2, "not a timestamp"),
+            flow(3, "2024-01-01T12:00:00Z"),
+            flow(4, "2024-01-01T11:00:00+00:00"),
+        ];
+
+        truncate_newest(&mut flows, 2);
+        let ports: Vec<u16> = flows.iter().map(|f| f.destination_port).collect();
+        assert_eq!(ports, vec![3, 4]);
+    }
+
+    #[tokio::test]
+    async fn streamed_csv_quotes_fields_with_commas_and_quotes() {
+        use futures::StreamExt;
+
+        let forwarded = NetworkFlow {
+            source_namespace: "argocd".to_string(),
+            source_pod: "argocd-server".to_string(),
+            source_labels: vec![],
+            destination_namespace: "kusanagi".to_string(),
+            destination_pod: "kusanagi-app".to_string(),
+            destination_labels: vec![],
+            destination_port: 8080,
+            protocol: "TCP".to_string(),
+            verdict: "FORWARDED".to_string(),
+            drop_reason: None,
+            dns_query: None,
+            dns_rcode: None,
+            bytes_sent: 1024,
+            bytes_received: 2048,
+            last_seen: "2024-01-01T12:00:00Z".to_string(),
+        };
+        let dropped = NetworkFlow {
+            verdict: "DROPPED".to_string(),
+            drop_reason: Some("Policy \"deny-all\", egress".to_string()),
+            bytes_received: 0,
+            ..forwarded.clone()
+        };
+        let flows = HubbleFlowsResponse::new(vec![forwarded, dropped], vec![], vec![], None);
+
+        let chunks: Vec<_> = export_flows_csv_stream(flows).collect().await;
+        let streamed: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.unwrap().to_vec()).collect();
+        assert_eq!(
+            String::from_utf8(streamed).unwrap(),
+            "source_namespace,source_pod,destination_namespace,destination_pod,port,protocol,verdict,drop_reason,dns_query,dns_rcode,bytes_sent,bytes_received\n\
+             argocd,argocd-server,kusanagi,kusanagi-app,8080,TCP,FORWARDED,,,,1024,2048\n\
+             argocd,argocd-server,kusanagi,kusanagi-app,8080,TCP,DROPPED,\"Policy \"\"deny-all\"\", egress\",,,1024,0\n"
+        );
+    }
+
+    #[test]
+    fn relay_status_serializes_with_state_tag() {
+        let status = RelayStatus::Reconnecting {
+            attempt: 2,
+            retry_in_secs: 1,
+            error: "refused".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            serde_json::json!({"state": "reconnecting", "attempt": 2, "retry_in_secs": 1, "error": "refused"})
+        );
+        assert_eq!(serde_json::to_value(RelayStatus::Connected).unwrap(), serde_json::json!({"state": "connected"}));
+    }
+
+    #[test]
+    fn dot_export_has_one_edge_per_matrix_entry() {
+        let flows = mock_flows(None, usize::MAX, true);
+        assert!(!flows.matrix.is_empty());
+
+        let dot = export_flows_dot(&flows);
+        assert!(dot.starts_with("digraph cilium_flows {"));
+        assert!(dot.trim_end().ends_with('}'));
+        assert_eq!(dot.matches(" -> ").count(), flows.matrix.len());
+
+        let dropped = flows.matrix.iter().find(|e| e.verdict == "DROPPED").unwrap();
+        let edge = format!("\"{}\" -> \"{}\" [label=\"{}/{}\", color=red];", dropped.source, dropped.destination, dropped.port, dropped.protocol);
+        assert!(dot.contains(&edge), "{}", dot);
+    }
+
+    #[test]
+    fn each_matrix_endpoint_becomes_one_node() {
+        let flows = mock_flows(None, usize::MAX, true);
+        let topology = build_topology(&flows.matrix, &flows.flows);
+
+        let mut endpoints: Vec<&String> = flows.matrix.iter().flat_map(|e| [&e.source, &e.destination]).collect();
+        endpoints.sort();
+        endpoints.dedup();
+        let ids: Vec<&String> = topology.nodes.iter().map(|n| &n.id).collect();
+        assert_eq!(ids, endpoints);
+
+        assert_eq!(topology.edges.len(), flows.matrix.len());
+        for node in &topology.nodes {
+            assert!(node.id.starts_with(&format!("{}/", node.namespace)));
+        }
+    }
+
+    #[test]
+    fn relay_url_override_is_used() {
+        let url = relay_url_or_default(Some("http://hubble-relay.cilium.svc:4245"));
+        assert_eq!(url, "http://hubble-relay.cilium.svc:4245");
+        assert_eq!(relay_service(&url), Some(("hubble-relay".to_string(), "cilium".to_string())));
+
+        assert_eq!(relay_url_or_default(None), DEFAULT_HUBBLE_RELAY_URL);
+        assert_eq!(relay_url_or_default(Some(" ")), DEFAULT_HUBBLE_RELAY_URL);
+        assert_eq!(
+            relay_service(DEFAULT_HUBBLE_RELAY_URL),
+            Some(("hubble-relay".to_string(), "kube-system".to_string()))
+        );
+        assert_eq!(relay_service("http://10.0.0.7:4245"), None);
+        assert_eq!(relay_service("http://relay:4245"), None);
+    }
+
+    #[test]
+    fn relay_address_defaults_the_port_from_the_scheme() {
+        assert_eq!(relay_address("http://10.0.0.7:4245"), Some("10.0.0.7:4245".to_string()));
+        assert_eq!(relay_address("https://relay.example.com/"), Some("relay.example.com:443".to_string()));
+        assert_eq!(relay_address("relay"), Some("relay:80".to_string()));
+        assert_eq!(relay_address("http://[::1]:4245"), Some("[::1]:4245".to_string()));
+        assert_eq!(relay_address("http://"), None);
+    }
+
+    #[tokio::test]
+    async fn relay_without_a_service_is_probed_directly() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        assert_eq!(connect_relay(None, &url).await, Ok(()));
+
+        drop(listener);
+        let err = connect_relay(None, &url).await.unwrap_err();
+        assert!(err.contains("unreachable"), "{}", err);
+
+        let err = connect_relay(None, DEFAULT_HUBBLE_RELAY_URL).await.unwrap_err();
+        assert!(err.contains("No Kubernetes client"), "{}", err);
+    }
+
+    #[test]
+    fn verdict_counts_sum_to_total_flows() {
+        let sample = mock_flows(None, usize::MAX, true);
+        let response = HubbleFlowsResponse::new(sample.flows.clone(), vec![], vec![], None);
+        assert!(response.forwarded > 0 && response.dropped > 0);
+        assert_eq!(response.forwarded + response.dropped + response.audit, response.total_flows);
+
+        // Other verdicts only count towards the total
+        let mut flows = sample.flows;
+        flows.push(NetworkFlow {
+            verdict: "ERROR".to_string(),
+            ..flows[0].clone()
+        });
+        let response = HubbleFlowsResponse::new(flows, vec![], vec![], None);
+        assert_eq!(response.forwarded + response.dropped + response.audit + 1, response.total_flows);
+    }
+
+    #[test]
+    fn label_filter_matches_source_or_destination() {
+        let base = mock_flows(None, usize::MAX, false).flows[0].clone();
+        let flow = NetworkFlow {
+            source_labels: vec!["k8s:app=argocd-server".to_string(), "k8s:tier=frontend".to_string()],
+            destination_labels: vec!["app=redis".to_string()],
+            ..base
+        };
+
+        let matches = |selector: &str| LabelFilter::parse(selector).unwrap().matches(&flow);
+        assert!(matches("app=argocd-server"));
+        assert!(matches("app=redis"));
+        assert!(matches(" tier = frontend "));
+        assert!(matches("tier"));
+        assert!(!matches("app=argocd-repo-server"));
+        assert!(!matches("team=platform"));
+        assert!(LabelFilter::parse("=x").is_err());
+    }
+
+    #[test]
+    fn cnp_parses_into_the_struct() {
+        let policy: DynamicObject = serde_json::from_value(serde_json::json!({
+            "apiVersion": "cilium.io/v2",
+            "kind": "CiliumNetworkPolicy",
+            "metadata": { "name": "allow-frontend", "namespace": "shop" },
+            "spec": {
+                "endpointSelector": { "matchLabels": { "k8s:app": "api" } },
+                "ingress": [{ "fromEndpoints": [{ "matchLabels": { "app": "frontend" } }] }]
+            },
+            "status": { "nodes": { "node-a": { "ok": true }, "node-b": { "ok": false } } }
+        }))
+        .unwrap();
+        let endpoint = |ns: &str, labels: &[&str]| (ns.to_string(), labels.iter().map(|l| l.to_string()).collect());
+        let endpoints = vec![
+            endpoint("shop", &["k8s:app=api", "k8s:io.kubernetes.pod.namespace=shop"]),
+            endpoint("shop", &["k8s:app=api"]),
+            endpoint("shop", &["k8s:app=frontend"]),
+            endpoint("other", &["k8s:app=api"]),
+        ];
+
+        let parsed = parse_network_policy(&policy, &endpoints);
+        assert_eq!(parsed.name, "allow-frontend");
+        assert_eq!(parsed.namespace, "shop");
+        assert_eq!(parsed.endpoints_matched, 2);
+        assert!(!parsed.enabled);
+        let spec: serde_json::Value = serde_json::from_str(&parsed.spec_json).unwrap();
+        assert_eq!(spec["ingress"][0]["fromEndpoints"][0]["matchLabels"]["app"], "frontend");
+    }
+}
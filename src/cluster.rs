@@ -1,11 +1,58 @@
-use k8s_openapi::api::core::v1::{Namespace, PersistentVolumeClaim};
+use k8s_openapi::api::core::v1::{Namespace, Node, PersistentVolumeClaim, Pod};
 use kube::{
     api::{Api, ListParams},
     Client,
 };
 use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use tracing::info;
 
+use crate::{apps, clusters, k8s_cache, pods};
+
+lazy_static::lazy_static! {
+    /// Namespaces left out of cluster-wide views (KUSANAGI_IGNORED_NAMESPACES)
+    static ref IGNORED_NAMESPACES: HashSet<String> = std::env::var("KUSANAGI_IGNORED_NAMESPACES")
+        .map(|value| parse_name_list(&value))
+        .unwrap_or_default();
+    /// Label key prefixes kept in node and namespace responses (NODE_LABEL_PREFIXES); None keeps all
+    static ref LABEL_PREFIXES: Option<HashSet<String>> = std::env::var("NODE_LABEL_PREFIXES")
+        .ok()
+        .map(|value| parse_name_list(&value))
+        .filter(|prefixes| !prefixes.is_empty());
+}
+
+/// Names from a comma-separated list, trimmed with empty entries dropped
+pub fn parse_name_list(value: &str) -> HashSet<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Keep only labels whose key starts with one of `prefixes`; every label when there are none
+pub fn filter_labels(labels: BTreeMap<String, String>, prefixes: Option<&HashSet<String>>) -> BTreeMap<String, String> {
+    match prefixes {
+        Some(prefixes) => labels
+            .into_iter()
+            .filter(|(key, _)| prefixes.iter().any(|p| key.starts_with(p.as_str())))
+            .collect(),
+        None => labels,
+    }
+}
+
+/// Labels trimmed to the NODE_LABEL_PREFIXES allow-list
+pub fn allowed_labels(labels: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    filter_labels(labels, LABEL_PREFIXES.as_ref())
+}
+
+/// True when `namespace` is hidden from cluster-wide results and counts
+/// Views scoped to an explicit namespace don't consult this
+pub fn is_ignored_namespace(namespace: &str) -> bool {
+    IGNORED_NAMESPACES.contains(namespace)
+}
+
 /// Cluster overview response
 #[derive(Clone, Debug, Serialize)]
 pub struct ClusterOverview {
@@ -21,6 +68,10 @@ pub struct NamespaceInfo {
     pub name: String,
     pub status: String,
     pub labels: std::collections::BTreeMap<String, String>,
+    /// RFC 3339 creation time
+    pub created_at: Option<String>,
+    /// e.g. "3h12m", to spot recently created or leftover namespaces
+    pub age: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -35,46 +86,223 @@ pub struct PvcInfo {
     pub bound_to: Option<String>,
 }
 
+/// How long the readiness probe waits for the API server
+const READINESS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Readiness probe result
+#[derive(Clone, Debug, Serialize)]
+pub struct ReadinessStatus {
+    pub status: String,
+    pub kubernetes: String,
+    pub round_trip_ms: u128,
+}
+
+/// Check Kubernetes API connectivity with a lightweight namespace list
+pub async fn check_readiness() -> Result<ReadinessStatus, String> {
+    readiness(async {
+        let client = Client::try_default()
+            .await
+            .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+
+        let ns_api: Api<Namespace> = Api::all(client);
+        ns_api
+            .list(&ListParams::default().limit(1))
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Kubernetes API unreachable: {}", e))
+    })
+    .await
+}
+
+/// Run a connectivity probe under READINESS_TIMEOUT and report its outcome
+async fn readiness<T>(probe: impl std::future::Future<Output = Result<T, String>>) -> Result<ReadinessStatus, String> {
+    let start = std::time::Instant::now();
+
+    match tokio::time::timeout(READINESS_TIMEOUT, probe).await {
+        Ok(Ok(_)) => Ok(ReadinessStatus {
+            status: "ready".to_string(),
+            kubernetes: "reachable".to_string(),
+            round_trip_ms: start.elapsed().as_millis(),
+        }),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(format!(
+            "Kubernetes API did not respond within {}s",
+            READINESS_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Check a Kubernetes label selector before sending it to the API server
+/// Accepts `key`, `!key`, `key=value`, `key==value`, `key!=value`,
+/// `key in (a,b)` and `key notin (a,b)`, comma-separated
+pub fn validate_label_selector(selector: &str) -> Result<(), String> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut terms = Vec::new();
+    for (i, c) in selector.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Err("unbalanced ')' in label selector".to_string()),
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                terms.push(&selector[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err("unbalanced '(' in label selector".to_string());
+    }
+    terms.push(&selector[start..]);
+
+    for term in terms {
+        validate_requirement(term.trim())?;
+    }
+    Ok(())
+}
+
+fn validate_requirement(term: &str) -> Result<(), String> {
+    if term.is_empty() {
+        return Err("empty requirement in label selector".to_string());
+    }
+
+    for op in [" notin ", " in "] {
+        if let Some((key, values)) = term.split_once(op) {
+            let values = values
+                .trim()
+                .strip_prefix('(')
+                .and_then(|v| v.strip_suffix(')'))
+                .ok_or_else(|| format!("expected (values) after '{}' in '{}'", op.trim(), term))?;
+            validate_label_key(key.trim())?;
+            return values
+                .split(',')
+                .try_for_each(|v| validate_label_value(v.trim()));
+        }
+    }
+
+    for op in ["!=", "==", "="] {
+        if let Some((key, value)) = term.split_once(op) {
+            validate_label_key(key.trim())?;
+            return validate_label_value(value.trim());
+        }
+    }
+
+    validate_label_key(term.strip_prefix('!').unwrap_or(term).trim())
+}
+
+fn validate_label_key(key: &str) -> Result<(), String> {
+    let (prefix, name) = match key.split_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+    if let Some(prefix) = prefix {
+        let valid_prefix = !prefix.is_empty()
+            && prefix.len() <= 253
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.');
+        if !valid_prefix {
+            return Err(format!("invalid label key prefix '{}'", prefix));
+        }
+    }
+    if name.is_empty() || !is_label_token(name) {
+        return Err(format!("invalid label key '{}'", key));
+    }
+    Ok(())
+}
+
+fn validate_label_value(value: &str) -> Result<(), String> {
+    if value.is_empty() || is_label_token(value) {
+        Ok(())
+    } else {
+        Err(format!("invalid label value '{}'", value))
+    }
+}
+
+/// Label names and values: up to 63 alphanumerics, '-', '_' or '.', starting and ending alphanumeric
+fn is_label_token(s: &str) -> bool {
+    s.len() <= 63
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && s.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && s.ends_with(|c: char| c.is_ascii_alphanumeric())
+}
+
+/// List parameters for the namespace query, optionally narrowed by a label selector
+fn namespace_list_params(label_selector: Option<&str>) -> ListParams {
+    match label_selector {
+        Some(selector) => ListParams::default().labels(selector),
+        None => ListParams::default(),
+    }
+}
+
+/// Build the API representation of a namespace, with its age relative to `now`
+pub fn namespace_info(ns: &Namespace, now: &chrono::DateTime<chrono::Utc>) -> NamespaceInfo {
+    let status = ns
+        .status
+        .as_ref()
+        .and_then(|s| s.phase.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+    let created = ns.metadata.creation_timestamp.as_ref().map(|t| t.0);
+    NamespaceInfo {
+        name: ns.metadata.name.clone().unwrap_or_default(),
+        status,
+        labels: allowed_labels(ns.metadata.labels.clone().unwrap_or_default()),
+        created_at: created.map(|t| t.to_rfc3339()),
+        age: created.map(|t| pods::format_age(now.signed_duration_since(t).num_seconds())),
+    }
+}
+
 /// Get cluster overview with namespaces and PVCs
-pub async fn get_cluster_overview() -> Result<ClusterOverview, String> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+/// `label_selector` (e.g. `team=platform`) limits the namespaces returned
+pub async fn get_cluster_overview(
+    cluster: Option<&str>,
+    label_selector: Option<&str>,
+) -> Result<ClusterOverview, String> {
+    let client = clusters::client(cluster).await.map_err(|e| e.to_string())?;
 
     let ns_api: Api<Namespace> = Api::all(client.clone());
-    let pvc_api: Api<PersistentVolumeClaim> = Api::all(client);
 
     // Get namespaces
     let namespaces = ns_api
-        .list(&ListParams::default())
+        .list(&namespace_list_params(label_selector))
         .await
         .map_err(|e| format!("Failed to list namespaces: {}", e))?;
 
-    let namespace_infos: Vec<NamespaceInfo> = namespaces
-        .items
-        .iter()
-        .map(|ns| {
-            let name = ns.metadata.name.clone().unwrap_or_default();
-            let status = ns
-                .status
-                .as_ref()
-                .and_then(|s| s.phase.clone())
-                .unwrap_or_else(|| "Unknown".to_string());
-            let labels = ns.metadata.labels.clone().unwrap_or_default();
-            NamespaceInfo { name, status, labels }
-        })
-        .collect();
-
     // Get PVCs
-    let pvcs = pvc_api
-        .list(&ListParams::default())
+    let pvcs = k8s_cache::list_pvcs(client, cluster, None)
         .await
         .map_err(|e| format!("Failed to list PVCs: {}", e))?;
 
+    let overview = build_overview(&namespaces.items, &pvcs, &chrono::Utc::now(), is_ignored_namespace);
+    info!(
+        "Cluster overview: {} namespaces, {} PVCs ({})",
+        overview.namespace_count,
+        overview.pvc_count,
+        overview.pvc_total_capacity
+    );
+
+    Ok(overview)
+}
+
+/// Overview of the listed namespaces and PVCs, leaving out those in namespaces `is_ignored` hides
+fn build_overview(
+    namespaces: &[Namespace],
+    pvcs: &[PersistentVolumeClaim],
+    now: &chrono::DateTime<chrono::Utc>,
+    is_ignored: impl Fn(&str) -> bool,
+) -> ClusterOverview {
+    let namespace_infos: Vec<NamespaceInfo> = namespaces
+        .iter()
+        .filter(|ns| !is_ignored(ns.metadata.name.as_deref().unwrap_or_default()))
+        .map(|ns| namespace_info(ns, now))
+        .collect();
+
     let mut total_bytes: i64 = 0;
     let pvc_infos: Vec<PvcInfo> = pvcs
-        .items
         .iter()
+        .filter(|pvc| !is_ignored(pvc.metadata.namespace.as_deref().unwrap_or_default()))
         .map(|pvc| {
             let name = pvc.metadata.name.clone().unwrap_or_default();
             let namespace = pvc.metadata.namespace.clone().unwrap_or_default();
@@ -127,45 +355,144 @@ pub async fn get_cluster_overview() -> Result<ClusterOverview, String> {
 
     let pvc_total_capacity = format_bytes(total_bytes);
 
-    info!(
-        "Cluster overview: {} namespaces, {} PVCs ({})",
-        namespace_infos.len(),
-        pvc_infos.len(),
-        pvc_total_capacity
-    );
-
-    Ok(ClusterOverview {
+    ClusterOverview {
         namespace_count: namespace_infos.len(),
         namespaces: namespace_infos,
         pvc_count: pvc_infos.len(),
         pvc_total_capacity,
         pvcs: pvc_infos,
-    })
+    }
 }
 
-/// Parse capacity string (e.g., "10Gi", "500Mi") to bytes
-fn parse_capacity_to_bytes(capacity: &str) -> i64 {
-    let trimmed = capacity.trim();
-    
-    if trimmed.ends_with("Ti") {
-        let value: f64 = trimmed.trim_end_matches("Ti").parse().unwrap_or(0.0);
-        (value * 1024.0 * 1024.0 * 1024.0 * 1024.0) as i64
-    } else if trimmed.ends_with("Gi") {
-        let value: f64 = trimmed.trim_end_matches("Gi").parse().unwrap_or(0.0);
-        (value * 1024.0 * 1024.0 * 1024.0) as i64
-    } else if trimmed.ends_with("Mi") {
-        let value: f64 = trimmed.trim_end_matches("Mi").parse().unwrap_or(0.0);
-        (value * 1024.0 * 1024.0) as i64
-    } else if trimmed.ends_with("Ki") {
-        let value: f64 = trimmed.trim_end_matches("Ki").parse().unwrap_or(0.0);
-        (value * 1024.0) as i64
-    } else {
-        trimmed.parse().unwrap_or(0)
+/// Node allocatable CPU/memory against the requests and limits of running pods
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CapacitySummary {
+    pub node_count: usize,
+    pub pod_count: usize,
+    pub cpu_allocatable_millis: i64,
+    pub cpu_requests_millis: i64,
+    pub cpu_limits_millis: i64,
+    pub memory_allocatable_bytes: i64,
+    pub memory_requests_bytes: i64,
+    pub memory_limits_bytes: i64,
+    /// requests / allocatable (1.0 = nothing left to schedule)
+    pub cpu_request_ratio: f64,
+    pub memory_request_ratio: f64,
+    /// limits / allocatable (above 1.0 = overcommitted)
+    pub cpu_limit_ratio: f64,
+    pub memory_limit_ratio: f64,
+    /// allocatable - requests: what new pods can still request
+    pub cpu_headroom_millis: i64,
+    pub memory_headroom_bytes: i64,
+}
+
+/// Ratio rounded to three decimals, 0 when there is no capacity
+fn capacity_ratio(used: i64, capacity: i64) -> f64 {
+    if capacity <= 0 {
+        return 0.0;
+    }
+    (used as f64 / capacity as f64 * 1000.0).round() / 1000.0
+}
+
+/// Sum node allocatable and the container requests/limits of pods that still hold resources
+/// Succeeded and Failed pods are skipped since the scheduler no longer counts them
+pub fn summarize_capacity(nodes: &[Node], pods: &[Pod]) -> CapacitySummary {
+    let mut summary = CapacitySummary {
+        node_count: nodes.len(),
+        ..Default::default()
+    };
+
+    for allocatable in nodes.iter().filter_map(|n| n.status.as_ref()?.allocatable.as_ref()) {
+        summary.cpu_allocatable_millis += allocatable.get("cpu").map_or(0, |q| apps::parse_cpu(&q.0));
+        summary.memory_allocatable_bytes += allocatable.get("memory").map_or(0, |q| parse_capacity_to_bytes(&q.0));
+    }
+
+    let active = pods.iter().filter(|p| {
+        let phase = p.status.as_ref().and_then(|s| s.phase.as_deref());
+        !matches!(phase, Some("Succeeded") | Some("Failed"))
+    });
+    for pod in active {
+        summary.pod_count += 1;
+        let containers = pod.spec.iter().flat_map(|s| s.containers.iter());
+        for resources in containers.filter_map(|c| c.resources.as_ref()) {
+            if let Some(requests) = &resources.requests {
+                summary.cpu_requests_millis += requests.get("cpu").map_or(0, |q| apps::parse_cpu(&q.0));
+                summary.memory_requests_bytes += requests.get("memory").map_or(0, |q| parse_capacity_to_bytes(&q.0));
+            }
+            if let Some(limits) = &resources.limits {
+                summary.cpu_limits_millis += limits.get("cpu").map_or(0, |q| apps::parse_cpu(&q.0));
+                summary.memory_limits_bytes += limits.get("memory").map_or(0, |q| parse_capacity_to_bytes(&q.0));
+            }
+        }
+    }
+
+    summary.cpu_request_ratio = capacity_ratio(summary.cpu_requests_millis, summary.cpu_allocatable_millis);
+    summary.memory_request_ratio = capacity_ratio(summary.memory_requests_bytes, summary.memory_allocatable_bytes);
+    summary.cpu_limit_ratio = capacity_ratio(summary.cpu_limits_millis, summary.cpu_allocatable_millis);
+    summary.memory_limit_ratio = capacity_ratio(summary.memory_limits_bytes, summary.memory_allocatable_bytes);
+    summary.cpu_headroom_millis = summary.cpu_allocatable_millis - summary.cpu_requests_millis;
+    summary.memory_headroom_bytes = summary.memory_allocatable_bytes - summary.memory_requests_bytes;
+    summary
+}
+
+/// Cluster-wide capacity vs requests: "can I schedule more?"
+pub async fn get_capacity_summary(cluster: Option<&str>) -> Result<CapacitySummary, String> {
+    let client = clusters::client(cluster).await.map_err(|e| e.to_string())?;
+
+    let (nodes, pods) = tokio::join!(
+        k8s_cache::list_nodes(client.clone(), cluster),
+        k8s_cache::list_pods(client, cluster, None)
+    );
+    let nodes = nodes.map_err(|e| format!("Failed to list nodes: {}", e))?;
+    let pods = pods.map_err(|e| format!("Failed to list pods: {}", e))?;
+
+    let summary = summarize_capacity(&nodes, &pods);
+    info!(
+        "Cluster capacity: CPU requests {:.0}%, memory requests {:.0}%",
+        summary.cpu_request_ratio * 100.0,
+        summary.memory_request_ratio * 100.0
+    );
+    Ok(summary)
+}
+
+/// Parse a Kubernetes quantity (e.g. "10Gi", "500M", "1.5Ti", "128974848") to bytes
+/// Accepts binary suffixes (Ki..Ei), decimal suffixes (k..E), milli ("m") and plain or exponent numbers
+pub fn parse_quantity_bytes(quantity: &str) -> Result<u64, String> {
+    const SUFFIXES: [(&str, f64); 13] = [
+        ("Ki", 1024.0),
+        ("Mi", 1048576.0),
+        ("Gi", 1073741824.0),
+        ("Ti", 1099511627776.0),
+        ("Pi", 1125899906842624.0),
+        ("Ei", 1152921504606846976.0),
+        ("m", 1e-3),
+        ("k", 1e3),
+        ("M", 1e6),
+        ("G", 1e9),
+        ("T", 1e12),
+        ("P", 1e15),
+        ("E", 1e18),
+    ];
+
+    let quantity = quantity.trim();
+    let (number, multiplier) = SUFFIXES
+        .iter()
+        .find_map(|(suffix, multiplier)| quantity.strip_suffix(suffix).map(|n| (n, *multiplier)))
+        .unwrap_or((quantity, 1.0));
+
+    match number.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Ok((value * multiplier) as u64),
+        _ => Err(format!("Invalid quantity '{}': expected a value such as 20Gi or 500M", quantity)),
     }
 }
 
+/// Parse a quantity to bytes, counting anything unparseable as zero
+pub fn parse_capacity_to_bytes(capacity: &str) -> i64 {
+    parse_quantity_bytes(capacity).map_or(0, |bytes| bytes.min(i64::MAX as u64) as i64)
+}
+
 /// Format bytes to human-readable string
-fn format_bytes(bytes: i64) -> String {
+pub fn format_bytes(bytes: i64) -> String {
     const TI: i64 = 1024 * 1024 * 1024 * 1024;
     const GI: i64 = 1024 * 1024 * 1024;
     const MI: i64 = 1024 * 1024;
@@ -180,3 +507,188 @@ fn format_bytes(bytes: i64) -> String {
         format!("{} bytes", bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantities_parse_binary_and_decimal_suffixes() {
+        assert_eq!(parse_quantity_bytes("10Gi"), Ok(10 * 1024 * 1024 * 1024));
+        assert_eq!(parse_quantity_bytes("1.5Ki"), Ok(1536));
+        assert_eq!(parse_quantity_bytes("2Ti"), Ok(2 * 1024u64.pow(4)));
+        assert_eq!(parse_quantity_bytes("500M"), Ok(500_000_000));
+        assert_eq!(parse_quantity_bytes("1G"), Ok(1_000_000_000));
+        assert_eq!(parse_quantity_bytes("3k"), Ok(3000));
+        assert_eq!(parse_quantity_bytes(" 128974848 "), Ok(128974848));
+        assert_eq!(parse_quantity_bytes("1e3"), Ok(1000));
+        assert_eq!(parse_quantity_bytes("1500m"), Ok(1));
+        assert_eq!(parse_quantity_bytes("0"), Ok(0));
+    }
+
+    #[test]
+    fn invalid_quantities_are_rejected() {
+        for bad in ["", "Gi", "ten", "-1Gi", "10Xi"] {
+            assert!(parse_quantity_bytes(bad).is_err(), "{:?} should not parse", bad);
+        }
+        assert_eq!(parse_capacity_to_bytes("ten"), 0);
+        assert_eq!(parse_capacity_to_bytes("1Mi"), 1048576);
+    }
+
+    #[test]
+    fn only_allow_listed_labels_survive() {
+        let labels: BTreeMap<String, String> = [
+            ("topology.kubernetes.io/zone", "eu-west-1a"),
+            ("node.kubernetes.io/instance-type", "m5.large"),
+            ("eks.amazonaws.com/nodegroup", "workers"),
+            ("kubernetes.io/hostname", "node-1"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let prefixes = parse_name_list("topology.kubernetes.io, node.kubernetes.io,");
+        let kept: Vec<String> = filter_labels(labels.clone(), Some(&prefixes)).into_keys().collect();
+        assert_eq!(kept, vec!["node.kubernetes.io/instance-type", "topology.kubernetes.io/zone"]);
+
+        assert_eq!(filter_labels(labels.clone(), None), labels);
+    }
+
+    #[tokio::test]
+    async fn readiness_reports_reachable_api() {
+        let status = readiness(async { Ok::<_, String>(()) }).await.unwrap();
+        assert_eq!((status.status.as_str(), status.kubernetes.as_str()), ("ready", "reachable"));
+    }
+
+    #[tokio::test]
+    async fn readiness_is_degraded_when_api_unreachable() {
+        let err = readiness(async { Err::<(), _>("Kubernetes API unreachable: connection refused".to_string()) })
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Kubernetes API unreachable: connection refused");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn readiness_times_out_on_a_hung_api() {
+        let err = readiness(std::future::pending::<Result<(), String>>()).await.unwrap_err();
+        assert_eq!(err, "Kubernetes API did not respond within 3s");
+    }
+
+    #[test]
+    fn label_selector_is_applied_to_list_params() {
+        let params = namespace_list_params(Some("team=platform"));
+        assert_eq!(params.label_selector.as_deref(), Some("team=platform"));
+        assert_eq!(namespace_list_params(None).label_selector, None);
+    }
+
+    #[test]
+    fn label_selectors_are_validated() {
+        for ok in ["team=platform", "team=", "team==platform,tier!=db", "env in (prod, staging)", "!legacy", "app.kubernetes.io/name notin (a,b)"] {
+            assert!(validate_label_selector(ok).is_ok(), "{:?} should be valid", ok);
+        }
+        for bad in ["", "=platform", "team=plat form", "env in prod", "env in (prod", "a,,b", "-team=x"] {
+            assert!(validate_label_selector(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn capacity_sums_nodes_and_active_pods() {
+        let node = |cpu: &str, memory: &str| -> Node {
+            serde_json::from_value(serde_json::json!({
+                "metadata": { "name": "node" },
+                "status": { "allocatable": { "cpu": cpu, "memory": memory } }
+            }))
+            .unwrap()
+        };
+        let container = |requests: (&str, &str), limits: (&str, &str)| {
+            serde_json::json!({
+                "name": "app",
+                "resources": {
+                    "requests": { "cpu": requests.0, "memory": requests.1 },
+                    "limits": { "cpu": limits.0, "memory": limits.1 }
+                }
+            })
+        };
+        let pod = |phase: &str, containers: Vec<serde_json::Value>| -> Pod {
+            serde_json::from_value(serde_json::json!({
+                "metadata": { "name": "pod" },
+                "spec": { "containers": containers },
+                "status": { "phase": phase }
+            }))
+            .unwrap()
+        };
+
+        let nodes = vec![node("4", "8Gi"), node("2000m", "8Gi")];
+        let pods = vec![
+            pod(
+                "Running",
+                vec![container(("250m", "1Gi"), ("500m", "2Gi")), container(("250m", "1Gi"), ("500m", "2Gi"))],
+            ),
+            pod("Pending", vec![container(("1500m", "2Gi"), ("4", "4Gi"))]),
+            pod("Succeeded", vec![container(("8", "32Gi"), ("8", "32Gi"))]),
+        ];
+
+        let summary = summarize_capacity(&nodes, &pods);
+        let gi = 1024 * 1024 * 1024;
+        assert_eq!((summary.node_count, summary.pod_count), (2, 2));
+        assert_eq!(summary.cpu_allocatable_millis, 6000);
+        assert_eq!(summary.memory_allocatable_bytes, 16 * gi);
+        assert_eq!((summary.cpu_requests_millis, summary.cpu_limits_millis), (2000, 5000));
+        assert_eq!((summary.memory_requests_bytes, summary.memory_limits_bytes), (4 * gi, 8 * gi));
+        assert_eq!((summary.cpu_request_ratio, summary.memory_request_ratio), (0.333, 0.25));
+        assert_eq!((summary.cpu_limit_ratio, summary.memory_limit_ratio), (0.833, 0.5));
+        assert_eq!((summary.cpu_headroom_millis, summary.memory_headroom_bytes), (4000, 12 * gi));
+    }
+
+    #[test]
+    fn capacity_ratio_is_zero_without_capacity() {
+        assert_eq!(capacity_ratio(500, 0), 0.0);
+        assert_eq!(capacity_ratio(9000, 6000), 1.5);
+    }
+
+    #[test]
+    fn namespace_age_is_formatted_from_creation() {
+        let ns: Namespace = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "leftover", "creationTimestamp": "2024-03-01T09:15:00Z" },
+            "status": { "phase": "Active" }
+        }))
+        .unwrap();
+        let now: chrono::DateTime<chrono::Utc> = "2024-03-01T12:45:00Z".parse().unwrap();
+
+        let info = namespace_info(&ns, &now);
+        assert_eq!(info.age.as_deref(), Some("3h30m"));
+        assert_eq!(info.created_at.as_deref(), Some("2024-03-01T09:15:00+00:00"));
+        assert_eq!(info.status, "Active");
+
+        let unknown = namespace_info(&Namespace::default(), &now);
+        assert_eq!((unknown.age, unknown.created_at), (None, None));
+    }
+
+    #[test]
+    fn overview_counts_leave_out_ignored_namespaces() {
+        let namespace = |name: &str| -> Namespace {
+            serde_json::from_value(serde_json::json!({"metadata": {"name": name}, "status": {"phase": "Active"}})).unwrap()
+        };
+        let pvc = |namespace: &str, size: &str| -> PersistentVolumeClaim {
+            serde_json::from_value(serde_json::json!({
+                "metadata": {"name": "data", "namespace": namespace},
+                "status": {"phase": "Bound", "capacity": {"storage": size}}
+            }))
+            .unwrap()
+        };
+        let namespaces = [namespace("default"), namespace("kube-system"), namespace("apps")];
+        let pvcs = [pvc("apps", "1Gi"), pvc("kube-system", "10Gi"), pvc("default", "1Gi")];
+
+        let ignored = parse_name_list("kube-system");
+        let overview = build_overview(&namespaces, &pvcs, &chrono::Utc::now(), |ns| ignored.contains(ns));
+        assert_eq!(overview.namespace_count, 2);
+        let names: Vec<&str> = overview.namespaces.iter().map(|ns| ns.name.as_str()).collect();
+        assert_eq!(names, ["default", "apps"]);
+        assert_eq!(overview.pvc_count, 2);
+        assert!(overview.pvcs.iter().all(|pvc| pvc.namespace != "kube-system"));
+        assert_eq!(overview.pvc_total_capacity, format_bytes(2 * 1024 * 1024 * 1024));
+
+        let overview = build_overview(&namespaces, &pvcs, &chrono::Utc::now(), |_| false);
+        assert_eq!((overview.namespace_count, overview.pvc_count), (3, 3));
+    }
+}
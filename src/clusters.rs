@@ -0,0 +1,160 @@
+//! Kubernetes clients for additional clusters
+//! KUSANAGI_CLUSTERS lists kubeconfig context names (comma-separated); each one gets
+//! its own client so status endpoints can target it with `?cluster=<context>`.
+
+use kube::{
+    config::{KubeConfigOptions, Kubeconfig},
+    Client, Config,
+};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::error::KusanagiError;
+
+lazy_static::lazy_static! {
+    /// Clients for the contexts listed in KUSANAGI_CLUSTERS, keyed by context name
+    static ref CLUSTERS: RwLock<HashMap<String, Client>> = RwLock::new(HashMap::new());
+}
+
+/// Context names from a KUSANAGI_CLUSTERS value, trimmed and de-duplicated
+pub fn parse_context_names(value: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Client configuration for one kubeconfig context
+async fn config_for_context(kubeconfig: &Kubeconfig, context: &str) -> Result<Config, String> {
+    let options = KubeConfigOptions {
+        context: Some(context.to_string()),
+        ..Default::default()
+    };
+    Config::from_custom_kubeconfig(kubeconfig.clone(), &options)
+        .await
+        .map_err(|e| format!("Failed to load kubeconfig context {}: {}", context, e))
+}
+
+/// Build a client for one kubeconfig context
+async fn client_for_context(kubeconfig: &Kubeconfig, context: &str) -> Result<Client, String> {
+    let config = config_for_context(kubeconfig, context).await?;
+    Client::try_from(config)
+        .map_err(|e| format!("Failed to create client for context {}: {}", context, e))
+}
+
+/// Load a client for each context in KUSANAGI_CLUSTERS
+/// Contexts that fail to load are logged and skipped so one bad entry doesn't block startup
+pub async fn load_clusters() {
+    let names = match std::env::var("KUSANAGI_CLUSTERS") {
+        Ok(value) => parse_context_names(&value),
+        Err(_) => return,
+    };
+    if names.is_empty() {
+        return;
+    }
+
+    let kubeconfig = match Kubeconfig::read() {
+        Ok(kubeconfig) => kubeconfig,
+        Err(e) => {
+            warn!("KUSANAGI_CLUSTERS is set but no kubeconfig could be read: {}", e);
+            return;
+        }
+    };
+
+    let mut clusters = CLUSTERS.write().await;
+    for name in names {
+        match client_for_context(&kubeconfig, &name).await {
+            Ok(client) => {
+                clusters.insert(name, client);
+            }
+            Err(e) => warn!("{}", e),
+        }
+    }
+
+    info!("Loaded {} additional cluster(s)", clusters.len());
+}
+
+/// Names of the clusters that can be selected with `?cluster=`
+pub async fn cluster_names() -> Vec<String> {
+    let mut names: Vec<String> = CLUSTERS.read().await.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Client for the selected cluster, or the current context when `cluster` is None
+pub async fn client(cluster: Option<&str>) -> Result<Client, KusanagiError> {
+    match cluster {
+        Some(name) => CLUSTERS
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| KusanagiError::NotFound(format!("Unknown cluster: {}", name))),
+        None => Client::try_default()
+            .await
+            .map_err(|e| KusanagiError::kube("Failed to create Kubernetes client", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KUBECONFIG: &str = r#"
+apiVersion: v1
+kind: Config
+current-context: staging
+clusters:
+  - name: staging-cluster
+    cluster:
+      server: https://staging.example.com:6443
+  - name: prod-cluster
+    cluster:
+      server: https://prod.example.com:6443
+contexts:
+  - name: staging
+    context:
+      cluster: staging-cluster
+      user: admin
+      namespace: apps
+  - name: prod
+    context:
+      cluster: prod-cluster
+      user: admin
+users:
+  - name: admin
+    user:
+      token: secret
+"#;
+
+    #[test]
+    fn context_names_are_trimmed_and_deduplicated() {
+        assert_eq!(parse_context_names(" prod, staging ,,prod,"), vec!["prod", "staging"]);
+        assert!(parse_context_names(" , ").is_empty());
+    }
+
+    #[tokio::test]
+    async fn each_context_loads_its_own_cluster() {
+        let kubeconfig = Kubeconfig::from_yaml(KUBECONFIG).unwrap();
+
+        let prod = config_for_context(&kubeconfig, "prod").await.unwrap();
+        assert_eq!(prod.cluster_url.host(), Some("prod.example.com"));
+
+        let staging = config_for_context(&kubeconfig, "staging").await.unwrap();
+        assert_eq!(staging.cluster_url.host(), Some("staging.example.com"));
+        assert_eq!(staging.default_namespace, "apps");
+
+        let err = config_for_context(&kubeconfig, "dev").await.unwrap_err();
+        assert!(err.starts_with("Failed to load kubeconfig context dev"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn unknown_cluster_is_not_found() {
+        let result = client(Some("nowhere")).await;
+        assert!(matches!(result, Err(KusanagiError::NotFound(_))));
+    }
+}
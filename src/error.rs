@@ -0,0 +1,123 @@
+//! Typed errors for Kusanagi
+//! Lets handlers `?`-propagate failures and map them to the right HTTP status
+
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use std::fmt;
+
+/// Error returned by Kusanagi modules
+#[derive(Debug, Clone)]
+pub enum KusanagiError {
+    /// The requested resource does not exist
+    NotFound(String),
+    /// An upstream system (Kubernetes, Prometheus, ...) failed or is unreachable
+    Upstream(String),
+    /// The agent is not allowed to perform the operation
+    Unauthorized(String),
+    /// Input could not be parsed or validated
+    Parse(String),
+    /// The cluster lacks an optional component the operation needs (e.g. metrics-server)
+    NotImplemented(String),
+    /// Unexpected internal failure
+    Internal(String),
+}
+
+impl KusanagiError {
+    /// Map a kube error to the matching variant, prefixed with some context
+    pub fn kube(context: &str, e: kube::Error) -> Self {
+        let message = format!("{}: {}", context, e);
+        match &e {
+            kube::Error::Api(resp) if resp.code == 404 => KusanagiError::NotFound(message),
+            kube::Error::Api(resp) if resp.code == 401 || resp.code == 403 => {
+                KusanagiError::Unauthorized(message)
+            }
+            _ => KusanagiError::Upstream(message),
+        }
+    }
+
+    /// The error message without the variant
+    pub fn message(&self) -> &str {
+        match self {
+            KusanagiError::NotFound(m)
+            | KusanagiError::Upstream(m)
+            | KusanagiError::Unauthorized(m)
+            | KusanagiError::Parse(m)
+            | KusanagiError::NotImplemented(m)
+            | KusanagiError::Internal(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for KusanagiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for KusanagiError {}
+
+impl From<String> for KusanagiError {
+    fn from(message: String) -> Self {
+        KusanagiError::Internal(message)
+    }
+}
+
+impl ResponseError for KusanagiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            KusanagiError::NotFound(_) => StatusCode::NOT_FOUND,
+            KusanagiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            KusanagiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            KusanagiError::Parse(_) => StatusCode::BAD_REQUEST,
+            KusanagiError::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
+            KusanagiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        tracing::error!("{}", self);
+        HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "error": self.message()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(code: u16) -> kube::Error {
+        kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".to_string(),
+            message: "test".to_string(),
+            reason: "test".to_string(),
+            code,
+        })
+    }
+
+    #[test]
+    fn variants_map_to_status_codes() {
+        let cases = [
+            (KusanagiError::NotFound("x".into()), StatusCode::NOT_FOUND),
+            (KusanagiError::Upstream("x".into()), StatusCode::BAD_GATEWAY),
+            (KusanagiError::Unauthorized("x".into()), StatusCode::UNAUTHORIZED),
+            (KusanagiError::Parse("x".into()), StatusCode::BAD_REQUEST),
+            (KusanagiError::NotImplemented("x".into()), StatusCode::NOT_IMPLEMENTED),
+            (KusanagiError::Internal("x".into()), StatusCode::INTERNAL_SERVER_ERROR),
+        ];
+        for (error, status) in cases {
+            assert_eq!(error.status_code(), status, "{:?}", error);
+            assert_eq!(error.error_response().status(), status);
+        }
+    }
+
+    #[test]
+    fn kube_errors_map_by_api_code() {
+        assert!(matches!(KusanagiError::kube("get", api_error(404)), KusanagiError::NotFound(_)));
+        assert!(matches!(KusanagiError::kube("get", api_error(401)), KusanagiError::Unauthorized(_)));
+        assert!(matches!(KusanagiError::kube("get", api_error(403)), KusanagiError::Unauthorized(_)));
+        assert!(matches!(KusanagiError::kube("get", api_error(500)), KusanagiError::Upstream(_)));
+        assert!(KusanagiError::kube("Failed to get pod", api_error(404))
+            .message()
+            .starts_with("Failed to get pod: "));
+    }
+}
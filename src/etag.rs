@@ -0,0 +1,112 @@
+//! Conditional GET for polled JSON endpoints
+//! The body is still computed, but an unchanged one is answered with 304 and no payload.
+//! Ages and uptimes tick every second, so they are left out of the comparison.
+
+use actix_web::{http::header, HttpRequest, HttpResponse};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Fields derived from the current time; a response where only these moved counts as unchanged
+const TIME_DERIVED_FIELDS: &[&str] = &["age", "age_seconds", "uptime", "uptime_seconds"];
+
+/// Remove time-derived fields from a JSON value, at any depth
+pub fn without_time_fields(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|key, _| !TIME_DERIVED_FIELDS.contains(&key.as_str()));
+            map.values_mut().for_each(without_time_fields);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(without_time_fields),
+        _ => {}
+    }
+}
+
+/// Weak ETag for a serialized body; only stable within one process, which is all polling needs
+pub fn weak_etag(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+/// Whether an If-None-Match value matches `etag`, using weak comparison
+pub fn if_none_match(header_value: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    header_value
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// ETag of a value, ignoring time-derived fields
+pub fn etag_for<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    let mut projection = serde_json::to_value(value)?;
+    without_time_fields(&mut projection);
+    Ok(weak_etag(&serde_json::to_vec(&projection)?))
+}
+
+/// 200 with the JSON body and its ETag, or 304 when the client already has it
+/// (ages in the client's copy may then be slightly behind)
+pub fn json_response<T: Serialize>(req: &HttpRequest, value: &T) -> HttpResponse {
+    let (body, etag) = match serde_json::to_vec(value).and_then(|body| Ok((body, etag_for(value)?))) {
+        Ok(tagged) => tagged,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to serialize response: {}", e)
+            }))
+        }
+    };
+
+    let unchanged = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| if_none_match(v, &etag));
+    if unchanged {
+        return HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .content_type("application/json")
+        .body(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test::TestRequest};
+    use serde_json::json;
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let etag = weak_etag(b"{}");
+        assert!(if_none_match(&etag, &etag));
+        assert!(if_none_match(etag.trim_start_matches("W/"), &etag));
+        assert!(if_none_match(&format!("\"other\", {}", etag), &etag));
+        assert!(if_none_match("*", &etag));
+        assert!(!if_none_match("W/\"other\"", &etag));
+    }
+
+    #[test]
+    fn repeated_request_gets_304_even_as_ages_tick() {
+        let first = json!({"pods_in_error": [{"name": "web", "age": "5m", "age_seconds": 300}]});
+        let response = json_response(&TestRequest::default().to_http_request(), &first);
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers().get(header::ETAG).unwrap().to_str().unwrap().to_string();
+
+        let aged = json!({"pods_in_error": [{"name": "web", "age": "6m", "age_seconds": 360}]});
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_http_request();
+        assert_eq!(json_response(&req, &aged).status(), StatusCode::NOT_MODIFIED);
+
+        let changed = json!({"pods_in_error": [{"name": "api", "age": "6m", "age_seconds": 360}]});
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_http_request();
+        assert_eq!(json_response(&req, &changed).status(), StatusCode::OK);
+    }
+}
@@ -1,11 +1,46 @@
 use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use k8s_openapi::api::core::v1::Event;
 use kube::{
     api::{Api, ListParams},
+    runtime::{
+        reflector::{self, ObjectRef, Store},
+        watcher, WatchStreamExt,
+    },
     Client,
 };
-use serde::Serialize;
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, warn};
+
+use crate::error::KusanagiError;
+use crate::{cluster, clusters};
+
+/// How far back /api/events and the grouped view look
+const EVENT_WINDOW_HOURS: i64 = 1;
+
+/// Delay before re-creating the watch after the client or stream goes away
+const WATCH_RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Changed events buffered per live-events subscriber before it starts lagging
+const EVENT_UPDATES_CAPACITY: usize = 256;
+
+lazy_static::lazy_static! {
+    /// Mirror of the cluster's events kept up to date by `watch_events`
+    /// Deleted events leave it too, so it stays as small as the API server's own event TTL
+    static ref EVENT_STORE: RwLock<Option<Store<Event>>> = RwLock::new(None);
+    /// New and changed events from the shared watch, for /ws/events
+    static ref EVENT_UPDATES: broadcast::Sender<Arc<Event>> = broadcast::channel(EVENT_UPDATES_CAPACITY).0;
+}
+
+/// Receive every new or changed event seen by the shared watch
+/// Re-lists after a reconnect are not repeated; only events that actually changed are sent
+pub fn subscribe() -> broadcast::Receiver<Arc<Event>> {
+    EVENT_UPDATES.subscribe()
+}
 
 /// Events response
 #[derive(Clone, Debug, Serialize)]
@@ -31,91 +66,111 @@ pub struct EventInfo {
     pub age: Option<String>,
 }
 
-/// Get recent Kubernetes events (last 1 hour, warnings prioritized)
-/// Optionally filter by event type (e.g., "Warning" or "Normal")
-pub async fn get_events(event_type_filter: Option<String>) -> Result<EventsResponse, String> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+/// Start a background watch that keeps the in-memory event store current.
+/// Watches all namespaces when `namespace` is None. The watcher re-lists and
+/// backs off on its own after errors; if the stream ends we start a new one.
+pub fn watch_events(namespace: Option<&str>) {
+    let namespace = namespace.map(|ns| ns.to_string());
 
-    let events_api: Api<Event> = Api::all(client);
+    tokio::spawn(async move {
+        loop {
+            let client = match Client::try_default().await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Event watcher: failed to create Kubernetes client: {}", e);
+                    tokio::time::sleep(WATCH_RESTART_DELAY).await;
+                    continue;
+                }
+            };
 
-    let events = events_api
-        .list(&ListParams::default())
-        .await
-        .map_err(|e| format!("Failed to list events: {}", e))?;
+            let events_api: Api<Event> = match &namespace {
+                Some(ns) => Api::namespaced(client, ns),
+                None => Api::all(client),
+            };
 
-    let now = Utc::now();
-    let one_hour_ago = now - chrono::Duration::hours(1);
+            info!("Event watcher started (namespace: {})", namespace.as_deref().unwrap_or("all"));
 
-    let mut event_infos: Vec<EventInfo> = events
-        .items
-        .iter()
-        .filter_map(|event| {
-            // Get the most recent timestamp
-            let last_ts = event
-                .last_timestamp
-                .as_ref()
-                .and_then(|t| DateTime::parse_from_rfc3339(&t.0.to_rfc3339()).ok())
-                .map(|t| t.with_timezone(&Utc));
-
-            // Filter events from last hour
-            if let Some(ts) = last_ts {
-                if ts < one_hour_ago {
-                    return None;
+            let (store, mut writer) = reflector::store();
+            *EVENT_STORE.write().await = Some(store.clone());
+
+            let mut stream = watcher(events_api, watcher::Config::default())
+                .default_backoff()
+                .boxed();
+
+            while let Some(item) = stream.next().await {
+                match item {
+                    Ok(change) => {
+                        // Compare against the store before applying, so a re-list doesn't
+                        // announce events subscribers have already seen
+                        let updated: Vec<Event> = if EVENT_UPDATES.receiver_count() > 0 {
+                            change
+                                .clone()
+                                .into_iter_applied()
+                                .filter(|event| is_update(store.get(&ObjectRef::from_obj(event)).as_deref(), event))
+                                .collect()
+                        } else {
+                            Vec::new()
+                        };
+                        writer.apply_watcher_event(&change);
+                        for event in updated {
+                            // No subscribers left is not an error
+                            let _ = EVENT_UPDATES.send(Arc::new(event));
+                        }
+                    }
+                    Err(e) => warn!("Event watcher error, reconnecting: {}", e),
                 }
             }
 
-            let name = event.metadata.name.clone().unwrap_or_default();
-            let namespace = event.metadata.namespace.clone().unwrap_or_else(|| "default".to_string());
-            
-            let event_type = event.type_.clone().unwrap_or_else(|| "Normal".to_string());
-            let reason = event.reason.clone().unwrap_or_default();
-            let message = event.message.clone().unwrap_or_default();
-            
-            let involved_object_kind = event
-                .involved_object
-                .kind
-                .clone()
-                .unwrap_or_default();
-            let involved_object_name = event
-                .involved_object
-                .name
-                .clone()
-                .unwrap_or_default();
-            
-            let count = event.count.unwrap_or(1);
-            
-            let first_timestamp = event
-                .first_timestamp
-                .as_ref()
-                .map(|t| t.0.to_rfc3339());
-            let last_timestamp = event
-                .last_timestamp
-                .as_ref()
-                .map(|t| t.0.to_rfc3339());
-
-            // Calculate age from last timestamp
-            let age = last_ts.map(|ts| {
-                let duration = now.signed_duration_since(ts);
-                format_duration(duration)
-            });
-
-            Some(EventInfo {
-                name,
-                namespace,
-                event_type,
-                reason,
-                message,
-                involved_object_kind,
-                involved_object_name,
-                count,
-                first_timestamp,
-                last_timestamp,
-                age,
-            })
-        })
-        .collect();
+            warn!("Event watch stream ended, restarting");
+            tokio::time::sleep(WATCH_RESTART_DELAY).await;
+        }
+    });
+}
+
+/// Whether `event` is new or differs from the stored copy (by resourceVersion)
+pub fn is_update(previous: Option<&Event>, event: &Event) -> bool {
+    previous.is_none_or(|old| old.metadata.resource_version != event.metadata.resource_version)
+}
+
+/// Most recent timestamp of an event
+/// Events written through events.k8s.io leave `lastTimestamp` empty and record the
+/// latest occurrence in `series` (or only `eventTime` for a single one)
+pub fn last_seen(event: &Event) -> Option<DateTime<Utc>> {
+    event
+        .last_timestamp
+        .as_ref()
+        .map(|t| t.0)
+        .or_else(|| event.series.as_ref().and_then(|s| s.last_observed_time.as_ref()).map(|t| t.0))
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .or_else(|| event.metadata.creation_timestamp.as_ref().map(|t| t.0))
+}
+
+/// When an event first happened, with the same fallbacks as `last_seen`
+pub fn first_seen(event: &Event) -> Option<DateTime<Utc>> {
+    event
+        .first_timestamp
+        .as_ref()
+        .map(|t| t.0)
+        .or_else(|| event.event_time.as_ref().map(|t| t.0))
+        .or_else(|| event.metadata.creation_timestamp.as_ref().map(|t| t.0))
+}
+
+/// Get recent Kubernetes events (last 1 hour, warnings prioritized)
+/// Optionally filter by event type (e.g., "Warning" or "Normal")
+/// Served from the watch store once it has synced, otherwise from a direct list
+pub async fn get_events(cluster: Option<&str>, event_type_filter: Option<String>) -> Result<EventsResponse, String> {
+    let field_type = event_type_filter.as_deref().and_then(canonical_event_type);
+    let event_infos = recent_events(cluster, None, field_type).await?;
+    Ok(events_response(event_infos, event_type_filter, cluster::is_ignored_namespace))
+}
+
+/// Drop events from ignored namespaces, then sort, filter by type and count what is left
+pub fn events_response(
+    mut event_infos: Vec<EventInfo>,
+    event_type_filter: Option<String>,
+    is_ignored: impl Fn(&str) -> bool,
+) -> EventsResponse {
+    event_infos.retain(|e| !is_ignored(&e.namespace));
 
     // Sort by last timestamp (newest first)
     event_infos.sort_by(|a, b| {
@@ -137,12 +192,296 @@ pub async fn get_events(event_type_filter: Option<String>) -> Result<EventsRespo
         normal_count
     );
 
-    Ok(EventsResponse {
+    EventsResponse {
         total_events: event_infos.len(),
         warning_count,
         normal_count,
         events: event_infos,
-    })
+    }
+}
+
+/// Order of /api/events results (`?sort=warnings|time|count`)
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSort {
+    /// Warnings first, newest first within each type
+    #[default]
+    Warnings,
+    /// Newest first, regardless of type
+    Time,
+    /// Most repeated first, to surface events that keep firing
+    Count,
+}
+
+/// Sort events in place; ties fall back to newest first
+pub fn sort_events(events: &mut [EventInfo], sort: EventSort) {
+    let newest_first = |a: &EventInfo, b: &EventInfo| b.last_timestamp.cmp(&a.last_timestamp);
+    match sort {
+        EventSort::Warnings => events.sort_by(|a, b| {
+            (b.event_type == "Warning")
+                .cmp(&(a.event_type == "Warning"))
+                .then_with(|| newest_first(a, b))
+        }),
+        EventSort::Time => events.sort_by(newest_first),
+        EventSort::Count => events.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| newest_first(a, b))),
+    }
+}
+
+/// The exact `type` value for a case-insensitive filter, when the API server can select on it
+fn canonical_event_type(filter: &str) -> Option<&'static str> {
+    ["Warning", "Normal"]
+        .into_iter()
+        .find(|t| t.eq_ignore_ascii_case(filter))
+}
+
+/// List params for a direct event list, selecting on `type` server-side when given
+/// Core events only support equality field selectors, so the time window stays in memory
+pub fn event_list_params(event_type: Option<&str>) -> ListParams {
+    match event_type {
+        Some(event_type) => ListParams::default().fields(&format!("type={}", event_type)),
+        None => ListParams::default(),
+    }
+}
+
+/// Events from the last window, optionally limited to one namespace and one event type
+/// The default cluster reads the watch store once it has synced; otherwise events are listed directly
+async fn recent_events(
+    cluster: Option<&str>,
+    namespace: Option<&str>,
+    event_type: Option<&str>,
+) -> Result<Vec<EventInfo>, String> {
+    let stored = match cluster {
+        Some(_) => None,
+        None => EVENT_STORE
+            .read()
+            .await
+            .as_ref()
+            .map(|store| store.state())
+            .filter(|state| !state.is_empty()),
+    };
+
+    let events: Vec<Event> = if let Some(stored) = stored {
+        stored
+            .iter()
+            .filter(|event| namespace.is_none() || event.metadata.namespace.as_deref() == namespace)
+            .filter(|event| event_type.is_none() || event.type_.as_deref() == event_type)
+            .map(|event| event.as_ref().clone())
+            .collect()
+    } else {
+        let client = clusters::client(cluster).await.map_err(|e| e.to_string())?;
+
+        let events_api: Api<Event> = match namespace {
+            Some(ns) => Api::namespaced(client, ns),
+            None => Api::all(client),
+        };
+
+        events_api
+            .list(&event_list_params(event_type))
+            .await
+            .map_err(|e| format!("Failed to list events: {}", e))?
+            .items
+    };
+
+    let now = Utc::now();
+    Ok(within_window(&events, now, chrono::Duration::hours(EVENT_WINDOW_HOURS))
+        .into_iter()
+        .map(|event| to_event_info(event, now))
+        .collect())
+}
+
+/// Events last seen no earlier than `window` before `now`
+/// An event exactly at the edge is kept; events without any timestamp are kept too
+pub fn within_window(events: &[Event], now: DateTime<Utc>, window: chrono::Duration) -> Vec<&Event> {
+    let cutoff = now - window;
+    events
+        .iter()
+        .filter(|event| !matches!(last_seen(event), Some(ts) if ts < cutoff))
+        .collect()
+}
+
+/// Get events about one object (e.g. kind `Deployment`, name `web`), newest first
+/// `kind` is matched exactly, as Kubernetes does for field selectors
+pub async fn get_events_for_object(
+    cluster: Option<&str>,
+    kind: &str,
+    name: &str,
+    namespace: &str,
+) -> Result<Vec<EventInfo>, KusanagiError> {
+    validate_object_ref(kind, name).map_err(KusanagiError::Parse)?;
+    let client = clusters::client(cluster).await?;
+
+    let events_api: Api<Event> = Api::namespaced(client, namespace);
+    let field_selector = format!("involvedObject.kind={},involvedObject.name={}", kind, name);
+    let events = events_api
+        .list(&ListParams::default().fields(&field_selector))
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list events", e))?;
+
+    let now = Utc::now();
+    let mut event_infos: Vec<EventInfo> = events
+        .items
+        .iter()
+        .filter(|event| involves(event, kind, name, namespace, None))
+        .map(|event| to_event_info(event, now))
+        .collect();
+    event_infos.sort_by(|a, b| b.last_timestamp.cmp(&a.last_timestamp));
+
+    Ok(event_infos)
+}
+
+/// Check a kind (e.g. `Deployment`) and a DNS-1123 object name before they go into a field selector
+pub fn validate_object_ref(kind: &str, name: &str) -> Result<(), String> {
+    let valid_kind = kind.len() <= 63
+        && kind.starts_with(|c: char| c.is_ascii_alphabetic())
+        && kind.chars().all(|c| c.is_ascii_alphanumeric());
+    if !valid_kind {
+        return Err(format!("invalid kind '{}'", kind));
+    }
+
+    let valid_name = name.len() <= 253
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric());
+    if !valid_name {
+        return Err(format!("invalid object name '{}'", name));
+    }
+    Ok(())
+}
+
+/// Whether an event's involved object is the given kind/name/namespace
+/// (cluster-scoped objects have no namespace). With a `uid`, an event carrying another UID,
+/// i.e. about an earlier object with the same name, doesn't match
+pub fn involves(event: &Event, kind: &str, name: &str, namespace: &str, uid: Option<&str>) -> bool {
+    let obj = &event.involved_object;
+    obj.kind.as_deref() == Some(kind)
+        && obj.name.as_deref() == Some(name)
+        && (obj.namespace.is_none() || obj.namespace.as_deref() == Some(namespace))
+        && match (obj.uid.as_deref(), uid) {
+            (Some(event_uid), Some(uid)) => event_uid == uid,
+            _ => true,
+        }
+}
+
+/// Events rolled up per involved object
+#[derive(Clone, Debug, Serialize)]
+pub struct ObjectEventGroup {
+    pub namespace: String,
+    pub involved_object_kind: String,
+    pub involved_object_name: String,
+    pub event_count: usize,
+    pub total_count: i32,
+    pub warning_count: i32,
+    pub latest_reason: String,
+    pub latest_message: String,
+    pub last_timestamp: Option<String>,
+}
+
+/// Get recent events grouped by involved object, noisiest objects (by warnings) first
+pub async fn get_events_grouped(
+    cluster: Option<&str>,
+    namespace: Option<&str>,
+) -> Result<Vec<ObjectEventGroup>, String> {
+    let event_infos = recent_events(cluster, namespace, None).await?;
+    let groups = group_by_object(&event_infos);
+
+    info!(
+        "Events grouped: {} events across {} objects",
+        event_infos.len(),
+        groups.len()
+    );
+
+    Ok(groups)
+}
+
+/// Group events by namespace/kind/name, summing counts and keeping the latest message
+pub fn group_by_object(events: &[EventInfo]) -> Vec<ObjectEventGroup> {
+    let mut groups: HashMap<(String, String, String), ObjectEventGroup> = HashMap::new();
+
+    for event in events {
+        let key = (
+            event.namespace.clone(),
+            event.involved_object_kind.clone(),
+            event.involved_object_name.clone(),
+        );
+        let group = groups.entry(key).or_insert_with(|| ObjectEventGroup {
+            namespace: event.namespace.clone(),
+            involved_object_kind: event.involved_object_kind.clone(),
+            involved_object_name: event.involved_object_name.clone(),
+            event_count: 0,
+            total_count: 0,
+            warning_count: 0,
+            latest_reason: event.reason.clone(),
+            latest_message: event.message.clone(),
+            last_timestamp: event.last_timestamp.clone(),
+        });
+
+        group.event_count += 1;
+        group.total_count += event.count;
+        if event.event_type == "Warning" {
+            group.warning_count += event.count;
+        }
+        // RFC 3339 timestamps compare correctly as strings
+        if event.last_timestamp > group.last_timestamp {
+            group.latest_reason = event.reason.clone();
+            group.latest_message = event.message.clone();
+            group.last_timestamp = event.last_timestamp.clone();
+        }
+    }
+
+    let mut groups: Vec<ObjectEventGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| {
+        b.warning_count
+            .cmp(&a.warning_count)
+            .then(b.total_count.cmp(&a.total_count))
+            .then(b.last_timestamp.cmp(&a.last_timestamp))
+    });
+    groups
+}
+
+/// Convert a Kubernetes event to its API representation, with age relative to `now`
+pub fn to_event_info(event: &Event, now: DateTime<Utc>) -> EventInfo {
+    let name = event.metadata.name.clone().unwrap_or_default();
+    let namespace = event.metadata.namespace.clone().unwrap_or_else(|| "default".to_string());
+    
+    let event_type = event.type_.clone().unwrap_or_else(|| "Normal".to_string());
+    let reason = event.reason.clone().unwrap_or_default();
+    let message = event.message.clone().unwrap_or_default();
+    
+    let involved_object_kind = event
+        .involved_object
+        .kind
+        .clone()
+        .unwrap_or_default();
+    let involved_object_name = event
+        .involved_object
+        .name
+        .clone()
+        .unwrap_or_default();
+    
+    let count = event.count.unwrap_or(1);
+    
+    let first_timestamp = first_seen(event).map(|t| t.to_rfc3339());
+    let last_timestamp = last_seen(event).map(|t| t.to_rfc3339());
+
+    // Calculate age from last timestamp
+    let age = last_seen(event).map(|ts| {
+        let duration = now.signed_duration_since(ts);
+        format_duration(duration)
+    });
+
+    EventInfo {
+        name,
+        namespace,
+        event_type,
+        reason,
+        message,
+        involved_object_kind,
+        involved_object_name,
+        count,
+        first_timestamp,
+        last_timestamp,
+        age,
+    }
 }
 
 fn format_duration(duration: chrono::Duration) -> String {
@@ -164,3 +503,228 @@ fn format_duration(duration: chrono::Duration) -> String {
         format!("{}s ago", seconds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::EventSeries;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{MicroTime, Time};
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        rfc3339.parse().unwrap()
+    }
+
+    #[test]
+    fn last_seen_falls_back_for_events_api_events() {
+        let mut event = Event {
+            metadata: kube::api::ObjectMeta {
+                creation_timestamp: Some(Time(at("2024-01-01T10:00:00Z"))),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(last_seen(&event), Some(at("2024-01-01T10:00:00Z")));
+
+        event.event_time = Some(MicroTime(at("2024-01-01T10:01:00Z")));
+        assert_eq!(last_seen(&event), Some(at("2024-01-01T10:01:00Z")));
+        assert_eq!(first_seen(&event), Some(at("2024-01-01T10:01:00Z")));
+
+        event.series = Some(EventSeries {
+            count: Some(3),
+            last_observed_time: Some(MicroTime(at("2024-01-01T10:05:00Z"))),
+        });
+        assert_eq!(last_seen(&event), Some(at("2024-01-01T10:05:00Z")));
+
+        event.last_timestamp = Some(Time(at("2024-01-01T10:06:00Z")));
+        assert_eq!(last_seen(&event), Some(at("2024-01-01T10:06:00Z")));
+    }
+
+    #[test]
+    fn relisted_events_are_not_updates() {
+        let versioned = |rv: &str| Event {
+            metadata: kube::api::ObjectMeta {
+                resource_version: Some(rv.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(is_update(None, &versioned("1")));
+        assert!(!is_update(Some(&versioned("1")), &versioned("1")));
+        assert!(is_update(Some(&versioned("1")), &versioned("2")));
+    }
+
+    fn event_about(kind: &str, name: &str, namespace: Option<&str>, uid: Option<&str>) -> Event {
+        Event {
+            involved_object: k8s_openapi::api::core::v1::ObjectReference {
+                kind: Some(kind.to_string()),
+                name: Some(name.to_string()),
+                namespace: namespace.map(String::from),
+                uid: uid.map(String::from),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn involves_matches_kind_name_namespace_and_uid() {
+        let event = event_about("Deployment", "web", Some("prod"), Some("uid-1"));
+        assert!(involves(&event, "Deployment", "web", "prod", None));
+        assert!(involves(&event, "Deployment", "web", "prod", Some("uid-1")));
+        assert!(!involves(&event, "Deployment", "web", "prod", Some("uid-2")));
+        assert!(!involves(&event, "deployment", "web", "prod", None));
+        assert!(!involves(&event, "Deployment", "web", "staging", None));
+
+        let cluster_scoped = event_about("Node", "node-1", None, None);
+        assert!(involves(&cluster_scoped, "Node", "node-1", "default", Some("uid-1")));
+    }
+
+    #[test]
+    fn object_refs_are_validated_before_building_selectors() {
+        assert!(validate_object_ref("Deployment", "web-7d9f.v2").is_ok());
+        for (kind, name) in [
+            ("Deployment", "web,involvedObject.kind=Secret"),
+            ("Deployment", "Web"),
+            ("Deployment", "-web"),
+            ("Deployment", ""),
+            ("Pod,x=y", "web"),
+            ("", "web"),
+            ("1Pod", "web"),
+        ] {
+            assert!(validate_object_ref(kind, name).is_err(), "{}/{} should be rejected", kind, name);
+        }
+    }
+
+    fn info(name: &str, event_type: &str, count: i32, last: &str) -> EventInfo {
+        EventInfo {
+            name: name.to_string(),
+            namespace: "default".to_string(),
+            event_type: event_type.to_string(),
+            reason: String::new(),
+            message: String::new(),
+            involved_object_kind: "Pod".to_string(),
+            involved_object_name: "web".to_string(),
+            count,
+            first_timestamp: None,
+            last_timestamp: Some(last.to_string()),
+            age: None,
+        }
+    }
+
+    fn sorted(sort: EventSort) -> Vec<String> {
+        let mut events = vec![
+            info("old-warning", "Warning", 2, "2024-01-01T10:00:00Z"),
+            info("new-normal", "Normal", 1, "2024-01-01T12:00:00Z"),
+            info("noisy-normal", "Normal", 9, "2024-01-01T09:00:00Z"),
+            info("new-warning", "Warning", 2, "2024-01-01T11:00:00Z"),
+        ];
+        sort_events(&mut events, sort);
+        events.into_iter().map(|e| e.name).collect()
+    }
+
+    #[test]
+    fn warnings_sort_puts_warnings_first() {
+        assert_eq!(
+            sorted(EventSort::Warnings),
+            vec!["new-warning", "old-warning", "new-normal", "noisy-normal"]
+        );
+    }
+
+    #[test]
+    fn time_sort_is_newest_first() {
+        assert_eq!(
+            sorted(EventSort::Time),
+            vec!["new-normal", "new-warning", "old-warning", "noisy-normal"]
+        );
+    }
+
+    #[test]
+    fn count_sort_surfaces_repeated_events() {
+        assert_eq!(
+            sorted(EventSort::Count),
+            vec!["noisy-normal", "new-warning", "old-warning", "new-normal"]
+        );
+    }
+
+    #[test]
+    fn grouping_sums_counts_and_keeps_the_latest_message() {
+        let on = |object: &str, event: EventInfo| EventInfo {
+            involved_object_name: object.to_string(),
+            message: format!("{} message", event.name),
+            ..event
+        };
+        let events = vec![
+            on("web", info("backoff", "Warning", 5, "2024-01-01T10:00:00Z")),
+            on("web", info("unhealthy", "Warning", 2, "2024-01-01T11:00:00Z")),
+            on("web", info("pulled", "Normal", 1, "2024-01-01T09:00:00Z")),
+            on("db", info("scheduled", "Normal", 20, "2024-01-01T12:00:00Z")),
+        ];
+
+        let groups = group_by_object(&events);
+        assert_eq!(groups.len(), 2);
+
+        let web = &groups[0];
+        assert_eq!(web.involved_object_name, "web");
+        assert_eq!((web.event_count, web.total_count, web.warning_count), (3, 8, 7));
+        assert_eq!(web.latest_message, "unhealthy message");
+        assert_eq!(web.last_timestamp.as_deref(), Some("2024-01-01T11:00:00Z"));
+
+        assert_eq!((groups[1].involved_object_name.as_str(), groups[1].warning_count), ("db", 0));
+    }
+
+    #[test]
+    fn warnings_only_sets_the_type_field_selector() {
+        assert_eq!(event_list_params(Some("Warning")).field_selector.as_deref(), Some("type=Warning"));
+        assert_eq!(event_list_params(None).field_selector, None);
+        assert_eq!(canonical_event_type("warning"), Some("Warning"));
+        assert_eq!(canonical_event_type("bogus"), None);
+    }
+
+    #[test]
+    fn ignored_namespaces_are_excluded_from_list_and_counts() {
+        let in_namespace = |namespace: &str, event: EventInfo| EventInfo {
+            namespace: namespace.to_string(),
+            ..event
+        };
+        let events = vec![
+            in_namespace("kube-system", info("coredns", "Warning", 1, "2024-03-01T10:00:00Z")),
+            in_namespace("cilium", info("agent", "Normal", 1, "2024-03-01T10:01:00Z")),
+            in_namespace("shop", info("api", "Warning", 1, "2024-03-01T10:02:00Z")),
+            in_namespace("shop", info("web", "Normal", 1, "2024-03-01T10:03:00Z")),
+        ];
+        let ignored = cluster::parse_name_list("kube-system, cilium");
+
+        let response = events_response(events.clone(), None, |ns| ignored.contains(ns));
+        assert_eq!(response.total_events, 2);
+        assert_eq!((response.warning_count, response.normal_count), (1, 1));
+        assert!(response.events.iter().all(|e| e.namespace == "shop"));
+
+        // The default (nothing ignored) keeps everything
+        let response = events_response(events, None, |_| false);
+        assert_eq!((response.total_events, response.warning_count), (4, 2));
+    }
+
+    #[test]
+    fn window_keeps_the_edge_and_drops_older_events() {
+        let event = |name: &str, last: Option<&str>| Event {
+            metadata: kube::api::ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            last_timestamp: last.map(|t| Time(at(t))),
+            ..Default::default()
+        };
+        let events = vec![
+            event("recent", Some("2024-03-01T11:30:00Z")),
+            event("at-edge", Some("2024-03-01T11:00:00Z")),
+            event("just-older", Some("2024-03-01T10:59:59Z")),
+            event("undated", None),
+        ];
+
+        let kept: Vec<&str> = within_window(&events, at("2024-03-01T12:00:00Z"), chrono::Duration::hours(1))
+            .iter()
+            .filter_map(|e| e.metadata.name.as_deref())
+            .collect();
+        assert_eq!(kept, vec!["recent", "at-edge", "undated"]);
+    }
+}
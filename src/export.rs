@@ -2,6 +2,7 @@ use chrono::Utc;
 use serde::Serialize;
 
 use crate::alertmanager::{self, AlertsResponse};
+use crate::http_util::{self, with_timeout};
 use crate::argocd::{self, ArgoStatusResponse};
 use crate::events::{self, EventsResponse};
 use crate::nodes::{self, NodesStatusResponse};
@@ -38,15 +39,19 @@ pub struct ReportSummary {
 }
 
 /// Generate a complete cluster report
+/// Every section shares one deadline (KUSANAGI_SCAN_TIMEOUT_SECS): a slow optional section
+/// is left out, a slow required one fails the report with a timeout error
 pub async fn generate_report() -> Result<ClusterReport, String> {
+    let deadline = http_util::scan_timeout();
+
     // Gather all data concurrently
     let (nodes_result, argocd_result, alerts_result, events_result, storage_result, metrics_result) = tokio::join!(
-        nodes::get_nodes_status(),
-        argocd::get_argocd_status(),
-        alertmanager::get_active_alerts(),
-        events::get_events(None),
-        storage::get_storage_status(),
-        prometheus::get_cluster_metrics()
+        with_timeout("nodes", deadline, nodes::get_nodes_status(None)),
+        with_timeout("ArgoCD", deadline, argocd::get_argocd_status(None)),
+        with_timeout("alerts", deadline, alertmanager::get_active_alerts(None)),
+        with_timeout("events", deadline, events::get_events(None, None)),
+        with_timeout("storage", deadline, storage::get_storage_status(None)),
+        with_timeout("metrics", deadline, prometheus::get_cluster_metrics())
     );
     
     // Process nodes - required
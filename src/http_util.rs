@@ -0,0 +1,127 @@
+//! Shared HTTP client helpers
+//! Every outbound integration builds its client here so timeouts are always set
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Build a reqwest client with a request-level timeout
+pub fn http_client(timeout: Duration) -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// A client built on first use and then shared, so keep-alive connections stay warm
+/// The timeout is read once, when the client is built
+pub struct SharedClient {
+    client: OnceLock<reqwest::Client>,
+    timeout: fn() -> Duration,
+}
+
+impl SharedClient {
+    pub const fn new(timeout: fn() -> Duration) -> Self {
+        Self {
+            client: OnceLock::new(),
+            timeout,
+        }
+    }
+
+    /// The shared client, building it on the first call
+    pub fn get(&self) -> Result<&reqwest::Client, String> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client = http_client((self.timeout)())?;
+        // A concurrent first call may have won the race; either client is fine
+        Ok(self.client.get_or_init(|| client))
+    }
+}
+
+/// Read a timeout in seconds from an env var, falling back to a default
+/// e.g. `PROMETHEUS_TIMEOUT_SECS=5`
+pub fn timeout_from_env(var: &str, default_secs: u64) -> Duration {
+    let secs = std::env::var(var)
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|s| *s > 0)
+        .unwrap_or(default_secs);
+    Duration::from_secs(secs)
+}
+
+/// Describe a request error, making timeouts explicit in the message
+pub fn describe_error(e: &reqwest::Error) -> String {
+    if e.is_timeout() {
+        format!("request timed out ({})", e)
+    } else {
+        e.to_string()
+    }
+}
+
+/// Overall deadline for cluster-wide scans such as the report (KUSANAGI_SCAN_TIMEOUT_SECS, default 20s)
+pub fn scan_timeout() -> Duration {
+    timeout_from_env("KUSANAGI_SCAN_TIMEOUT_SECS", 20)
+}
+
+/// Await a fallible future for at most `timeout`, reporting expiry as "<what> timed out after Ns"
+pub async fn with_timeout<T, E: std::fmt::Display>(
+    what: &str,
+    timeout: Duration,
+    fut: impl Future<Output = Result<T, E>>,
+) -> Result<T, String> {
+    match tokio::time::timeout(timeout, fut).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("{} timed out after {}s", what, timeout.as_secs())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CLIENT: SharedClient = SharedClient::new(|| Duration::from_secs(1));
+
+    #[test]
+    fn shared_client_returns_same_instance() {
+        let first = CLIENT.get().unwrap();
+        let second = CLIENT.get().unwrap();
+        assert!(std::ptr::eq(first, second));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn with_timeout_cuts_off_slow_future() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok::<_, String>(1)
+        };
+        let result = with_timeout("Metrics from node a", Duration::from_secs(5), slow).await;
+        assert_eq!(result, Err("Metrics from node a timed out after 5s".to_string()));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn with_timeout_passes_through_results() {
+        let fast = async { Ok::<_, String>(1) };
+        assert_eq!(with_timeout("fast", Duration::from_secs(5), fast).await, Ok(1));
+
+        let failing = async { Err::<u8, _>("boom") };
+        assert_eq!(with_timeout("failing", Duration::from_secs(5), failing).await, Err("boom".to_string()));
+    }
+
+    #[tokio::test]
+    async fn request_timeout_is_described_as_timed_out() {
+        // Accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                open.push(stream);
+            }
+        });
+
+        let client = http_client(Duration::from_millis(100)).unwrap();
+        let err = client.get(&url).send().await.unwrap_err();
+        assert!(describe_error(&err).contains("timed out"), "{}", describe_error(&err));
+    }
+}
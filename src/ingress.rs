@@ -1,7 +1,9 @@
 use kube::{Client, Api, api::ListParams};
-use k8s_openapi::api::networking::v1::Ingress;
+use k8s_openapi::api::core::v1::{Endpoints, Service};
+use k8s_openapi::api::networking::v1::{Ingress, IngressBackend};
 use serde::Serialize;
 use chrono::Utc;
+use std::collections::HashSet;
 
 #[derive(Serialize)]
 pub struct IngressInfo {
@@ -9,14 +11,73 @@ pub struct IngressInfo {
     pub namespace: String,
     pub load_balancer: Option<String>,
     pub rules: Vec<String>,
+    pub backends: Vec<BackendStatus>,
+    /// Backend services that do not exist in the ingress namespace
+    pub missing_services: Vec<String>,
     pub age: String,
 }
 
+/// Health of the service behind one ingress rule
+#[derive(Serialize)]
+pub struct BackendStatus {
+    pub rule: String,
+    pub service: String,
+    pub service_exists: bool,
+    /// False when the service is missing or has no ready endpoints
+    pub backend_healthy: bool,
+}
+
+/// Services and their ready endpoints, keyed by (namespace, name)
+pub struct BackendIndex {
+    pub services: HashSet<(String, String)>,
+    pub ready: HashSet<(String, String)>,
+}
+
+impl BackendIndex {
+    pub fn from_lists(services: &[Service], endpoints: &[Endpoints]) -> Self {
+        let key = |meta: &k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta| {
+            (meta.namespace.clone().unwrap_or_default(), meta.name.clone().unwrap_or_default())
+        };
+        let services = services.iter().map(|svc| key(&svc.metadata)).collect();
+        let ready = endpoints
+            .iter()
+            .filter(|ep| {
+                ep.subsets.iter().flatten().any(|subset| {
+                    subset.addresses.as_ref().map(|a| !a.is_empty()).unwrap_or(false)
+                })
+            })
+            .map(|ep| key(&ep.metadata))
+            .collect();
+        BackendIndex { services, ready }
+    }
+
+    pub fn status(&self, namespace: &str, rule: String, service: &str) -> BackendStatus {
+        let key = (namespace.to_string(), service.to_string());
+        let service_exists = self.services.contains(&key);
+        BackendStatus {
+            rule,
+            service: service.to_string(),
+            service_exists,
+            backend_healthy: service_exists && self.ready.contains(&key),
+        }
+    }
+}
+
+fn backend_service(backend: &IngressBackend) -> Option<String> {
+    backend.service.as_ref().map(|s| s.name.clone())
+}
+
 pub async fn get_ingresses() -> Result<Vec<IngressInfo>, String> {
     let client = Client::try_default().await.map_err(|e| e.to_string())?;
-    let ingresses: Api<Ingress> = Api::all(client);
+    let ingresses: Api<Ingress> = Api::all(client.clone());
     let list = ingresses.list(&ListParams::default()).await.map_err(|e| e.to_string())?;
 
+    let services: Api<Service> = Api::all(client.clone());
+    let endpoints: Api<Endpoints> = Api::all(client);
+    let service_list = services.list(&ListParams::default()).await.map_err(|e| e.to_string())?;
+    let endpoint_list = endpoints.list(&ListParams::default()).await.map_err(|e| e.to_string())?;
+    let index = BackendIndex::from_lists(&service_list.items, &endpoint_list.items);
+
     let mut ingress_infos = Vec::new();
 
     for ing in list {
@@ -37,6 +98,30 @@ pub async fn get_ingresses() -> Result<Vec<IngressInfo>, String> {
             None
         };
 
+        let mut backends = Vec::new();
+        if let Some(spec) = &ing.spec {
+            if let Some(service) = spec.default_backend.as_ref().and_then(backend_service) {
+                backends.push(index.status(&namespace, "(default)".to_string(), &service));
+            }
+            for rule in spec.rules.iter().flatten() {
+                let host = rule.host.clone().unwrap_or("*".to_string());
+                for path in rule.http.iter().flat_map(|http| http.paths.iter()) {
+                    if let Some(service) = backend_service(&path.backend) {
+                        let rule = format!("{}{}", host, path.path.clone().unwrap_or_default());
+                        backends.push(index.status(&namespace, rule, &service));
+                    }
+                }
+            }
+        }
+
+        let mut missing_services: Vec<String> = backends
+            .iter()
+            .filter(|b| !b.service_exists)
+            .map(|b| b.service.clone())
+            .collect();
+        missing_services.sort();
+        missing_services.dedup();
+
         let rules = if let Some(spec) = ing.spec {
             spec.rules.unwrap_or_default().iter().flat_map(|rule| {
                 let host = rule.host.clone().unwrap_or("*".to_string());
@@ -67,9 +152,64 @@ pub async fn get_ingresses() -> Result<Vec<IngressInfo>, String> {
             namespace,
             load_balancer,
             rules,
+            backends,
+            missing_services,
             age,
         });
     }
 
     Ok(ingress_infos)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::{EndpointAddress, EndpointSubset};
+    use kube::api::ObjectMeta;
+
+    fn meta(namespace: &str, name: &str) -> ObjectMeta {
+        ObjectMeta {
+            namespace: Some(namespace.to_string()),
+            name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rule_on_service_without_ready_endpoints_is_unhealthy() {
+        let services = vec![
+            Service { metadata: meta("web", "frontend"), ..Default::default() },
+            Service { metadata: meta("web", "api"), ..Default::default() },
+        ];
+        let endpoints = vec![
+            Endpoints {
+                metadata: meta("web", "frontend"),
+                subsets: Some(vec![EndpointSubset {
+                    addresses: Some(vec![EndpointAddress { ip: "10.0.0.5".to_string(), ..Default::default() }]),
+                    ..Default::default()
+                }]),
+            },
+            // Pods exist but none is ready
+            Endpoints {
+                metadata: meta("web", "api"),
+                subsets: Some(vec![EndpointSubset {
+                    not_ready_addresses: Some(vec![EndpointAddress { ip: "10.0.0.6".to_string(), ..Default::default() }]),
+                    ..Default::default()
+                }]),
+            },
+        ];
+        let index = BackendIndex::from_lists(&services, &endpoints);
+
+        let frontend = index.status("web", "example.com/".to_string(), "frontend");
+        assert!(frontend.service_exists && frontend.backend_healthy);
+
+        let api = index.status("web", "example.com/api".to_string(), "api");
+        assert!(api.service_exists && !api.backend_healthy);
+
+        let missing = index.status("web", "example.com/old".to_string(), "legacy");
+        assert!(!missing.service_exists && !missing.backend_healthy);
+
+        // Same name in another namespace does not count
+        assert!(!index.status("other", "x/".to_string(), "frontend").service_exists);
+    }
+}
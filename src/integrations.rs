@@ -0,0 +1,127 @@
+//! Reachability of the external systems Kusanagi depends on
+//! Probes Ollama, the MCP servers, Prometheus and Alertmanager concurrently
+//! so the UI can show which integrations are online before they are needed.
+
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::{alertmanager, chat, http_util, mcp, prometheus};
+
+/// Probe timeout (INTEGRATION_PROBE_TIMEOUT_SECS, default 3s)
+fn probe_timeout() -> Duration {
+    http_util::timeout_from_env("INTEGRATION_PROBE_TIMEOUT_SECS", 3)
+}
+
+/// Result of probing one integration
+#[derive(Clone, Debug, Serialize)]
+pub struct IntegrationStatus {
+    pub name: String,
+    pub reachable: bool,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// All probe results plus how many are online
+#[derive(Clone, Debug, Serialize)]
+pub struct IntegrationsStatus {
+    pub online: usize,
+    pub total: usize,
+    pub integrations: Vec<IntegrationStatus>,
+}
+
+impl IntegrationsStatus {
+    pub fn from_results(integrations: Vec<IntegrationStatus>) -> Self {
+        Self {
+            online: integrations.iter().filter(|i| i.reachable).count(),
+            total: integrations.len(),
+            integrations,
+        }
+    }
+}
+
+/// Time a probe, bounding it by the probe timeout
+async fn probe<F>(name: String, check: F) -> IntegrationStatus
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let timeout = probe_timeout();
+    let start = Instant::now();
+    let result = match tokio::time::timeout(timeout, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("no response within {}s", timeout.as_secs())),
+    };
+
+    IntegrationStatus {
+        name,
+        reachable: result.is_ok(),
+        latency_ms: start.elapsed().as_millis(),
+        error: result.err(),
+    }
+}
+
+/// GET a URL and require a success status
+async fn http_get_ok(url: String) -> Result<(), String> {
+    let client = http_util::http_client(probe_timeout())?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| http_util::describe_error(&e))?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("returned status {}", response.status()))
+    }
+}
+
+/// Probe every integration concurrently
+pub async fn get_integrations_status() -> IntegrationsStatus {
+    let ollama_tags = format!("{}/api/tags", chat::OLLAMA_URL.trim_end_matches("/api/generate"));
+
+    let mut probes: Vec<std::pin::Pin<Box<dyn Future<Output = IntegrationStatus> + Send>>> = vec![
+        Box::pin(probe("ollama".to_string(), http_get_ok(ollama_tags))),
+        Box::pin(probe(
+            "prometheus".to_string(),
+            http_get_ok(format!("{}/-/ready", prometheus::get_prometheus_url())),
+        )),
+        Box::pin(probe(
+            "alertmanager".to_string(),
+            http_get_ok(format!("{}/-/ready", alertmanager::get_alertmanager_url())),
+        )),
+    ];
+    for (name, url) in mcp::MCP_SERVERS.iter() {
+        probes.push(Box::pin(probe(format!("mcp-{}", name), async move {
+            mcp::list_tools(url).await.map(|_| ())
+        })));
+    }
+
+    let status = IntegrationsStatus::from_results(futures::future::join_all(probes).await);
+    info!("Integrations: {}/{} reachable", status.online, status.total);
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn probes_aggregate_into_name_reachable_latency() {
+        let (ollama, prometheus, alertmanager) = tokio::join!(
+            probe("ollama".to_string(), async { Ok(()) }),
+            probe("prometheus".to_string(), async { Err("returned status 503".to_string()) }),
+            probe("alertmanager".to_string(), std::future::pending()),
+        );
+        let status = IntegrationsStatus::from_results(vec![ollama, prometheus, alertmanager]);
+
+        assert_eq!((status.online, status.total), (1, 3));
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["integrations"][0], serde_json::json!({ "name": "ollama", "reachable": true, "latency_ms": 0 }));
+        assert_eq!(json["integrations"][1]["error"], "returned status 503");
+        assert_eq!(json["integrations"][2]["reachable"], false);
+        assert_eq!(json["integrations"][2]["error"], "no response within 3s");
+    }
+}
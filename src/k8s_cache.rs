@@ -0,0 +1,142 @@
+//! Shared snapshots of cluster-wide Kubernetes lists
+//! Nodes, apps, storage and pods all need the full pod/PVC lists (and nodes and the
+//! capacity view the node list); they read them from here so one request cycle
+//! (e.g. a report) issues a single list call. Lists retry transient API errors.
+
+use k8s_openapi::api::core::v1::{Node, PersistentVolumeClaim, Pod};
+use kube::{
+    api::{Api, ListParams, ObjectList},
+    Client,
+};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::cache::{cache_ttl, TtlCache};
+use crate::kube_retry;
+
+lazy_static::lazy_static! {
+    static ref PODS: TtlCache<Vec<Pod>> = TtlCache::new(cache_ttl());
+    static ref PVCS: TtlCache<Vec<PersistentVolumeClaim>> = TtlCache::new(cache_ttl());
+    static ref NODES: TtlCache<Vec<Node>> = TtlCache::new(cache_ttl());
+}
+
+/// One full list call, retried on transient errors
+async fn list<K>(api: &Api<K>, what: &str) -> Result<ObjectList<K>, kube::Error>
+where
+    K: Clone + DeserializeOwned + std::fmt::Debug,
+{
+    let params = ListParams::default();
+    kube_retry::retry(what, || api.list(&params)).await
+}
+
+/// Where a list call goes
+#[derive(Debug, PartialEq)]
+enum ListScope<'a> {
+    /// One namespace, via `Api::namespaced` (uncached)
+    Namespace(&'a str),
+    /// Cluster-wide on a non-default cluster (uncached)
+    Cluster,
+    /// Cluster-wide on the default cluster, through the shared snapshot
+    CachedCluster,
+}
+
+fn list_scope<'a>(cluster: Option<&str>, namespace: Option<&'a str>) -> ListScope<'a> {
+    match (cluster, namespace) {
+        (_, Some(ns)) => ListScope::Namespace(ns),
+        (Some(_), None) => ListScope::Cluster,
+        (None, None) => ListScope::CachedCluster,
+    }
+}
+
+/// List all pods cluster-wide, reusing a recent snapshot when available
+pub async fn list_all_pods(client: Client) -> Result<Arc<Vec<Pod>>, kube::Error> {
+    PODS.get_or_fetch(|| async move {
+        debug!("Listing pods cluster-wide (cache miss)");
+        let pods_api: Api<Pod> = Api::all(client);
+        list(&pods_api, "List pods").await.map(|l| l.items)
+    })
+    .await
+}
+
+/// List pods in one namespace (uncached), or all pods when `namespace` is None
+/// Only the default cluster (`cluster` None) is cached; other clusters are listed directly
+pub async fn list_pods(
+    client: Client,
+    cluster: Option<&str>,
+    namespace: Option<&str>,
+) -> Result<Arc<Vec<Pod>>, kube::Error> {
+    match list_scope(cluster, namespace) {
+        ListScope::Namespace(ns) => {
+            let pods_api: Api<Pod> = Api::namespaced(client, ns);
+            list(&pods_api, "List pods").await.map(|l| Arc::new(l.items))
+        }
+        ListScope::Cluster => {
+            let pods_api: Api<Pod> = Api::all(client);
+            list(&pods_api, "List pods").await.map(|l| Arc::new(l.items))
+        }
+        ListScope::CachedCluster => list_all_pods(client).await,
+    }
+}
+
+/// List all PVCs cluster-wide, reusing a recent snapshot when available
+pub async fn list_all_pvcs(client: Client) -> Result<Arc<Vec<PersistentVolumeClaim>>, kube::Error> {
+    PVCS.get_or_fetch(|| async move {
+        debug!("Listing PVCs cluster-wide (cache miss)");
+        let pvc_api: Api<PersistentVolumeClaim> = Api::all(client);
+        list(&pvc_api, "List PVCs").await.map(|l| l.items)
+    })
+    .await
+}
+
+/// List PVCs in one namespace (uncached), or all PVCs when `namespace` is None
+/// Only the default cluster (`cluster` None) is cached; other clusters are listed directly
+pub async fn list_pvcs(
+    client: Client,
+    cluster: Option<&str>,
+    namespace: Option<&str>,
+) -> Result<Arc<Vec<PersistentVolumeClaim>>, kube::Error> {
+    match list_scope(cluster, namespace) {
+        ListScope::Namespace(ns) => {
+            let pvc_api: Api<PersistentVolumeClaim> = Api::namespaced(client, ns);
+            list(&pvc_api, "List PVCs").await.map(|l| Arc::new(l.items))
+        }
+        ListScope::Cluster => {
+            let pvc_api: Api<PersistentVolumeClaim> = Api::all(client);
+            list(&pvc_api, "List PVCs").await.map(|l| Arc::new(l.items))
+        }
+        ListScope::CachedCluster => list_all_pvcs(client).await,
+    }
+}
+
+/// List all nodes, reusing a recent snapshot for the default cluster (`cluster` None)
+pub async fn list_nodes(client: Client, cluster: Option<&str>) -> Result<Arc<Vec<Node>>, kube::Error> {
+    let list_all = || async move {
+        let nodes_api: Api<Node> = Api::all(client);
+        list(&nodes_api, "List nodes").await.map(|l| l.items)
+    };
+    match cluster {
+        Some(_) => list_all().await.map(Arc::new),
+        None => {
+            NODES
+                .get_or_fetch(|| async {
+                    debug!("Listing nodes (cache miss)");
+                    list_all().await
+                })
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_namespace_scopes_the_list_call() {
+        assert_eq!(list_scope(None, Some("team-a")), ListScope::Namespace("team-a"));
+        assert_eq!(list_scope(Some("prod"), Some("team-a")), ListScope::Namespace("team-a"));
+        assert_eq!(list_scope(Some("prod"), None), ListScope::Cluster);
+        assert_eq!(list_scope(None, None), ListScope::CachedCluster);
+    }
+}
@@ -0,0 +1,135 @@
+//! Retries for transient Kubernetes API failures
+//! Throttling (429), server errors (5xx) and dropped connections are retried with
+//! exponential backoff; anything else (404, 403, bad requests) fails immediately.
+
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// Delay before the first retry, doubled on each further attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Upper bound for a single retry delay
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Upper bound for KUBE_RETRY_ATTEMPTS, so a typo can't stall a request for minutes
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Attempts per call from KUBE_RETRY_ATTEMPTS (default 3, 1 disables retries, at most 10)
+pub fn max_attempts() -> u32 {
+    std::env::var("KUBE_RETRY_ATTEMPTS")
+        .ok()
+        .and_then(|s| s.parse::<u32>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(3)
+        .min(MAX_ATTEMPTS)
+}
+
+/// Delay after the given failed attempt (1-based): 200ms, 400ms, 800ms... capped at MAX_BACKOFF
+pub fn backoff(attempt: u32) -> Duration {
+    2u32.checked_pow(attempt.saturating_sub(1))
+        .and_then(|factor| INITIAL_BACKOFF.checked_mul(factor))
+        .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
+}
+
+/// Whether a kube error is worth retrying
+pub fn is_transient(e: &kube::Error) -> bool {
+    match e {
+        kube::Error::Api(resp) => resp.code == 429 || resp.code >= 500,
+        kube::Error::HyperError(_) | kube::Error::Service(_) => true,
+        _ => false,
+    }
+}
+
+/// Run `op` up to `attempts` times, retrying only transient errors
+pub async fn retry_with<T, F, Fut>(what: &str, attempts: u32, mut op: F) -> Result<T, kube::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, kube::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if attempt < attempts && is_transient(&e) => {
+                let delay = backoff(attempt);
+                warn!("{} failed (attempt {}/{}), retrying in {:?}: {}", what, attempt, attempts, delay, e);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Run `op` with the configured number of attempts
+pub async fn retry<T, F, Fut>(what: &str, op: F) -> Result<T, kube::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, kube::Error>>,
+{
+    retry_with(what, max_attempts(), op).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn api_error(code: u16) -> kube::Error {
+        kube::Error::Api(kube::core::ErrorResponse {
+            status: "Failure".to_string(),
+            message: "test".to_string(),
+            reason: "test".to_string(),
+            code,
+        })
+    }
+
+    #[test]
+    fn backoff_doubles_then_caps() {
+        assert_eq!(backoff(1), Duration::from_millis(200));
+        assert_eq!(backoff(2), Duration::from_millis(400));
+        assert_eq!(backoff(3), Duration::from_millis(800));
+        assert_eq!(backoff(10), MAX_BACKOFF);
+        assert_eq!(backoff(40), MAX_BACKOFF);
+        assert_eq!(backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_transient_error_once_then_succeeds() {
+        let calls = AtomicU32::new(0);
+        let result = retry_with("test", 3, || async {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(api_error(503))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_retry_permanent_errors() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = retry_with("test", 3, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(api_error(404))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = retry_with("test", 3, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(api_error(500))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,8 +1,10 @@
-use actix_web::{get, post, web, App, HttpServer, Responder, HttpResponse};
+use actix_web::{get, middleware, post, web, App, HttpRequest, HttpServer, Responder, HttpResponse, ResponseError};
 use actix_files::Files;
 use serde::Deserialize;
 use tracing::info;
 
+use crate::error::KusanagiError;
+
 mod apps;
 mod argocd;
 mod backups;
@@ -22,15 +24,38 @@ mod prometheus;
 mod alertmanager;
 mod export;
 mod telemetry;
+mod http_util;
+mod error;
+mod cache;
+mod k8s_cache;
+mod workloads;
+mod clusters;
+mod integrations;
+mod summary;
+mod capabilities;
+mod resources;
+mod etag;
+mod kube_retry;
+mod notifiers;
+mod resource_metrics;
 
 #[derive(Deserialize)]
 struct SyncRequest {
     app_name: String,
+    /// Validate the app exists and is syncable without triggering the sync
+    #[serde(default)]
+    dry_run: bool,
 }
 
 #[derive(Deserialize)]
 struct EventsQuery {
+    cluster: Option<String>,
     event_type: Option<String>,
+    /// Shorthand for `event_type=Warning`
+    #[serde(default)]
+    warnings_only: bool,
+    #[serde(default)]
+    sort: events::EventSort,
 }
 
 #[get("/health")]
@@ -38,6 +63,27 @@ async fn health_check() -> impl Responder {
     HttpResponse::Ok().body("Kusanagi Agent Controller is healthy")
 }
 
+#[get("/readyz")]
+async fn readiness_check() -> impl Responder {
+    match cluster::check_readiness().await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => {
+            tracing::warn!("Readiness check failed: {}", e);
+            HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "status": "unavailable",
+                "error": e
+            }))
+        }
+    }
+}
+
+#[get("/metrics")]
+async fn metrics_endpoint() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(telemetry::render_metrics())
+}
+
 #[get("/")]
 async fn index() -> impl Responder {
     HttpResponse::Ok()
@@ -46,38 +92,72 @@ async fn index() -> impl Responder {
 }
 
 #[get("/api/argocd/status")]
-async fn argocd_status() -> impl Responder {
-    match argocd::get_argocd_status().await {
-        Ok(status) => HttpResponse::Ok().json(status),
-        Err(e) => {
-            tracing::error!("Failed to get ArgoCD status: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": e
-            }))
-        }
-    }
+async fn argocd_status(query: web::Query<ClusterQuery>) -> Result<HttpResponse, KusanagiError> {
+    let status = argocd::get_argocd_status(query.cluster.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(status))
+}
+
+#[get("/api/argocd/projects")]
+async fn argocd_projects(query: web::Query<ClusterQuery>) -> Result<HttpResponse, KusanagiError> {
+    let projects = argocd::get_apps_by_project(query.cluster.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(projects))
+}
+
+#[get("/api/argocd/appsets")]
+async fn argocd_appsets(query: web::Query<ClusterQuery>) -> Result<HttpResponse, KusanagiError> {
+    let appsets = argocd::get_application_sets(query.cluster.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(appsets))
+}
+
+#[get("/api/argocd/operations")]
+async fn argocd_operations(query: web::Query<ClusterQuery>) -> Result<HttpResponse, KusanagiError> {
+    let operations = argocd::get_recent_operations(query.cluster.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(operations))
+}
+
+#[get("/api/argocd/diff/{app}")]
+async fn argocd_diff(path: web::Path<String>) -> Result<HttpResponse, KusanagiError> {
+    let diff = argocd::get_app_diff(&path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(diff))
+}
+
+#[get("/api/argocd/app/{name}")]
+async fn argocd_app(path: web::Path<String>) -> Result<HttpResponse, KusanagiError> {
+    let app = argocd::get_application(&path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(app))
 }
 
 #[post("/api/argocd/sync")]
-async fn argocd_sync(body: web::Json<SyncRequest>) -> impl Responder {
-    info!("Sync requested for application: {}", body.app_name);
+async fn argocd_sync(body: web::Json<SyncRequest>, query: web::Query<ClusterQuery>) -> impl Responder {
+    info!("Sync requested for application: {} (dry run: {})", body.app_name, body.dry_run);
     
-    match argocd::sync_application(&body.app_name).await {
+    match argocd::sync_application(query.cluster.as_deref(), &body.app_name, body.dry_run).await {
         Ok(response) => HttpResponse::Ok().json(response),
         Err(e) => {
             tracing::error!("Failed to sync application {}: {}", body.app_name, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
+            HttpResponse::build(e.status_code()).json(serde_json::json!({
                 "success": false,
-                "message": e
+                "message": e.message()
             }))
         }
     }
 }
 
+#[post("/api/argocd/sync-all")]
+async fn argocd_sync_all(
+    body: Option<web::Json<argocd::SyncAllRequest>>,
+    query: web::Query<ClusterQuery>,
+) -> Result<HttpResponse, KusanagiError> {
+    let request = body.map(web::Json::into_inner).unwrap_or_default();
+    info!("Bulk sync requested (dry run: {})", request.dry_run);
+    let results = argocd::sync_all_out_of_sync(query.cluster.as_deref(), request.dry_run).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
+
 #[get("/api/nodes/status")]
-async fn nodes_status() -> impl Responder {
-    match nodes::get_nodes_status().await {
-        Ok(status) => HttpResponse::Ok().json(status),
+async fn nodes_status(req: HttpRequest, query: web::Query<ClusterQuery>) -> impl Responder {
+    match nodes::get_nodes_status(query.cluster.as_deref()).await {
+        Ok(status) => etag::json_response(&req, &status),
         Err(e) => {
             tracing::error!("Failed to get nodes status: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -87,10 +167,39 @@ async fn nodes_status() -> impl Responder {
     }
 }
 
+#[get("/api/nodes/{name}")]
+async fn node_detail(path: web::Path<String>) -> Result<HttpResponse, KusanagiError> {
+    let node = nodes::get_node(&path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(node))
+}
+
+#[get("/api/summary")]
+async fn dashboard_summary() -> impl Responder {
+    HttpResponse::Ok().json(summary::get_dashboard_summary().await)
+}
+
+#[get("/api/health-score")]
+async fn health_score() -> impl Responder {
+    HttpResponse::Ok().json(summary::compute_health_score().await)
+}
+
+/// Validate an optional label selector from a query, returning a 400 response when malformed
+fn invalid_selector(selector: Option<&str>) -> Option<HttpResponse> {
+    let error = cluster::validate_label_selector(selector?).err()?;
+    Some(HttpResponse::BadRequest().json(serde_json::json!({
+        "error": error
+    })))
+}
+
 #[get("/api/cluster/overview")]
-async fn cluster_overview() -> impl Responder {
-    match cluster::get_cluster_overview().await {
-        Ok(overview) => HttpResponse::Ok().json(overview),
+async fn cluster_overview(req: HttpRequest, query: web::Query<ClusterOverviewQuery>) -> impl Responder {
+    let label_selector = query.label_selector.as_deref().filter(|s| !s.trim().is_empty());
+    if let Some(response) = invalid_selector(label_selector) {
+        return response;
+    }
+
+    match cluster::get_cluster_overview(query.cluster.as_deref(), label_selector).await {
+        Ok(overview) => etag::json_response(&req, &overview),
         Err(e) => {
             tracing::error!("Failed to get cluster overview: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -100,10 +209,76 @@ async fn cluster_overview() -> impl Responder {
     }
 }
 
+#[get("/api/cluster/capacity")]
+async fn cluster_capacity(query: web::Query<ClusterQuery>) -> impl Responder {
+    match cluster::get_capacity_summary(query.cluster.as_deref()).await {
+        Ok(summary) => HttpResponse::Ok().json(summary),
+        Err(e) => {
+            tracing::error!("Failed to get cluster capacity: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
+#[get("/api/capabilities")]
+async fn capabilities_status(query: web::Query<ClusterQuery>) -> Result<HttpResponse, KusanagiError> {
+    let cluster = query.cluster.as_deref();
+    let client = clusters::client(cluster).await?;
+    Ok(HttpResponse::Ok().json(capabilities::get_capabilities(cluster, &client).await))
+}
+
+#[derive(Deserialize)]
+struct ResourceYamlQuery {
+    cluster: Option<String>,
+    /// API group, empty for core resources
+    #[serde(default)]
+    group: String,
+    version: String,
+    kind: String,
+    plural: String,
+    /// Omitted for cluster-scoped resources
+    namespace: Option<String>,
+    name: String,
+}
+
+#[get("/api/resource/yaml")]
+async fn resource_yaml(query: web::Query<ResourceYamlQuery>) -> Result<HttpResponse, KusanagiError> {
+    let yaml = resources::get_object_yaml(
+        query.cluster.as_deref(),
+        &query.group,
+        &query.version,
+        &query.kind,
+        &query.plural,
+        query.namespace.as_deref(),
+        &query.name,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().content_type("application/yaml; charset=utf-8").body(yaml))
+}
+
+#[get("/api/clusters")]
+async fn clusters_list() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "default": "current-context",
+        "clusters": clusters::cluster_names().await
+    }))
+}
+
 #[get("/api/events")]
 async fn k8s_events(query: web::Query<EventsQuery>) -> impl Responder {
-    match events::get_events(query.event_type.clone()).await {
-        Ok(events) => HttpResponse::Ok().json(events),
+    let event_type = if query.warnings_only {
+        Some("Warning".to_string())
+    } else {
+        query.event_type.clone()
+    };
+
+    match events::get_events(query.cluster.as_deref(), event_type).await {
+        Ok(mut response) => {
+            events::sort_events(&mut response.events, query.sort);
+            HttpResponse::Ok().json(response)
+        }
         Err(e) => {
             tracing::error!("Failed to get events: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -113,10 +288,40 @@ async fn k8s_events(query: web::Query<EventsQuery>) -> impl Responder {
     }
 }
 
+#[get("/api/events/grouped")]
+async fn k8s_events_grouped(query: web::Query<ClusterNamespaceQuery>) -> impl Responder {
+    match events::get_events_grouped(query.cluster.as_deref(), query.namespace.as_deref()).await {
+        Ok(groups) => HttpResponse::Ok().json(groups),
+        Err(e) => {
+            tracing::error!("Failed to get grouped events: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ObjectEventsQuery {
+    cluster: Option<String>,
+    kind: String,
+    name: String,
+    namespace: String,
+    limit: Option<usize>,
+}
+
+#[get("/api/events/object")]
+async fn k8s_events_for_object(query: web::Query<ObjectEventsQuery>) -> Result<HttpResponse, KusanagiError> {
+    let mut events =
+        events::get_events_for_object(query.cluster.as_deref(), &query.kind, &query.name, &query.namespace).await?;
+    events.truncate(query.limit.unwrap_or(50));
+    Ok(HttpResponse::Ok().json(events))
+}
+
 #[get("/api/apps")]
-async fn apps_with_resources() -> impl Responder {
-    match apps::get_apps_with_resources().await {
-        Ok(apps) => HttpResponse::Ok().json(apps),
+async fn apps_with_resources(req: HttpRequest, query: web::Query<apps::AppsQuery>) -> impl Responder {
+    match apps::get_apps_with_resources(&query).await {
+        Ok(apps) => etag::json_response(&req, &apps),
         Err(e) => {
             tracing::error!("Failed to get apps with resources: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -133,9 +338,39 @@ async fn chat_endpoint(body: web::Json<chat::ChatRequest>) -> impl Responder {
     HttpResponse::Ok().json(response)
 }
 
+#[derive(Deserialize)]
+struct ChatSearchQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+#[get("/api/chat/search")]
+async fn chat_search(query: web::Query<ChatSearchQuery>) -> impl Responder {
+    if query.q.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "q must not be empty"
+        }));
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    match chat_storage::search_chat_messages(query.q.trim(), limit).await {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => {
+            tracing::error!("Failed to search chat history: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
 #[get("/api/backups")]
-async fn backups_status() -> impl Responder {
-    match backups::get_backups_status().await {
+async fn backups_status(query: web::Query<backups::BackupFilter>) -> impl Responder {
+    if let Some(response) = invalid_selector(query.label_selector.as_deref()) {
+        return response;
+    }
+    let filter = query.into_inner().or_env();
+
+    match backups::get_backups_status(&filter).await {
         Ok(status) => HttpResponse::Ok().json(status),
         Err(e) => {
             tracing::error!("Failed to get backups status: {}", e);
@@ -146,10 +381,59 @@ async fn backups_status() -> impl Responder {
     }
 }
 
+#[derive(Deserialize)]
+struct BackupHistoryQuery {
+    days: Option<i64>,
+    #[serde(flatten)]
+    filter: backups::BackupFilter,
+}
+
+#[get("/api/backups/history")]
+async fn backups_history(query: web::Query<BackupHistoryQuery>) -> impl Responder {
+    if let Some(response) = invalid_selector(query.filter.label_selector.as_deref()) {
+        return response;
+    }
+    let query = query.into_inner();
+    let days = query.days.unwrap_or(7).clamp(1, 90);
+    let filter = query.filter.or_env();
+
+    match backups::get_backup_history(days, &filter).await {
+        Ok(history) => HttpResponse::Ok().json(history),
+        Err(e) => {
+            tracing::error!("Failed to get backup history: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JobLogsQuery {
+    namespace: String,
+    job: String,
+    tail_lines: Option<i64>,
+}
+
+#[get("/api/backups/logs")]
+async fn backups_job_logs(query: web::Query<JobLogsQuery>) -> impl Responder {
+    let tail_lines = query.tail_lines.unwrap_or(200).clamp(1, 5000);
+
+    match backups::get_job_logs(&query.namespace, &query.job, tail_lines).await {
+        Ok(logs) => HttpResponse::Ok().content_type("text/plain; charset=utf-8").body(logs),
+        Err(e) => {
+            tracing::error!("Failed to get logs for job {}/{}: {}", query.namespace, query.job, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
 #[get("/api/storage")]
-async fn storage_status() -> impl Responder {
-    match storage::get_storage_status().await {
-        Ok(status) => HttpResponse::Ok().json(status),
+async fn storage_status(req: HttpRequest, query: web::Query<ClusterQuery>) -> impl Responder {
+    match storage::get_storage_status(query.cluster.as_deref()).await {
+        Ok(status) => etag::json_response(&req, &status),
         Err(e) => {
             tracing::error!("Failed to get storage status: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
@@ -159,6 +443,45 @@ async fn storage_status() -> impl Responder {
     }
 }
 
+#[post("/api/storage/resize")]
+async fn storage_resize(body: web::Json<storage::ResizeRequest>) -> impl Responder {
+    info!("Resize requested for PVC {}/{} to {}", body.namespace, body.name, body.size);
+
+    match storage::resize_pvc(&body.namespace, &body.name, &body.size).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "message": format!("PVC {} resize to {} requested", body.name, body.size)
+        })),
+        Err(e) => {
+            tracing::error!("Failed to resize PVC {}/{}: {}", body.namespace, body.name, e);
+            HttpResponse::build(e.status_code()).json(serde_json::json!({
+                "success": false,
+                "message": e.message()
+            }))
+        }
+    }
+}
+
+#[get("/api/storage/orphaned")]
+async fn storage_orphaned() -> impl Responder {
+    match storage::find_orphaned_pvcs().await {
+        Ok(pvcs) => HttpResponse::Ok().json(pvcs),
+        Err(e) => {
+            tracing::error!("Failed to find orphaned PVCs: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
+#[post("/api/storage/delete")]
+async fn storage_delete(body: web::Json<storage::PvcDeleteRequest>) -> Result<HttpResponse, KusanagiError> {
+    info!("PVC deletion requested for {}/{} (dry_run: {})", body.namespace, body.name, body.dry_run);
+    let result = storage::delete_pvc(&body.namespace, &body.name, body.dry_run).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
 #[get("/api/services")]
 async fn services_status() -> impl Responder {
     match services::get_services().await {
@@ -185,17 +508,58 @@ async fn ingress_status() -> impl Responder {
     }
 }
 
+/// Selects a cluster from KUSANAGI_CLUSTERS (current context when omitted)
+#[derive(Deserialize)]
+struct ClusterQuery {
+    cluster: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ClusterOverviewQuery {
+    cluster: Option<String>,
+    #[serde(rename = "labelSelector")]
+    label_selector: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NamespaceQuery {
+    namespace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ClusterNamespaceQuery {
+    cluster: Option<String>,
+    namespace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PodsQuery {
+    cluster: Option<String>,
+    namespace: Option<String>,
+    /// Collapse error pods under their controller
+    grouped: Option<bool>,
+}
+
 #[get("/api/pods/status")]
-async fn pods_status() -> impl Responder {
-    match pods::get_pods_status().await {
-        Ok(status) => HttpResponse::Ok().json(status),
-        Err(e) => {
-            tracing::error!("Failed to get pods status: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": e
-            }))
-        }
+async fn pods_status(req: HttpRequest, query: web::Query<PodsQuery>) -> Result<HttpResponse, KusanagiError> {
+    let mut status = pods::get_pods_status(query.cluster.as_deref(), query.namespace.as_deref()).await?;
+    if query.grouped.unwrap_or(false) {
+        status.groups = Some(pods::group_by_owner(&status.pods_in_error));
     }
+    Ok(etag::json_response(&req, &status))
+}
+
+#[derive(Deserialize)]
+struct PodDescribeQuery {
+    cluster: Option<String>,
+    namespace: String,
+    name: String,
+}
+
+#[get("/api/pods/describe")]
+async fn pods_describe(query: web::Query<PodDescribeQuery>) -> Result<HttpResponse, KusanagiError> {
+    let description = pods::describe_pod(query.cluster.as_deref(), &query.namespace, &query.name).await?;
+    Ok(HttpResponse::Ok().json(description))
 }
 
 #[post("/api/pods/force-delete")]
@@ -206,9 +570,41 @@ async fn force_delete_pod(body: web::Json<pods::ForceDeleteRequest>) -> impl Res
         Ok(response) => HttpResponse::Ok().json(response),
         Err(e) => {
             tracing::error!("Failed to force delete pod: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
+            HttpResponse::build(e.status_code()).json(serde_json::json!({
+                "success": false,
+                "message": e.message()
+            }))
+        }
+    }
+}
+
+#[post("/api/pods/cleanup")]
+async fn pods_cleanup(
+    body: web::Json<pods::CleanupRequest>,
+    query: web::Query<ClusterQuery>,
+) -> Result<HttpResponse, KusanagiError> {
+    info!("Pod cleanup requested (namespace: {:?}, dry_run: {})", body.namespace, body.dry_run);
+    let result = pods::cleanup_terminated_pods(
+        query.cluster.as_deref(),
+        body.namespace.as_deref(),
+        body.dry_run,
+        body.all_failed,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/api/workloads/restart")]
+async fn workload_restart(body: web::Json<workloads::RestartRequest>) -> impl Responder {
+    info!("Restart requested for {:?} {}/{}", body.kind, body.namespace, body.name);
+
+    match workloads::restart_workload(body.kind, &body.namespace, &body.name).await {
+        Ok(response) => HttpResponse::Ok().json(response),
+        Err(e) => {
+            tracing::error!("Failed to restart workload: {}", e);
+            HttpResponse::build(e.status_code()).json(serde_json::json!({
                 "success": false,
-                "message": e
+                "message": e.message()
             }))
         }
     }
@@ -219,6 +615,25 @@ struct CiliumQuery {
     namespace: Option<String>,
     limit: Option<usize>,
     format: Option<String>,
+    l7: Option<cilium::L7Filter>,
+    label: Option<String>,
+    /// Flow window, e.g. `5m`; the matrix then carries per-second rates
+    since: Option<String>,
+}
+
+impl CiliumQuery {
+    /// Parsed `?label=`, or an error when it is malformed
+    fn label_filter(&self) -> Result<Option<cilium::LabelFilter>, String> {
+        self.label
+            .as_deref()
+            .map(cilium::LabelFilter::parse)
+            .transpose()
+    }
+
+    /// Parsed `?since=`, or an error when it is malformed
+    fn window(&self) -> Result<Option<std::time::Duration>, String> {
+        self.since.as_deref().map(cilium::parse_since).transpose()
+    }
 }
 
 #[get("/api/cilium/namespaces")]
@@ -238,8 +653,16 @@ async fn cilium_namespaces() -> impl Responder {
 async fn cilium_flows(query: web::Query<CiliumQuery>) -> impl Responder {
     let namespace = query.namespace.as_deref();
     let limit = query.limit.unwrap_or(100);
-    
-    match cilium::get_hubble_flows(namespace, limit).await {
+    let (label, since) = match query.label_filter().and_then(|label| Ok((label, query.window()?))) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    };
+
+    match cilium::get_flows(namespace, limit, query.l7, label.as_ref(), since).await {
         Ok(flows) => HttpResponse::Ok().json(flows),
         Err(e) => {
             tracing::error!("Failed to get Cilium flows: {}", e);
@@ -250,11 +673,39 @@ async fn cilium_flows(query: web::Query<CiliumQuery>) -> impl Responder {
     }
 }
 
+#[get("/api/cilium/drops")]
+async fn cilium_drops(query: web::Query<CiliumQuery>) -> impl Responder {
+    let namespace = query.namespace.as_deref();
+    let limit = query.limit.unwrap_or(100);
+
+    match cilium::get_dropped_flows(namespace, limit).await {
+        Ok(flows) => HttpResponse::Ok().json(cilium::DroppedFlowsResponse {
+            total_dropped: flows.len(),
+            by_reason: cilium::count_drop_reasons(&flows),
+            flows,
+        }),
+        Err(e) => {
+            tracing::error!("Failed to get dropped flows: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
 #[get("/api/cilium/matrix")]
 async fn cilium_matrix(query: web::Query<CiliumQuery>) -> impl Responder {
     let namespace = query.namespace.as_deref();
-    
-    match cilium::get_flow_matrix(namespace).await {
+    let since = match query.window() {
+        Ok(since) => since,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    };
+
+    match cilium::get_flow_matrix(namespace, since).await {
         Ok(matrix) => HttpResponse::Ok().json(matrix),
         Err(e) => {
             tracing::error!("Failed to get flow matrix: {}", e);
@@ -265,6 +716,36 @@ async fn cilium_matrix(query: web::Query<CiliumQuery>) -> impl Responder {
     }
 }
 
+#[get("/api/cilium/topology")]
+async fn cilium_topology(query: web::Query<CiliumQuery>) -> impl Responder {
+    let namespace = query.namespace.as_deref();
+
+    match cilium::get_topology(namespace).await {
+        Ok(topology) => HttpResponse::Ok().json(topology),
+        Err(e) => {
+            tracing::error!("Failed to get flow topology: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
+#[get("/api/cilium/policies")]
+async fn cilium_policies(query: web::Query<CiliumQuery>) -> impl Responder {
+    let namespace = query.namespace.as_deref();
+
+    match cilium::get_network_policies(namespace).await {
+        Ok(policies) => HttpResponse::Ok().json(policies),
+        Err(e) => {
+            tracing::error!("Failed to get Cilium network policies: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    }
+}
+
 #[get("/api/cilium/metrics")]
 async fn cilium_metrics(query: web::Query<CiliumQuery>) -> impl Responder {
     let namespace = query.namespace.as_deref();
@@ -300,14 +781,26 @@ async fn cilium_export(query: web::Query<CiliumQuery>) -> impl Responder {
     let namespace = query.namespace.as_deref();
     let limit = query.limit.unwrap_or(1000);
     let format = query.format.as_deref().unwrap_or("json");
-    
-    match cilium::get_hubble_flows(namespace, limit).await {
+    let (label, since) = match query.label_filter().and_then(|label| Ok((label, query.window()?))) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": e
+            }))
+        }
+    };
+
+    match cilium::get_flows(namespace, limit, query.l7, label.as_ref(), since).await {
         Ok(flows) => {
             match format {
                 "csv" => HttpResponse::Ok()
                     .content_type("text/csv")
                     .insert_header(("Content-Disposition", "attachment; filename=flows.csv"))
-                    .body(cilium::export_flows_csv(&flows)),
+                    .streaming(cilium::export_flows_csv_stream(flows)),
+                "dot" => HttpResponse::Ok()
+                    .content_type("text/vnd.graphviz")
+                    .insert_header(("Content-Disposition", "attachment; filename=flows.dot"))
+                    .body(cilium::export_flows_dot(&flows)),
                 _ => HttpResponse::Ok()
                     .content_type("application/json")
                     .insert_header(("Content-Disposition", "attachment; filename=flows.json"))
@@ -329,24 +822,64 @@ struct PrometheusQuery {
 }
 
 #[get("/api/prometheus/metrics")]
-async fn prometheus_metrics() -> impl Responder {
-    match prometheus::get_cluster_metrics().await {
-        Ok(metrics) => HttpResponse::Ok().json(metrics),
-        Err(e) => {
-            tracing::error!("Failed to get Prometheus metrics: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": e
-            }))
-        }
-    }
+async fn prometheus_metrics() -> Result<HttpResponse, KusanagiError> {
+    let metrics = prometheus::get_cluster_metrics().await?;
+    Ok(HttpResponse::Ok().json(metrics))
 }
 
 #[get("/api/prometheus/query")]
-async fn prometheus_query(query: web::Query<PrometheusQuery>) -> impl Responder {
-    match prometheus::query_raw(&query.query).await {
-        Ok(result) => HttpResponse::Ok().json(result),
+async fn prometheus_query(query: web::Query<PrometheusQuery>) -> Result<HttpResponse, KusanagiError> {
+    let result = prometheus::query_raw(&query.query).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[derive(Deserialize)]
+struct MetricsQuery {
+    q: String,
+}
+
+#[get("/api/metrics/query")]
+async fn metrics_query(query: web::Query<MetricsQuery>) -> Result<HttpResponse, KusanagiError> {
+    let result = prometheus::query_raw(&query.q).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[get("/api/metrics/nodes")]
+async fn metrics_nodes() -> Result<HttpResponse, KusanagiError> {
+    let nodes = prometheus::get_node_utilization().await?;
+    Ok(HttpResponse::Ok().json(nodes))
+}
+
+#[get("/api/metrics/usage/nodes")]
+async fn metrics_usage_nodes(query: web::Query<ClusterQuery>) -> Result<HttpResponse, KusanagiError> {
+    let nodes = resource_metrics::get_node_usage(query.cluster.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(nodes))
+}
+
+#[get("/api/metrics/usage/pods")]
+async fn metrics_usage_pods(query: web::Query<ClusterNamespaceQuery>) -> Result<HttpResponse, KusanagiError> {
+    let pods = resource_metrics::get_pod_usage(query.cluster.as_deref(), query.namespace.as_deref()).await?;
+    Ok(HttpResponse::Ok().json(pods))
+}
+
+#[derive(Deserialize)]
+struct TopPodsQuery {
+    limit: Option<usize>,
+}
+
+#[get("/api/metrics/top-pods")]
+async fn metrics_top_pods(query: web::Query<TopPodsQuery>) -> Result<HttpResponse, KusanagiError> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    let pods = prometheus::get_top_pods_typed(limit).await?;
+    Ok(HttpResponse::Ok().json(pods))
+}
+
+#[get("/api/alerts")]
+async fn alerts_status(query: web::Query<NamespaceQuery>) -> impl Responder {
+    match alertmanager::get_active_alerts(query.namespace.as_deref()).await {
+        Ok(alerts) => HttpResponse::Ok().json(alerts),
         Err(e) => {
-            tracing::error!("Failed to execute Prometheus query: {}", e);
+            tracing::error!("Failed to get alerts: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": e
             }))
@@ -354,12 +887,39 @@ async fn prometheus_query(query: web::Query<PrometheusQuery>) -> impl Responder
     }
 }
 
-#[get("/api/alerts")]
-async fn alerts_status() -> impl Responder {
-    match alertmanager::get_active_alerts().await {
-        Ok(alerts) => HttpResponse::Ok().json(alerts),
+#[post("/api/alerts/ack")]
+async fn alerts_ack(body: web::Json<alertmanager::AckRequest>) -> Result<HttpResponse, KusanagiError> {
+    info!("Ack requested for alert {} ({}h by {})", body.fingerprint, body.hours, body.who);
+
+    let silence_id = alertmanager::ack_alert(&body.fingerprint, body.hours, &body.who).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "silence_id": silence_id
+    })))
+}
+
+#[get("/api/mcp/tools")]
+async fn mcp_tools() -> impl Responder {
+    HttpResponse::Ok().json(mcp::list_all_tools().await)
+}
+
+#[get("/api/integrations/status")]
+async fn integrations_status() -> impl Responder {
+    HttpResponse::Ok().json(integrations::get_integrations_status().await)
+}
+
+#[get("/api/security/critical")]
+async fn security_critical(query: web::Query<NamespaceQuery>) -> impl Responder {
+    match mcp::get_critical_vulnerabilities(query.namespace.as_deref()).await {
+        Ok(critical) => {
+            let mut response = HttpResponse::Ok();
+            if !critical.available {
+                response.insert_header(("X-Source-Unavailable", "trivy"));
+            }
+            response.json(critical.images)
+        }
         Err(e) => {
-            tracing::error!("Failed to get alerts: {}", e);
+            tracing::error!("Failed to get critical vulnerabilities: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": e
             }))
@@ -416,44 +976,275 @@ async fn export_report(query: web::Query<ExportQuery>) -> impl Responder {
     }
 }
 
+// ============================================================================
+// Graceful shutdown
+// ============================================================================
+
+/// How long in-flight requests get to finish after SIGTERM
+/// (KUSANAGI_SHUTDOWN_TIMEOUT_SECS, default 20s — keep it under the pod's terminationGracePeriodSeconds)
+fn shutdown_timeout() -> u64 {
+    std::env::var("KUSANAGI_SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(20)
+}
+
+/// Resolve on SIGTERM (Kubernetes pod termination) or Ctrl-C
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Run the server until `signal` resolves, then drain requests and run `flush`.
+/// The flush happens only after the server has stopped so spans recorded by the
+/// last in-flight requests are included.
+async fn run_until_shutdown<F, Fut>(
+    server: actix_web::dev::Server,
+    signal: impl std::future::Future<Output = ()> + Send + 'static,
+    flush: F,
+) -> std::io::Result<()>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let handle = server.handle();
+    let stopper = tokio::spawn(async move {
+        signal.await;
+        info!("Shutting down: draining in-flight requests");
+        handle.stop(true).await;
+    });
+
+    let result = server.await;
+    stopper.abort();
+
+    flush().await;
+    info!("Shutdown complete");
+    result
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     tracing_subscriber::fmt::init();
+    telemetry::install_metrics_recorder();
+    telemetry::start_periodic_flush();
+    events::watch_events(None);
+    cilium::watch_hubble_relay();
+    ws::start_alert_monitor();
+    clusters::load_clusters().await;
 
     info!("Starting Kusanagi server on port 8080");
     info!("Access the cyberpunk interface at http://localhost:8080");
 
-    HttpServer::new(|| {
+    let server = HttpServer::new(|| {
         App::new()
+            .wrap(middleware::from_fn(telemetry::track_requests))
+            .wrap(middleware::from_fn(telemetry::request_id))
             .service(health_check)
+            .service(readiness_check)
+            .service(metrics_endpoint)
             .service(index)
             .service(argocd_status)
+            .service(argocd_projects)
+            .service(argocd_appsets)
+            .service(argocd_operations)
+            .service(argocd_diff)
+            .service(argocd_app)
             .service(argocd_sync)
+            .service(argocd_sync_all)
             .service(nodes_status)
+            .service(node_detail)
             .service(cluster_overview)
+            .service(cluster_capacity)
+            .service(dashboard_summary)
+            .service(health_score)
+            .service(clusters_list)
+            .service(capabilities_status)
+            .service(resource_yaml)
             .service(k8s_events)
+            .service(k8s_events_grouped)
+            .service(k8s_events_for_object)
             .service(apps_with_resources)
             .service(chat_endpoint)
+            .service(chat_search)
             .service(backups_status)
+            .service(backups_history)
+            .service(backups_job_logs)
             .service(storage_status)
+            .service(storage_resize)
+            .service(storage_orphaned)
+            .service(storage_delete)
             .service(services_status)
             .service(ingress_status)
             .service(pods_status)
+            .service(pods_describe)
             .service(force_delete_pod)
+            .service(pods_cleanup)
+            .service(workload_restart)
             .service(cilium_namespaces)
             .service(cilium_flows)
+            .service(cilium_drops)
             .service(cilium_matrix)
+            .service(cilium_topology)
+            .service(cilium_policies)
             .service(cilium_metrics)
             .service(cilium_anomalies)
             .service(cilium_export)
             .service(prometheus_metrics)
             .service(prometheus_query)
+            .service(metrics_query)
+            .service(metrics_nodes)
+            .service(metrics_usage_nodes)
+            .service(metrics_usage_pods)
+            .service(metrics_top_pods)
             .service(alerts_status)
+            .service(alerts_ack)
+            .service(mcp_tools)
+            .service(integrations_status)
+            .service(security_critical)
             .service(export_report)
             .route("/ws/notifications", web::get().to(ws::ws_notifications))
+            .route("/ws/events", web::get().to(ws::ws_events))
+            .route("/ws/cilium/flows", web::get().to(ws::ws_cilium_flows))
             .service(Files::new("/static", "./static").show_files_listing())
     })
     .bind(("0.0.0.0", 8080))?
-    .run()
-    .await
+    .shutdown_timeout(shutdown_timeout())
+    .disable_signals()
+    .run();
+
+    run_until_shutdown(server, shutdown_signal(), telemetry::force_flush).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test;
+
+    #[actix_web::test]
+    async fn metrics_endpoint_serves_exposition_format() {
+        telemetry::install_metrics_recorder();
+        let app = test::init_service(
+            App::new()
+                .wrap(middleware::from_fn(telemetry::track_requests))
+                .service(health_check)
+                .service(metrics_endpoint),
+        )
+        .await;
+
+        test::call_service(&app, test::TestRequest::get().uri("/health").to_request()).await;
+        let response = test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+
+        assert!(response.status().is_success());
+        let content_type = response.headers().get("content-type").unwrap().to_str().unwrap();
+        assert!(content_type.starts_with("text/plain; version=0.0.4"));
+        let body = String::from_utf8(test::read_body(response).await.to_vec()).unwrap();
+        assert!(body.contains("# TYPE kusanagi_http_requests_total counter"), "{}", body);
+        assert!(body.contains(r#"endpoint="/health""#), "{}", body);
+    }
+
+    #[actix_web::test]
+    async fn shutdown_drains_requests_before_flushing() {
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        // Shutdown is signalled once the request is being handled
+        let (started, mut handling) = tokio::sync::mpsc::unbounded_channel::<()>();
+        let handler_log = log.clone();
+        let server = HttpServer::new(move || {
+            let log = handler_log.clone();
+            let started = started.clone();
+            App::new().route(
+                "/slow",
+                web::get().to(move || {
+                    let log = log.clone();
+                    let started = started.clone();
+                    async move {
+                        let _ = started.send(());
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        log.lock().unwrap().push("request finished");
+                        HttpResponse::Ok().finish()
+                    }
+                }),
+            )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}/slow", server.addrs()[0]);
+        let server = server.shutdown_timeout(5).disable_signals().run();
+
+        let request = tokio::spawn(async move { reqwest::get(&url).await.unwrap().status() });
+
+        let flush_log = log.clone();
+        run_until_shutdown(
+            server,
+            async move {
+                handling.recv().await;
+            },
+            || async move {
+                flush_log.lock().unwrap().push("telemetry flushed");
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(request.await.unwrap().is_success());
+        assert_eq!(*log.lock().unwrap(), vec!["request finished", "telemetry flushed"]);
+    }
+
+    #[actix_web::test]
+    async fn metrics_query_without_q_is_a_bad_request() {
+        let app = test::init_service(App::new().service(metrics_query)).await;
+
+        for uri in ["/api/metrics/query", "/api/metrics/query?query=up", "/api/metrics/query?q=%20"] {
+            let response = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn capacity_targets_the_requested_cluster() {
+        let app = test::init_service(App::new().service(cluster_capacity)).await;
+
+        let request = test::TestRequest::get().uri("/api/cluster/capacity?cluster=unknown").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(body["error"], "Unknown cluster: unknown");
+    }
+
+    #[actix_web::test]
+    async fn argocd_projects_target_the_requested_cluster() {
+        let app = test::init_service(App::new().service(argocd_projects)).await;
+
+        let request = test::TestRequest::get().uri("/api/argocd/projects?cluster=unknown").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn argocd_operations_target_the_requested_cluster() {
+        let app = test::init_service(App::new().service(argocd_operations)).await;
+
+        let request = test::TestRequest::get().uri("/api/argocd/operations?cluster=unknown").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
 }
@@ -2,14 +2,25 @@
 //! Provides access to various infrastructure tools via MCP servers
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use tracing::{info, warn, error};
 
+use crate::http_util;
+
 /// MCP Server endpoints (configurable via env vars)
 const MCP_KUBERNETES_URL: &str = "http://localhost:3000/mcp/kubernetes";
 const MCP_CILIUM_URL: &str = "http://localhost:3000/mcp/cilium";
 const MCP_STEAMPIPE_URL: &str = "http://localhost:3000/mcp/steampipe";
 const MCP_TRIVY_URL: &str = "http://localhost:3000/mcp/trivy";
 
+/// All known MCP servers, by name
+pub const MCP_SERVERS: [(&str, &str); 4] = [
+    ("kubernetes", MCP_KUBERNETES_URL),
+    ("cilium", MCP_CILIUM_URL),
+    ("steampipe", MCP_STEAMPIPE_URL),
+    ("trivy", MCP_TRIVY_URL),
+];
+
 /// MCP Request structure
 #[derive(Serialize)]
 pub struct McpRequest {
@@ -41,6 +52,13 @@ pub struct K8sResourceSummary {
 pub struct CiliumPolicySummary {
     pub total_policies: i32,
     pub policies: Vec<CiliumPolicy>,
+    /// False when the Cilium MCP server could not be reached
+    #[serde(default = "default_available")]
+    pub available: bool,
+}
+
+fn default_available() -> bool {
+    true
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -70,6 +88,9 @@ pub struct TrivyVulnerabilitySummary {
     pub medium: i32,
     pub low: i32,
     pub images: Vec<TrivyImageReport>,
+    /// False when the Trivy MCP server could not be reached
+    #[serde(default = "default_available")]
+    pub available: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -83,10 +104,7 @@ pub struct TrivyImageReport {
 
 /// HTTP client helper for MCP requests
 async fn mcp_request(url: &str, method: &str, params: serde_json::Value) -> Result<McpResponse, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let client = http_util::http_client(http_util::timeout_from_env("MCP_TIMEOUT_SECS", 30))?;
 
     let request = McpRequest {
         method: method.to_string(),
@@ -98,7 +116,7 @@ async fn mcp_request(url: &str, method: &str, params: serde_json::Value) -> Resu
         .json(&request)
         .send()
         .await
-        .map_err(|e| format!("MCP request failed: {}", e))?;
+        .map_err(|e| format!("MCP request failed: {}", http_util::describe_error(&e)))?;
 
     if !response.status().is_success() {
         return Err(format!("MCP server returned status: {}", response.status()));
@@ -110,6 +128,68 @@ async fn mcp_request(url: &str, method: &str, params: serde_json::Value) -> Resu
         .map_err(|e| format!("Failed to parse MCP response: {}", e))
 }
 
+// ============================================================================
+// MCP Tool Discovery
+// ============================================================================
+
+/// List the tools exposed by an MCP server
+pub async fn list_tools(server_url: &str) -> Result<Vec<String>, String> {
+    let response = mcp_request(server_url, "list_tools", serde_json::json!({})).await?;
+
+    if !response.success {
+        return Err(response.error.unwrap_or_else(|| "Unknown MCP error".to_string()));
+    }
+
+    response
+        .data
+        .map(parse_tool_names)
+        .ok_or_else(|| "No data in MCP response".to_string())
+}
+
+/// Accepts `["a", "b"]`, `[{"name": "a"}, ...]` or `{"tools": [...]}`
+fn parse_tool_names(data: serde_json::Value) -> Vec<String> {
+    let tools = match data {
+        serde_json::Value::Object(mut obj) => obj.remove("tools").unwrap_or_default(),
+        other => other,
+    };
+
+    tools
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    serde_json::Value::String(name) => Some(name.clone()),
+                    serde_json::Value::Object(obj) => {
+                        obj.get("name").and_then(|n| n.as_str()).map(|n| n.to_string())
+                    }
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Tools of every reachable MCP server, keyed by server name
+/// Servers that fail to answer are left out
+pub async fn list_all_tools() -> BTreeMap<String, Vec<String>> {
+    let results = futures::future::join_all(
+        MCP_SERVERS.iter().map(|(name, url)| async move { (*name, list_tools(url).await) }),
+    )
+    .await;
+
+    results
+        .into_iter()
+        .filter_map(|(name, result)| match result {
+            Ok(tools) => Some((name.to_string(), tools)),
+            Err(e) => {
+                warn!("MCP {} tool discovery failed: {}", name, e);
+                None
+            }
+        })
+        .collect()
+}
+
 // ============================================================================
 // Kubernetes MCP Integration
 // ============================================================================
@@ -180,6 +260,7 @@ pub async fn get_cilium_policies(namespace: Option<&str>) -> Result<CiliumPolicy
             Ok(CiliumPolicySummary {
                 total_policies: 0,
                 policies: vec![],
+                available: false,
             })
         }
     }
@@ -189,15 +270,79 @@ pub async fn get_cilium_policies(namespace: Option<&str>) -> Result<CiliumPolicy
 // Steampipe MCP Integration
 // ============================================================================
 
+/// Accept a single read-only SELECT; a trailing `;` is fine, a second statement is not
+pub fn validate_select(sql: &str) -> Result<(), String> {
+    if !sql.trim().to_uppercase().starts_with("SELECT") {
+        return Err("Only SELECT queries are allowed".to_string());
+    }
+    let chained = unquoted_semicolons(sql)
+        .into_iter()
+        .any(|pos| !sql[pos + 1..].trim().is_empty());
+    if chained {
+        return Err("Only a single SELECT statement is allowed".to_string());
+    }
+    Ok(())
+}
+
+/// Byte offsets of `;` outside string literals, quoted identifiers, dollar quotes and comments
+fn unquoted_semicolons(sql: &str) -> Vec<usize> {
+    let bytes = sql.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // 'it''s' reads as two adjacent literals, which skips the same bytes
+            quote @ (b'\'' | b'"') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'$' => {
+                if let Some(tag_len) = dollar_tag_len(&bytes[i..]) {
+                    let tag = &bytes[i..i + tag_len];
+                    let body = i + tag_len;
+                    i = bytes[body..]
+                        .windows(tag_len)
+                        .position(|w| w == tag)
+                        .map_or(bytes.len(), |end| body + end + tag_len - 1);
+                }
+            }
+            b';' => found.push(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Length of a PostgreSQL dollar-quote tag (`$$` or `$name$`) at the start of `bytes`
+fn dollar_tag_len(bytes: &[u8]) -> Option<usize> {
+    let name_len = bytes[1..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+    let starts_with_digit = bytes.get(1).is_some_and(|b| b.is_ascii_digit());
+    (!starts_with_digit && bytes.get(1 + name_len) == Some(&b'$')).then_some(name_len + 2)
+}
+
 /// Execute Steampipe SQL query via MCP
 pub async fn query_steampipe(sql: &str) -> Result<SteampipeResult, String> {
     info!("Executing Steampipe query via MCP: {}", sql);
 
-    // Validate query is read-only (SELECT only)
-    let sql_upper = sql.trim().to_uppercase();
-    if !sql_upper.starts_with("SELECT") {
-        return Err("Only SELECT queries are allowed".to_string());
-    }
+    validate_select(sql)?;
 
     let params = serde_json::json!({
         "query": sql
@@ -255,17 +400,36 @@ pub async fn get_trivy_vulnerabilities() -> Result<TrivyVulnerabilitySummary, St
                 medium: 0,
                 low: 0,
                 images: vec![],
+                available: false,
             })
         }
     }
 }
 
-/// Get critical vulnerabilities only
-pub async fn get_critical_vulnerabilities() -> Result<Vec<TrivyImageReport>, String> {
+/// Images with critical findings, and whether Trivy could be reached
+pub struct CriticalVulnerabilities {
+    pub available: bool,
+    pub images: Vec<TrivyImageReport>,
+}
+
+/// Get critical vulnerabilities only, most critical first
+/// Optionally restricted to one namespace
+pub async fn get_critical_vulnerabilities(namespace: Option<&str>) -> Result<CriticalVulnerabilities, String> {
     let summary = get_trivy_vulnerabilities().await?;
-    Ok(summary.images.into_iter()
+    Ok(CriticalVulnerabilities {
+        available: summary.available,
+        images: critical_images(summary.images, namespace),
+    })
+}
+
+fn critical_images(images: Vec<TrivyImageReport>, namespace: Option<&str>) -> Vec<TrivyImageReport> {
+    let mut critical: Vec<TrivyImageReport> = images
+        .into_iter()
         .filter(|img| img.critical_count > 0)
-        .collect())
+        .filter(|img| namespace.is_none() || namespace == Some(img.namespace.as_str()))
+        .collect();
+    critical.sort_by_key(|img| std::cmp::Reverse(img.critical_count));
+    critical
 }
 
 // ============================================================================
@@ -296,6 +460,10 @@ pub fn format_k8s_resources(resources: &K8sResourceSummary) -> String {
 
 /// Format Cilium policies for chat response
 pub fn format_cilium_policies(summary: &CiliumPolicySummary) -> String {
+    if !summary.available {
+        return "## 🛡️ Cilium Policies\n\n⚠️ The Cilium MCP server is offline, policies cannot be listed right now.".to_string();
+    }
+
     if summary.policies.is_empty() {
         return "## 🛡️ Cilium Policies\n\nNo network policies found.".to_string();
     }
@@ -393,3 +561,83 @@ pub fn format_steampipe_result(result: &SteampipeResult) -> String {
 
     lines.join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semicolons_inside_literals_are_allowed() {
+        assert!(validate_select("SELECT 'a;b'").is_ok());
+        assert!(validate_select("SELECT 'it''s; fine' FROM t;").is_ok());
+        assert!(validate_select(r#"SELECT "odd;name" FROM t"#).is_ok());
+        assert!(validate_select("SELECT $$a;b$$, $tag$c;d$tag$").is_ok());
+        assert!(validate_select("SELECT 1 -- trailing; comment").is_ok());
+        assert!(validate_select("SELECT /* ; */ 1;  ").is_ok());
+    }
+
+    #[test]
+    fn chained_or_non_select_statements_are_rejected() {
+        assert!(validate_select("SELECT 1; DROP TABLE users").is_err());
+        assert!(validate_select("select 'a'; select 'b'").is_err());
+        assert!(validate_select("SELECT 'a;' ; DELETE FROM t").is_err());
+        assert!(validate_select("SELECT $1; DELETE FROM t").is_err());
+        assert!(validate_select("DELETE FROM t").is_err());
+    }
+
+    #[test]
+    fn unreachable_cilium_is_reported_as_offline() {
+        let offline = CiliumPolicySummary {
+            total_policies: 0,
+            policies: vec![],
+            available: false,
+        };
+        let message = format_cilium_policies(&offline);
+        assert!(message.contains("Cilium MCP server is offline"));
+        assert!(!message.contains("No network policies found"));
+
+        let empty = CiliumPolicySummary { available: true, ..offline };
+        assert!(format_cilium_policies(&empty).contains("No network policies found"));
+    }
+
+    #[test]
+    fn tool_names_are_parsed_from_every_shape() {
+        let expected = vec!["list_pods".to_string(), "get_logs".to_string()];
+        let shapes = [
+            serde_json::json!(["list_pods", "get_logs"]),
+            serde_json::json!([{"name": "list_pods"}, {"name": "get_logs", "description": "Tail logs"}]),
+            serde_json::json!({"tools": ["list_pods", {"name": "get_logs"}]}),
+        ];
+        for data in shapes {
+            assert_eq!(parse_tool_names(data), expected);
+        }
+        assert!(parse_tool_names(serde_json::json!({"other": 1})).is_empty());
+        assert!(parse_tool_names(serde_json::json!([42, {"id": "x"}])).is_empty());
+    }
+
+    fn image(name: &str, namespace: &str, critical_count: i32) -> TrivyImageReport {
+        TrivyImageReport {
+            image: name.to_string(),
+            namespace: namespace.to_string(),
+            critical_count,
+            high_count: 0,
+            last_scan: String::new(),
+        }
+    }
+
+    #[test]
+    fn critical_images_are_filtered_and_sorted() {
+        let images = vec![
+            image("redis", "cache", 2),
+            image("nginx", "web", 0),
+            image("postgres", "db", 7),
+            image("api", "web", 4),
+        ];
+
+        let all: Vec<String> = critical_images(images.clone(), None).into_iter().map(|i| i.image).collect();
+        assert_eq!(all, vec!["postgres", "api", "redis"]);
+
+        let web: Vec<String> = critical_images(images, Some("web")).into_iter().map(|i| i.image).collect();
+        assert_eq!(web, vec!["api"]);
+    }
+}
@@ -1,12 +1,12 @@
 use chrono::{DateTime, Utc};
 use k8s_openapi::api::core::v1::{Node, Pod};
-use kube::{
-    api::{Api, ListParams},
-    Client,
-};
+use kube::api::Api;
 use serde::Serialize;
 use tracing::info;
 
+use crate::error::KusanagiError;
+use crate::{cluster, clusters, k8s_cache, pods};
+
 /// Node status response
 #[derive(Clone, Debug, Serialize)]
 pub struct NodesStatusResponse {
@@ -30,6 +30,11 @@ pub struct NodeInfo {
     pub cpu_allocatable: String,
     pub memory_capacity: String,
     pub memory_allocatable: String,
+    pub ephemeral_storage_capacity: String,
+    pub ephemeral_storage_allocatable: String,
+    /// `nvidia.com/gpu` capacity, only present on GPU nodes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpu_capacity: Option<String>,
     pub pod_count: usize,
     pub pod_capacity: String,
     pub pods_in_error: usize,
@@ -37,6 +42,8 @@ pub struct NodeInfo {
     pub uptime: Option<String>,
     pub uptime_seconds: Option<i64>,
     pub conditions: Vec<NodeCondition>,
+    /// Why the node is NotReady, from the Ready condition (e.g. "KubeletNotReady: ...")
+    pub not_ready_reason: Option<String>,
     pub labels: std::collections::BTreeMap<String, String>,
 }
 
@@ -44,182 +51,84 @@ pub struct NodeInfo {
 pub struct NodeCondition {
     pub condition_type: String,
     pub status: String,
+    pub reason: Option<String>,
     pub message: Option<String>,
 }
 
-/// Get all nodes status with resource information
-pub async fn get_nodes_status() -> Result<NodesStatusResponse, String> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+/// One node with every pod scheduled on it, for the node drill-down page
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeDetail {
+    #[serde(flatten)]
+    pub node: NodeInfo,
+    pub pods: Vec<NodePod>,
+}
 
-    let nodes_api: Api<Node> = Api::all(client.clone());
-    let pods_api: Api<Pod> = Api::all(client);
+#[derive(Clone, Debug, Serialize)]
+pub struct NodePod {
+    pub name: String,
+    pub namespace: String,
+    pub phase: String,
+    pub in_error: bool,
+}
+
+/// Resource name of NVIDIA GPUs advertised by the device plugin
+const GPU_RESOURCE: &str = "nvidia.com/gpu";
+
+/// GPU count from a node's capacity map, None when the node has no GPU resource
+pub fn gpu_capacity(
+    capacity: &std::collections::BTreeMap<String, k8s_openapi::apimachinery::pkg::api::resource::Quantity>,
+) -> Option<String> {
+    capacity
+        .get(GPU_RESOURCE)
+        .map(|q| q.0.clone())
+        .filter(|q| q != "0")
+}
+
+/// Reason and message of the Ready condition when it isn't True
+pub fn not_ready_reason(conditions: &[NodeCondition]) -> Option<String> {
+    let ready = match conditions.iter().find(|c| c.condition_type == "Ready") {
+        Some(ready) => ready,
+        None => return Some("Ready condition not reported".to_string()),
+    };
+    if ready.status == "True" {
+        return None;
+    }
+
+    let reason = ready.reason.clone().unwrap_or_else(|| format!("Ready={}", ready.status));
+    Some(match ready.message.as_deref().filter(|m| !m.is_empty()) {
+        Some(message) => format!("{}: {}", reason, message),
+        None => reason,
+    })
+}
+
+/// Get all nodes status with resource information
+pub async fn get_nodes_status(cluster: Option<&str>) -> Result<NodesStatusResponse, String> {
+    let client = clusters::client(cluster).await.map_err(|e| e.to_string())?;
 
-    let nodes = nodes_api
-        .list(&ListParams::default())
+    let nodes = k8s_cache::list_nodes(client.clone(), cluster)
         .await
         .map_err(|e| format!("Failed to list nodes: {}", e))?;
 
-    let pods = pods_api
-        .list(&ListParams::default())
+    let pods = k8s_cache::list_pods(client, cluster, None)
         .await
         .map_err(|e| format!("Failed to list pods: {}", e))?;
 
     let now = Utc::now();
     let mut response = NodesStatusResponse {
-        total_nodes: nodes.items.len(),
+        total_nodes: nodes.len(),
         ready_nodes: 0,
         not_ready_nodes: 0,
         nodes: Vec::new(),
     };
 
-    for node in nodes.items {
-        let name = node.metadata.name.clone().unwrap_or_default();
-        let labels = node.metadata.labels.clone().unwrap_or_default();
-        
-        let status = node.status.as_ref();
-        let spec = node.spec.as_ref();
-
-        // Get node info
-        let node_info = status.and_then(|s| s.node_info.as_ref());
-        
-        let architecture = node_info
-            .map(|i| i.architecture.clone())
-            .unwrap_or_else(|| "unknown".to_string());
-        
-        let os = node_info
-            .map(|i| i.operating_system.clone())
-            .unwrap_or_else(|| "unknown".to_string());
-        
-        let kernel_version = node_info
-            .map(|i| i.kernel_version.clone())
-            .unwrap_or_else(|| "unknown".to_string());
-        
-        let kubelet_version = node_info
-            .map(|i| i.kubelet_version.clone())
-            .unwrap_or_else(|| "unknown".to_string());
-        
-        let container_runtime = node_info
-            .map(|i| i.container_runtime_version.clone())
-            .unwrap_or_else(|| "unknown".to_string());
-
-        // Get capacity
-        let capacity = status.and_then(|s| s.capacity.as_ref());
-        let allocatable = status.and_then(|s| s.allocatable.as_ref());
-
-        let cpu_capacity = capacity
-            .and_then(|c| c.get("cpu"))
-            .map(|q| q.0.clone())
-            .unwrap_or_else(|| "0".to_string());
-
-        let cpu_allocatable = allocatable
-            .and_then(|a| a.get("cpu"))
-            .map(|q| q.0.clone())
-            .unwrap_or_else(|| "0".to_string());
-
-        let memory_capacity = capacity
-            .and_then(|c| c.get("memory"))
-            .map(|q| format_memory(&q.0))
-            .unwrap_or_else(|| "0".to_string());
-
-        let memory_allocatable = allocatable
-            .and_then(|a| a.get("memory"))
-            .map(|q| format_memory(&q.0))
-            .unwrap_or_else(|| "0".to_string());
-
-        let pod_capacity = capacity
-            .and_then(|c| c.get("pods"))
-            .map(|q| q.0.clone())
-            .unwrap_or_else(|| "0".to_string());
-
-        // Count pods on this node
-        let node_pods: Vec<&Pod> = pods
-            .items
-            .iter()
-            .filter(|p| {
-                p.spec
-                    .as_ref()
-                    .and_then(|s| s.node_name.as_ref())
-                    .map(|n| n == &name)
-                    .unwrap_or(false)
-            })
-            .collect();
-
-        let pod_count = node_pods.len();
-
-        // Find pods in error state
-        let error_pods: Vec<String> = node_pods
-            .iter()
-            .filter(|p| is_pod_in_error(p))
-            .filter_map(|p| p.metadata.name.clone())
-            .collect();
-        
-        let pods_in_error = error_pods.len();
-
-        // Get node conditions
-        let conditions: Vec<NodeCondition> = status
-            .and_then(|s| s.conditions.as_ref())
-            .map(|conds| {
-                conds
-                    .iter()
-                    .map(|c| NodeCondition {
-                        condition_type: c.type_.clone(),
-                        status: c.status.clone(),
-                        message: c.message.clone(),
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        // Check if node is ready
-        let is_ready = conditions
-            .iter()
-            .any(|c| c.condition_type == "Ready" && c.status == "True");
-
-        let node_status = if is_ready {
+    for node in nodes.iter() {
+        let info = node_info(node, &pods, &now);
+        if info.status == "Ready" {
             response.ready_nodes += 1;
-            "Ready".to_string()
         } else {
             response.not_ready_nodes += 1;
-            "NotReady".to_string()
-        };
-
-        // Calculate uptime from creation timestamp
-        let (uptime, uptime_seconds) = node
-            .metadata
-            .creation_timestamp
-            .as_ref()
-            .and_then(|ts| {
-                DateTime::parse_from_rfc3339(&ts.0.to_rfc3339()).ok().map(|dt| {
-                    let duration = now.signed_duration_since(dt.with_timezone(&Utc));
-                    let seconds = duration.num_seconds();
-                    (Some(format_uptime(seconds)), Some(seconds))
-                })
-            })
-            .unwrap_or((None, None));
-
-        response.nodes.push(NodeInfo {
-            name,
-            status: node_status,
-            architecture,
-            os,
-            kernel_version,
-            kubelet_version,
-            container_runtime,
-            cpu_capacity,
-            cpu_allocatable,
-            memory_capacity,
-            memory_allocatable,
-            pod_count,
-            pod_capacity,
-            pods_in_error,
-            error_pod_names: error_pods,
-            uptime,
-            uptime_seconds,
-            conditions,
-            labels,
-        });
+        }
+        response.nodes.push(info);
     }
 
     // Sort nodes by name
@@ -233,6 +142,200 @@ pub async fn get_nodes_status() -> Result<NodesStatusResponse, String> {
     Ok(response)
 }
 
+/// Build the API view of one node from its object and the pods scheduled on the cluster
+fn node_info(node: &Node, pods: &[Pod], now: &DateTime<Utc>) -> NodeInfo {
+    let name = node.metadata.name.clone().unwrap_or_default();
+    let labels = cluster::allowed_labels(node.metadata.labels.clone().unwrap_or_default());
+    
+    let status = node.status.as_ref();
+    let spec = node.spec.as_ref();
+
+    // Get node info
+    let node_info = status.and_then(|s| s.node_info.as_ref());
+    
+    let architecture = node_info
+        .map(|i| i.architecture.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    let os = node_info
+        .map(|i| i.operating_system.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    let kernel_version = node_info
+        .map(|i| i.kernel_version.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    let kubelet_version = node_info
+        .map(|i| i.kubelet_version.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    let container_runtime = node_info
+        .map(|i| i.container_runtime_version.clone())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Get capacity
+    let capacity = status.and_then(|s| s.capacity.as_ref());
+    let allocatable = status.and_then(|s| s.allocatable.as_ref());
+
+    let cpu_capacity = capacity
+        .and_then(|c| c.get("cpu"))
+        .map(|q| q.0.clone())
+        .unwrap_or_else(|| "0".to_string());
+
+    let cpu_allocatable = allocatable
+        .and_then(|a| a.get("cpu"))
+        .map(|q| q.0.clone())
+        .unwrap_or_else(|| "0".to_string());
+
+    let memory_capacity = capacity
+        .and_then(|c| c.get("memory"))
+        .map(|q| format_memory(&q.0))
+        .unwrap_or_else(|| "0".to_string());
+
+    let memory_allocatable = allocatable
+        .and_then(|a| a.get("memory"))
+        .map(|q| format_memory(&q.0))
+        .unwrap_or_else(|| "0".to_string());
+
+    let ephemeral_storage_capacity = capacity
+        .and_then(|c| c.get("ephemeral-storage"))
+        .map(|q| format_storage(&q.0))
+        .unwrap_or_else(|| "0".to_string());
+
+    let ephemeral_storage_allocatable = allocatable
+        .and_then(|a| a.get("ephemeral-storage"))
+        .map(|q| format_storage(&q.0))
+        .unwrap_or_else(|| "0".to_string());
+
+    let gpu_capacity = capacity.and_then(gpu_capacity);
+
+    let pod_capacity = capacity
+        .and_then(|c| c.get("pods"))
+        .map(|q| q.0.clone())
+        .unwrap_or_else(|| "0".to_string());
+
+    // Count pods on this node
+    let node_pods = pods_on_node(pods, &name);
+
+    let pod_count = node_pods.len();
+
+    // Find pods in error state
+    let error_pods: Vec<String> = node_pods
+        .iter()
+        .filter(|p| is_pod_in_error(p))
+        .filter_map(|p| p.metadata.name.clone())
+        .collect();
+    
+    let pods_in_error = error_pods.len();
+
+    // Get node conditions
+    let conditions: Vec<NodeCondition> = status
+        .and_then(|s| s.conditions.as_ref())
+        .map(|conds| {
+            conds
+                .iter()
+                .map(|c| NodeCondition {
+                    condition_type: c.type_.clone(),
+                    status: c.status.clone(),
+                    reason: c.reason.clone(),
+                    message: c.message.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Check if node is ready
+    let is_ready = conditions
+        .iter()
+        .any(|c| c.condition_type == "Ready" && c.status == "True");
+
+    let not_ready_reason = not_ready_reason(&conditions);
+
+    let node_status = if is_ready { "Ready" } else { "NotReady" }.to_string();
+
+    // Calculate uptime from creation timestamp
+    let (uptime, uptime_seconds) = node
+        .metadata
+        .creation_timestamp
+        .as_ref()
+        .and_then(|ts| {
+            DateTime::parse_from_rfc3339(&ts.0.to_rfc3339()).ok().map(|dt| {
+                let duration = now.signed_duration_since(dt.with_timezone(&Utc));
+                let seconds = duration.num_seconds();
+                (Some(format_uptime(seconds)), Some(seconds))
+            })
+        })
+        .unwrap_or((None, None));
+
+    NodeInfo {
+        name,
+        status: node_status,
+        architecture,
+        os,
+        kernel_version,
+        kubelet_version,
+        container_runtime,
+        cpu_capacity,
+        cpu_allocatable,
+        memory_capacity,
+        memory_allocatable,
+        ephemeral_storage_capacity,
+        ephemeral_storage_allocatable,
+        gpu_capacity,
+        pod_count,
+        pod_capacity,
+        pods_in_error,
+        error_pod_names: error_pods,
+        uptime,
+        uptime_seconds,
+        conditions,
+        not_ready_reason,
+        labels,
+    }
+}
+
+/// Get one node and the pods scheduled on it
+pub async fn get_node(name: &str) -> Result<NodeDetail, KusanagiError> {
+    let client = clusters::client(None).await?;
+
+    let nodes_api: Api<Node> = Api::all(client.clone());
+    let node = nodes_api
+        .get(name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get node {}", name), e))?;
+
+    let pods = k8s_cache::list_all_pods(client)
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list pods", e))?;
+
+    let mut node_pods: Vec<NodePod> = pods_on_node(&pods, name)
+        .into_iter()
+        .map(|pod| NodePod {
+            name: pod.metadata.name.clone().unwrap_or_default(),
+            namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+            phase: pod
+                .status
+                .as_ref()
+                .and_then(|s| s.phase.clone())
+                .unwrap_or_else(|| "Unknown".to_string()),
+            in_error: is_pod_in_error(pod),
+        })
+        .collect();
+    node_pods.sort_by(|a, b| a.namespace.cmp(&b.namespace).then(a.name.cmp(&b.name)));
+
+    Ok(NodeDetail {
+        node: node_info(&node, &pods, &Utc::now()),
+        pods: node_pods,
+    })
+}
+
+/// Pods scheduled on the named node
+pub fn pods_on_node<'a>(pods: &'a [Pod], node_name: &str) -> Vec<&'a Pod> {
+    pods.iter()
+        .filter(|p| p.spec.as_ref().and_then(|s| s.node_name.as_deref()) == Some(node_name))
+        .collect()
+}
+
 /// Check if a pod is in error state
 fn is_pod_in_error(pod: &Pod) -> bool {
     let phase = pod
@@ -252,18 +355,12 @@ fn is_pod_in_error(pod: &Pod) -> bool {
         if let Some(container_statuses) = &status.container_statuses {
             for cs in container_statuses {
                 if let Some(waiting) = &cs.state.as_ref().and_then(|s| s.waiting.as_ref()) {
-                    let reason = waiting.reason.as_deref().unwrap_or("");
-                    if reason == "CrashLoopBackOff"
-                        || reason == "Error"
-                        || reason == "ImagePullBackOff"
-                        || reason == "ErrImagePull"
-                        || reason == "CreateContainerError"
-                    {
+                    if waiting.reason.as_deref().is_some_and(pods::is_error_reason) {
                         return true;
                     }
                 }
                 // Check restart count
-                if cs.restart_count > 5 {
+                if cs.restart_count > pods::restart_threshold() {
                     return true;
                 }
             }
@@ -273,6 +370,11 @@ fn is_pod_in_error(pod: &Pod) -> bool {
     false
 }
 
+/// Ephemeral storage is often reported in plain bytes, so normalize before formatting
+fn format_storage(quantity: &str) -> String {
+    cluster::format_bytes(cluster::parse_capacity_to_bytes(quantity))
+}
+
 /// Format memory from Ki to human readable
 fn format_memory(ki_str: &str) -> String {
     // Remove Ki suffix and parse
@@ -320,3 +422,112 @@ fn format_uptime(seconds: i64) -> String {
         format!("{}s", seconds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::core::v1::NodeStatus;
+
+    fn node(status: NodeStatus) -> Node {
+        Node {
+            metadata: kube::api::ObjectMeta {
+                name: Some("worker-1".to_string()),
+                ..Default::default()
+            },
+            status: Some(status),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn not_ready_reason_comes_from_a_false_ready_condition() {
+        let status = NodeStatus {
+            conditions: Some(vec![
+                k8s_openapi::api::core::v1::NodeCondition {
+                    type_: "MemoryPressure".to_string(),
+                    status: "False".to_string(),
+                    ..Default::default()
+                },
+                k8s_openapi::api::core::v1::NodeCondition {
+                    type_: "Ready".to_string(),
+                    status: "False".to_string(),
+                    reason: Some("KubeletNotReady".to_string()),
+                    message: Some("container runtime network not ready".to_string()),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let info = node_info(&node(status), &[], &Utc::now());
+        assert_eq!(info.status, "NotReady");
+        assert_eq!(
+            info.not_ready_reason.as_deref(),
+            Some("KubeletNotReady: container runtime network not ready")
+        );
+
+        let ready = NodeCondition {
+            condition_type: "Ready".to_string(),
+            status: "True".to_string(),
+            reason: Some("KubeletReady".to_string()),
+            message: None,
+        };
+        assert_eq!(not_ready_reason(&[ready]), None);
+        assert_eq!(not_ready_reason(&[]).as_deref(), Some("Ready condition not reported"));
+    }
+
+    #[test]
+    fn gpu_capacity_is_read_only_on_gpu_nodes() {
+        use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+        let capacity = |extra: &[(&str, &str)]| {
+            [("cpu", "8"), ("memory", "32Gi"), ("ephemeral-storage", "100Gi")]
+                .iter()
+                .chain(extra)
+                .map(|(k, v)| (k.to_string(), Quantity(v.to_string())))
+                .collect::<std::collections::BTreeMap<_, _>>()
+        };
+
+        let gpu_node = node(NodeStatus {
+            capacity: Some(capacity(&[("nvidia.com/gpu", "4")])),
+            ..Default::default()
+        });
+        let info = node_info(&gpu_node, &[], &Utc::now());
+        assert_eq!(info.gpu_capacity.as_deref(), Some("4"));
+        assert_eq!(serde_json::to_value(&info).unwrap()["gpu_capacity"], "4");
+
+        let cpu_node = node(NodeStatus {
+            capacity: Some(capacity(&[])),
+            ..Default::default()
+        });
+        let info = node_info(&cpu_node, &[], &Utc::now());
+        assert_eq!(info.gpu_capacity, None);
+        assert!(serde_json::to_value(&info).unwrap().get("gpu_capacity").is_none());
+        assert_eq!(gpu_capacity(&capacity(&[("nvidia.com/gpu", "0")])), None);
+    }
+
+    #[test]
+    fn only_pods_scheduled_on_the_node_are_included() {
+        let pod = |name: &str, node_name: Option<&str>| -> Pod {
+            serde_json::from_value(serde_json::json!({
+                "metadata": { "name": name, "namespace": "apps" },
+                "spec": { "containers": [], "nodeName": node_name },
+                "status": { "phase": "Running" }
+            }))
+            .unwrap()
+        };
+        let pods = vec![
+            pod("web-1", Some("worker-1")),
+            pod("web-2", Some("worker-2")),
+            pod("pending", None),
+            pod("db-0", Some("worker-1")),
+        ];
+
+        let names: Vec<_> = pods_on_node(&pods, "worker-1")
+            .iter()
+            .filter_map(|p| p.metadata.name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["web-1", "db-0"]);
+        assert!(pods_on_node(&pods, "worker-3").is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! Push critical alerts to a webhook (Slack or any JSON endpoint)
+//! Alerts otherwise only reach browsers connected to /ws; ALERT_WEBHOOK_URL delivers them
+//! when nobody is watching the dashboard.
+
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::cache::TtlMap;
+use crate::http_util;
+use crate::ws::NotificationMessage;
+
+lazy_static::lazy_static! {
+    /// Fingerprints already delivered, so each alert is sent once per ALERT_WEBHOOK_DEDUP_SECS
+    static ref SENT: TtlMap<()> = TtlMap::new(Duration::from_secs(
+        std::env::var("ALERT_WEBHOOK_DEDUP_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(3600),
+    ));
+}
+
+/// Severities forwarded to the webhook
+const WEBHOOK_SEVERITIES: &[&str] = &["critical", "error"];
+
+/// Webhook for critical alerts (ALERT_WEBHOOK_URL), unset to disable
+pub fn webhook_url() -> Option<String> {
+    std::env::var("ALERT_WEBHOOK_URL").ok().filter(|s| !s.trim().is_empty())
+}
+
+/// Slack incoming-webhook payload unless ALERT_WEBHOOK_FORMAT=generic
+fn slack_format() -> bool {
+    !std::env::var("ALERT_WEBHOOK_FORMAT").is_ok_and(|v| v.eq_ignore_ascii_case("generic"))
+}
+
+/// Client reused across webhook deliveries (ALERT_WEBHOOK_TIMEOUT_SECS, default 5s)
+static CLIENT: http_util::SharedClient =
+    http_util::SharedClient::new(|| http_util::timeout_from_env("ALERT_WEBHOOK_TIMEOUT_SECS", 5));
+
+/// Identity of an alert for de-duplication: the upstream fingerprint when there is one,
+/// otherwise source and title, so a changing count in the message doesn't resend it
+pub fn fingerprint(message: &NotificationMessage) -> Option<String> {
+    match message {
+        NotificationMessage::Alert { fingerprint: Some(fingerprint), .. } => Some(fingerprint.clone()),
+        NotificationMessage::Alert { source, title, .. } => {
+            let mut hasher = DefaultHasher::new();
+            (source, title).hash(&mut hasher);
+            Some(format!("{:016x}", hasher.finish()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether an alert is severe enough for the webhook
+pub fn is_critical(message: &NotificationMessage) -> bool {
+    matches!(message, NotificationMessage::Alert { severity, .. } if WEBHOOK_SEVERITIES.contains(&severity.as_str()))
+}
+
+/// JSON body for the webhook: Slack blocks, or the notification itself for generic receivers
+pub fn webhook_payload(message: &NotificationMessage, slack: bool) -> serde_json::Value {
+    if !slack {
+        return json!(message);
+    }
+    match message {
+        NotificationMessage::Alert { severity, title, message, source, timestamp, .. } => {
+            let emoji = if severity == "critical" { "🔴" } else { "🟠" };
+            json!({
+                "text": format!("{} {}: {}", emoji, title, message),
+                "blocks": [
+                    {
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": format!("{} *{}*\n{}", emoji, title, message)
+                        }
+                    },
+                    {
+                        "type": "context",
+                        "elements": [
+                            {
+                                "type": "mrkdwn",
+                                "text": format!("Kusanagi | {} | {} | {}", severity, source, timestamp)
+                            }
+                        ]
+                    }
+                ]
+            })
+        }
+        other => json!({ "text": serde_json::to_string(other).unwrap_or_default() }),
+    }
+}
+
+/// POST a notification to `url`; failures are returned for the caller to log
+pub async fn send_webhook(url: &str, message: &NotificationMessage) -> Result<(), String> {
+    let response = CLIENT
+        .get()?
+        .post(url)
+        .json(&webhook_payload(message, slack_format()))
+        .send()
+        .await
+        .map_err(|e| format!("Webhook request failed: {}", http_util::describe_error(&e)))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Send each critical alert not delivered recently to ALERT_WEBHOOK_URL
+/// Errors are logged and never reach the caller
+pub async fn notify_critical(alerts: &[NotificationMessage]) {
+    if let Some(url) = webhook_url() {
+        deliver(&url, alerts, &SENT).await;
+    }
+}
+
+/// Send the critical alerts missing from `sent`, recording the ones delivered
+/// A failed send is forgotten again so the next check retries it
+async fn deliver(url: &str, alerts: &[NotificationMessage], sent: &TtlMap<()>) {
+    for alert in alerts.iter().filter(|a| is_critical(a)) {
+        let Some(fingerprint) = fingerprint(alert) else {
+            continue;
+        };
+        if sent.get(&fingerprint).is_some() {
+            continue;
+        }
+        // Mark first so a concurrent check doesn't send it twice
+        sent.insert(&fingerprint, ());
+
+        match send_webhook(url, alert).await {
+            Ok(()) => info!("Alert {} sent to webhook", fingerprint),
+            Err(e) => {
+                sent.remove(&fingerprint);
+                warn!("Failed to send alert {} to webhook: {}", fingerprint, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(severity: &str, title: &str, message: &str, fingerprint: Option<&str>) -> NotificationMessage {
+        NotificationMessage::Alert {
+            severity: severity.to_string(),
+            title: title.to_string(),
+            message: message.to_string(),
+            source: "pods".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            fingerprint: fingerprint.map(String::from),
+        }
+    }
+
+    #[test]
+    fn fingerprint_ignores_message() {
+        let a = alert("error", "Pods in Error", "3 pods are in error state", None);
+        let b = alert("error", "Pods in Error", "4 pods are in error state", None);
+        let c = alert("error", "ArgoCD Apps Unhealthy", "3 pods are in error state", None);
+        assert_eq!(fingerprint(&a), fingerprint(&b));
+        assert_ne!(fingerprint(&a), fingerprint(&c));
+    }
+
+    #[test]
+    fn fingerprint_prefers_upstream() {
+        let a = alert("critical", "KubeNodeNotReady", "node-1", Some("abc123"));
+        assert_eq!(fingerprint(&a).as_deref(), Some("abc123"));
+        let heartbeat = NotificationMessage::Heartbeat { timestamp: String::new() };
+        assert_eq!(fingerprint(&heartbeat), None);
+    }
+
+    #[test]
+    fn only_critical_and_error_are_sent() {
+        assert!(is_critical(&alert("critical", "t", "m", None)));
+        assert!(is_critical(&alert("error", "t", "m", None)));
+        assert!(!is_critical(&alert("warning", "t", "m", None)));
+    }
+
+    #[test]
+    fn webhook_payload_formats() {
+        let a = alert("critical", "Disk full", "pvc data is full", None);
+        let slack = webhook_payload(&a, true);
+        assert_eq!(slack["text"], "🔴 Disk full: pvc data is full");
+        assert_eq!(slack["blocks"].as_array().map(Vec::len), Some(2));
+
+        let generic = webhook_payload(&a, false);
+        assert_eq!(generic["type"], "alert");
+        assert_eq!(generic["title"], "Disk full");
+    }
+
+    /// Webhook receiver answering every POST with `status`, counting requests
+    async fn mock_webhook(status: &'static str) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 8192];
+                let _ = stream.read(&mut buf).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried_on_the_next_check() {
+        use std::sync::atomic::Ordering;
+
+        let (url, requests) = mock_webhook("500 Internal Server Error").await;
+        let sent = TtlMap::new(Duration::from_secs(3600));
+        let alerts = vec![alert("critical", "KubeNodeNotReady", "node-1", Some("abc123"))];
+
+        deliver(&url, &alerts, &sent).await;
+        assert_eq!(sent.get("abc123"), None);
+        deliver(&url, &alerts, &sent).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn delivered_alert_is_not_sent_again() {
+        use std::sync::atomic::Ordering;
+
+        let (url, requests) = mock_webhook("200 OK").await;
+        let sent = TtlMap::new(Duration::from_secs(3600));
+        let alerts = vec![
+            alert("critical", "KubeNodeNotReady", "node-1", Some("abc123")),
+            alert("warning", "CPUThrottling", "api", Some("def456")),
+        ];
+
+        deliver(&url, &alerts, &sent).await;
+        deliver(&url, &alerts, &sent).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(sent.get("abc123"), Some(()));
+    }
+}
@@ -1,12 +1,19 @@
 use chrono::{DateTime, Utc};
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::api::apps::v1::ReplicaSet;
+use k8s_openapi::api::core::v1::{Container, Event, Pod};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
 use kube::{
     api::{Api, DeleteParams, ListParams, Patch, PatchParams},
     Client,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tracing::info;
+use std::collections::{BTreeMap, HashMap};
+use tracing::{info, warn};
+
+use crate::events::{self, EventInfo};
+use crate::{cluster, clusters, k8s_cache};
+use crate::error::KusanagiError;
 
 /// Pods status response
 #[derive(Clone, Debug, Serialize)]
@@ -17,7 +24,14 @@ pub struct PodsStatusResponse {
     pub succeeded_pods: usize,
     pub failed_pods: usize,
     pub error_pods: usize,
+    /// Error pods with a container currently or previously terminated as OOMKilled
+    pub oom_killed_pods: usize,
+    /// Error pods with BestEffort QoS, the first evicted under node pressure
+    pub best_effort_error_pods: usize,
     pub pods_in_error: Vec<PodInfo>,
+    /// Error pods collapsed under their controller (only with `?grouped=true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<OwnerGroup>>,
 }
 
 /// Individual pod information  
@@ -32,9 +46,27 @@ pub struct PodInfo {
     pub restart_count: i32,
     pub age: String,
     pub age_seconds: i64,
+    pub oom_killed: bool,
+    /// Guaranteed, Burstable or BestEffort, as assigned by Kubernetes
+    pub qos_class: Option<String>,
+    /// Top-level controller (e.g. Deployment rather than its ReplicaSet)
+    pub owner_kind: Option<String>,
+    pub owner_name: Option<String>,
     pub containers: Vec<ContainerInfo>,
 }
 
+/// Error pods of one controller
+#[derive(Clone, Debug, Serialize)]
+pub struct OwnerGroup {
+    pub namespace: String,
+    pub owner_kind: String,
+    pub owner_name: String,
+    pub error_pods: usize,
+    pub restart_count: i32,
+    pub reasons: Vec<String>,
+    pub pods: Vec<String>,
+}
+
 /// Container status information
 #[derive(Clone, Debug, Serialize)]
 pub struct ContainerInfo {
@@ -44,10 +76,16 @@ pub struct ContainerInfo {
     pub state: String,
     pub reason: Option<String>,
     pub message: Option<String>,
+    /// Why the previous instance of the container died (e.g. OOMKilled after a crash loop)
+    pub last_terminated_reason: Option<String>,
+    pub last_terminated_exit_code: Option<i32>,
+    /// Image from the pod spec when the container can't pull it
+    pub failing_image: Option<String>,
 }
 
-/// Error reasons we want to detect
-const ERROR_REASONS: &[&str] = &[
+/// Error reasons we want to detect, shared with the node view
+/// Extended at runtime by EXTRA_POD_ERROR_REASONS
+pub const ERROR_REASONS: &[&str] = &[
     "CrashLoopBackOff",
     "ImagePullBackOff",
     "ErrImagePull",
@@ -62,31 +100,105 @@ const ERROR_REASONS: &[&str] = &[
     "Evicted",
 ];
 
-/// Get pods status with focus on error pods
-pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+lazy_static::lazy_static! {
+    /// Extra reasons from EXTRA_POD_ERROR_REASONS, e.g. custom CSI or sidecar errors
+    static ref EXTRA_ERROR_REASONS: Vec<String> = extra_error_reasons_from_env();
+}
+
+fn extra_error_reasons_from_env() -> Vec<String> {
+    std::env::var("EXTRA_POD_ERROR_REASONS")
+        .map(|value| parse_extra_reasons(&value))
+        .unwrap_or_default()
+}
+
+/// Reasons from a comma-separated list, trimmed with empty entries dropped
+pub fn parse_extra_reasons(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// True when a container/pod reason matches a built-in or configured error reason
+pub fn is_error_reason(reason: &str) -> bool {
+    matches_error_reason(reason, &EXTRA_ERROR_REASONS)
+}
+
+fn matches_error_reason(reason: &str, extra: &[String]) -> bool {
+    ERROR_REASONS.iter().any(|er| reason.contains(er)) || extra.iter().any(|er| reason.contains(er.as_str()))
+}
+
+/// Restarts above which a pod is flagged, from POD_RESTART_THRESHOLD (default 5)
+pub fn restart_threshold() -> i32 {
+    std::env::var("POD_RESTART_THRESHOLD")
+        .ok()
+        .and_then(|s| s.parse::<i32>().ok())
+        .unwrap_or(5)
+}
+
+/// Waiting reasons that mean the image couldn't be pulled
+const IMAGE_PULL_REASONS: &[&str] = &["ImagePullBackOff", "ErrImagePull", "InvalidImageName", "ErrImageNeverPull"];
+
+/// The spec image of container `name` when `reason` is an image-pull failure
+pub fn image_pull_failure(reason: Option<&str>, spec_containers: Option<&[Container]>, name: &str) -> Option<String> {
+    if !reason.is_some_and(|r| IMAGE_PULL_REASONS.contains(&r)) {
+        return None;
+    }
+    spec_containers?
+        .iter()
+        .find(|c| c.name == name)
+        .and_then(|c| c.image.clone())
+}
+
+/// QoS class Kubernetes assigned to the pod (Guaranteed, Burstable or BestEffort)
+pub fn qos_class(pod: &Pod) -> Option<String> {
+    pod.status.as_ref().and_then(|s| s.qos_class.clone())
+}
 
-    let pods_api: Api<Pod> = Api::all(client);
+/// BestEffort pods have no requests or limits and are evicted first under pressure
+pub fn is_best_effort(qos_class: Option<&str>) -> bool {
+    qos_class == Some("BestEffort")
+}
 
-    let pods = pods_api
-        .list(&ListParams::default())
+/// Get pods status with focus on error pods
+/// Scoped to one namespace when given, otherwise cluster-wide
+/// `cluster` selects a KUSANAGI_CLUSTERS context (None = current context)
+pub async fn get_pods_status(
+    cluster: Option<&str>,
+    namespace: Option<&str>,
+) -> Result<PodsStatusResponse, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let pods = k8s_cache::list_pods(client.clone(), cluster, namespace)
         .await
-        .map_err(|e| format!("Failed to list pods: {}", e))?;
+        .map_err(|e| KusanagiError::kube("Failed to list pods", e))?;
+    // An explicitly requested namespace is shown even when ignored cluster-wide
+    let pods: Vec<&Pod> = pods
+        .iter()
+        .filter(|p| {
+            namespace.is_some()
+                || !cluster::is_ignored_namespace(p.metadata.namespace.as_deref().unwrap_or_default())
+        })
+        .collect();
 
     let now = Utc::now();
+    let restart_threshold = restart_threshold();
     let mut response = PodsStatusResponse {
-        total_pods: pods.items.len(),
+        total_pods: pods.len(),
         running_pods: 0,
         pending_pods: 0,
         succeeded_pods: 0,
         failed_pods: 0,
         error_pods: 0,
+        oom_killed_pods: 0,
+        best_effort_error_pods: 0,
         pods_in_error: Vec::new(),
+        groups: None,
     };
 
-    for pod in pods.items {
+    for pod in pods.iter() {
         let name = pod.metadata.name.clone().unwrap_or_default();
         let namespace = pod.metadata.namespace.clone().unwrap_or_default();
         
@@ -144,10 +256,11 @@ pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
                 total_restarts += cs.restart_count;
                 
                 let (state, reason, message) = get_container_state_info(cs);
+                let (last_terminated_reason, last_terminated_exit_code) = get_last_termination(cs);
                 
                 // Check for error reasons
                 if let Some(ref r) = reason {
-                    if ERROR_REASONS.iter().any(|er| r.contains(er)) {
+                    if is_error_reason(r) {
                         is_error_pod = true;
                         if pod_error_reason.is_none() {
                             pod_error_reason = reason.clone();
@@ -156,6 +269,7 @@ pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
                     }
                 }
                 
+                let failing_image = image_pull_failure(reason.as_deref(), spec.map(|s| s.containers.as_slice()), &cs.name);
                 containers.push(ContainerInfo {
                     name: cs.name.clone(),
                     ready: cs.ready,
@@ -163,6 +277,9 @@ pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
                     state,
                     reason,
                     message,
+                    last_terminated_reason,
+                    last_terminated_exit_code,
+                    failing_image,
                 });
             }
         }
@@ -171,10 +288,11 @@ pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
         if let Some(init_container_statuses) = status.and_then(|s| s.init_container_statuses.as_ref()) {
             for cs in init_container_statuses {
                 let (state, reason, message) = get_container_state_info(cs);
+                let (last_terminated_reason, last_terminated_exit_code) = get_last_termination(cs);
                 
                 // Check for error reasons in init containers
                 if let Some(ref r) = reason {
-                    if ERROR_REASONS.iter().any(|er| r.contains(er)) {
+                    if is_error_reason(r) {
                         is_error_pod = true;
                         if pod_error_reason.is_none() {
                             pod_error_reason = reason.clone();
@@ -183,6 +301,8 @@ pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
                     }
                 }
                 
+                let init_containers = spec.and_then(|s| s.init_containers.as_deref());
+                let failing_image = image_pull_failure(reason.as_deref(), init_containers, &cs.name);
                 containers.push(ContainerInfo {
                     name: format!("init:{}", cs.name),
                     ready: cs.ready,
@@ -190,19 +310,32 @@ pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
                     state,
                     reason,
                     message,
+                    last_terminated_reason,
+                    last_terminated_exit_code,
+                    failing_image,
                 });
             }
         }
 
-        // Check for high restart count (>5 is concerning)
-        if total_restarts > 5 && !is_error_pod {
+        let oom_killed = containers.iter().any(is_oom_killed);
+
+        // Check for high restart count
+        if total_restarts > restart_threshold && !is_error_pod {
             is_error_pod = true;
             pod_error_reason = Some(format!("HighRestartCount ({})", total_restarts));
         }
 
         // Add to error list if applicable
         if is_error_pod {
+            let owner = controller_of(&pod.metadata.owner_references);
+            let qos_class = qos_class(pod);
             response.error_pods += 1;
+            if oom_killed {
+                response.oom_killed_pods += 1;
+            }
+            if is_best_effort(qos_class.as_deref()) {
+                response.best_effort_error_pods += 1;
+            }
             response.pods_in_error.push(PodInfo {
                 name,
                 namespace,
@@ -213,11 +346,27 @@ pub async fn get_pods_status() -> Result<PodsStatusResponse, String> {
                 restart_count: total_restarts,
                 age,
                 age_seconds,
+                oom_killed,
+                qos_class,
+                owner_kind: owner.map(|o| o.kind.clone()),
+                owner_name: owner.map(|o| o.name.clone()),
                 containers,
             });
         }
     }
 
+    // Pods owned by a ReplicaSet are reported under its Deployment
+    if response.pods_in_error.iter().any(|p| p.owner_kind.as_deref() == Some("ReplicaSet")) {
+        match replica_set_owners(client, namespace).await {
+            Ok(owners) => {
+                for pod in response.pods_in_error.iter_mut() {
+                    resolve_top_owner(pod, &owners);
+                }
+            }
+            Err(e) => warn!("Could not resolve ReplicaSet owners: {}", e),
+        }
+    }
+
     // Sort error pods by restart count (highest first), then by age (newest first)
     response.pods_in_error.sort_by(|a, b| {
         b.restart_count.cmp(&a.restart_count)
@@ -256,8 +405,106 @@ fn get_container_state_info(cs: &k8s_openapi::api::core::v1::ContainerStatus) ->
     ("Unknown".to_string(), None, None)
 }
 
+/// The controller owner reference of an object, if any
+fn controller_of(owners: &Option<Vec<OwnerReference>>) -> Option<&OwnerReference> {
+    owners
+        .as_ref()
+        .and_then(|refs| refs.iter().find(|r| r.controller == Some(true)).or_else(|| refs.first()))
+}
+
+/// Map (namespace, ReplicaSet name) to the (kind, name) of the ReplicaSet's controller
+async fn replica_set_owners(
+    client: Client,
+    namespace: Option<&str>,
+) -> Result<HashMap<(String, String), (String, String)>, kube::Error> {
+    let rs_api: Api<ReplicaSet> = match namespace {
+        Some(ns) => Api::namespaced(client, ns),
+        None => Api::all(client),
+    };
+
+    let replica_sets = rs_api.list(&ListParams::default()).await?;
+    Ok(replica_sets
+        .items
+        .iter()
+        .filter_map(|rs| {
+            let owner = controller_of(&rs.metadata.owner_references)?;
+            Some((
+                (
+                    rs.metadata.namespace.clone().unwrap_or_default(),
+                    rs.metadata.name.clone().unwrap_or_default(),
+                ),
+                (owner.kind.clone(), owner.name.clone()),
+            ))
+        })
+        .collect())
+}
+
+/// Replace a ReplicaSet owner with the ReplicaSet's own controller (usually a Deployment)
+fn resolve_top_owner(pod: &mut PodInfo, replica_set_owners: &HashMap<(String, String), (String, String)>) {
+    if pod.owner_kind.as_deref() != Some("ReplicaSet") {
+        return;
+    }
+    let Some(rs_name) = pod.owner_name.clone() else {
+        return;
+    };
+    if let Some((kind, name)) = replica_set_owners.get(&(pod.namespace.clone(), rs_name)) {
+        pod.owner_kind = Some(kind.clone());
+        pod.owner_name = Some(name.clone());
+    }
+}
+
+/// Collapse error pods under their controller; pods without one form their own group
+pub fn group_by_owner(pods: &[PodInfo]) -> Vec<OwnerGroup> {
+    let mut groups: BTreeMap<(String, String, String), OwnerGroup> = BTreeMap::new();
+
+    for pod in pods {
+        let kind = pod.owner_kind.clone().unwrap_or_else(|| "Pod".to_string());
+        let name = pod.owner_name.clone().unwrap_or_else(|| pod.name.clone());
+
+        let group = groups
+            .entry((pod.namespace.clone(), kind.clone(), name.clone()))
+            .or_insert_with(|| OwnerGroup {
+                namespace: pod.namespace.clone(),
+                owner_kind: kind,
+                owner_name: name,
+                error_pods: 0,
+                restart_count: 0,
+                reasons: Vec::new(),
+                pods: Vec::new(),
+            });
+
+        group.error_pods += 1;
+        group.restart_count += pod.restart_count;
+        group.pods.push(pod.name.clone());
+        if let Some(reason) = &pod.reason {
+            if !group.reasons.contains(reason) {
+                group.reasons.push(reason.clone());
+            }
+        }
+    }
+
+    let mut groups: Vec<OwnerGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.error_pods.cmp(&a.error_pods).then_with(|| b.restart_count.cmp(&a.restart_count)));
+    groups
+}
+
+/// Whether the container is, or last was, terminated by the OOM killer
+fn is_oom_killed(container: &ContainerInfo) -> bool {
+    let terminated_oom = container.state == "Terminated" && container.reason.as_deref() == Some("OOMKilled");
+    terminated_oom || container.last_terminated_reason.as_deref() == Some("OOMKilled")
+}
+
+/// Extract the reason and exit code of the container's previous termination
+fn get_last_termination(cs: &k8s_openapi::api::core::v1::ContainerStatus) -> (Option<String>, Option<i32>) {
+    cs.last_state
+        .as_ref()
+        .and_then(|s| s.terminated.as_ref())
+        .map(|t| (t.reason.clone(), Some(t.exit_code)))
+        .unwrap_or((None, None))
+}
+
 /// Format age in human readable format
-fn format_age(seconds: i64) -> String {
+pub fn format_age(seconds: i64) -> String {
     if seconds < 0 {
         return "just now".to_string();
     }
@@ -295,10 +542,10 @@ pub struct ForceDeleteResponse {
 
 /// Force delete a pod by removing finalizers and deleting with 0 grace period
 /// This is useful for pods stuck in Terminating state
-pub async fn force_delete_pod(namespace: &str, pod_name: &str) -> Result<ForceDeleteResponse, String> {
+pub async fn force_delete_pod(namespace: &str, pod_name: &str) -> Result<ForceDeleteResponse, KusanagiError> {
     let client = Client::try_default()
         .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+        .map_err(|e| KusanagiError::kube("Failed to create Kubernetes client", e))?;
 
     let pods_api: Api<Pod> = Api::namespaced(client, namespace);
 
@@ -354,3 +601,355 @@ pub async fn force_delete_pod(namespace: &str, pod_name: &str) -> Result<ForceDe
         }
     }
 }
+
+/// Request to clean up terminated pods
+#[derive(Clone, Debug, Deserialize)]
+pub struct CleanupRequest {
+    pub namespace: Option<String>,
+    /// Only report what would be deleted (default)
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    /// Include every Failed pod, not just Evicted ones
+    #[serde(default)]
+    pub all_failed: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+/// Result of a cleanup run
+#[derive(Clone, Debug, Serialize)]
+pub struct CleanupResult {
+    pub dry_run: bool,
+    /// Matching pods as namespace/name
+    pub pods: Vec<String>,
+    pub deleted: usize,
+    pub errors: Vec<String>,
+}
+
+/// Whether a pod is a terminated leftover that can be removed
+/// Evicted pods always match; other Failed pods only with `all_failed`
+fn is_cleanup_candidate(pod: &Pod, all_failed: bool) -> bool {
+    let status = pod.status.as_ref();
+    let phase = status.and_then(|s| s.phase.as_deref());
+    let reason = status.and_then(|s| s.reason.as_deref());
+
+    phase == Some("Failed") && (all_failed || reason == Some("Evicted"))
+}
+
+/// Delete Evicted (and optionally all Failed) pods, cluster-wide or in one namespace
+pub async fn cleanup_terminated_pods(
+    cluster: Option<&str>,
+    namespace: Option<&str>,
+    dry_run: bool,
+    all_failed: bool,
+) -> Result<CleanupResult, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let pods_api: Api<Pod> = match namespace {
+        Some(ns) => Api::namespaced(client.clone(), ns),
+        None => Api::all(client.clone()),
+    };
+
+    let failed_pods = pods_api
+        .list(&ListParams::default().fields("status.phase=Failed"))
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list pods", e))?;
+
+    let mut result = CleanupResult {
+        dry_run,
+        pods: Vec::new(),
+        deleted: 0,
+        errors: Vec::new(),
+    };
+
+    for pod in failed_pods.items.iter().filter(|p| is_cleanup_candidate(p, all_failed)) {
+        let name = pod.metadata.name.clone().unwrap_or_default();
+        let pod_namespace = pod.metadata.namespace.clone().unwrap_or_default();
+        result.pods.push(format!("{}/{}", pod_namespace, name));
+
+        if dry_run {
+            continue;
+        }
+
+        let api: Api<Pod> = Api::namespaced(client.clone(), &pod_namespace);
+        match api.delete(&name, &DeleteParams::default()).await {
+            Ok(_) => result.deleted += 1,
+            Err(e) => result
+                .errors
+                .push(format!("Failed to delete pod {}/{}: {}", pod_namespace, name, e)),
+        }
+    }
+
+    info!(
+        "Pod cleanup{}: {} matched, {} deleted, {} errors",
+        if dry_run { " (dry run)" } else { "" },
+        result.pods.len(),
+        result.deleted,
+        result.errors.len()
+    );
+
+    Ok(result)
+}
+
+/// Full pod object plus its own recent events, like `kubectl describe pod`
+pub async fn describe_pod(cluster: Option<&str>, namespace: &str, name: &str) -> Result<serde_json::Value, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+
+    let pods_api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pod = pods_api
+        .get(name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get pod {}/{}", namespace, name), e))?;
+
+    let events_api: Api<Event> = Api::namespaced(client, namespace);
+    let field_selector = format!("involvedObject.kind=Pod,involvedObject.name={}", name);
+    let events = events_api
+        .list(&ListParams::default().fields(&field_selector))
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list pod events", e))?;
+
+    let now = Utc::now();
+    let mut pod_events: Vec<EventInfo> = events_for_pod(&events.items, &pod)
+        .into_iter()
+        .map(|event| events::to_event_info(event, now))
+        .collect();
+    pod_events.sort_by(|a, b| b.last_timestamp.cmp(&a.last_timestamp));
+
+    Ok(json!({
+        "pod": pod,
+        "events": pod_events,
+    }))
+}
+
+/// Events whose involved object is this pod; matched by UID when both sides have one,
+/// so events from an earlier pod with the same name are left out
+pub fn events_for_pod<'a>(events: &'a [Event], pod: &Pod) -> Vec<&'a Event> {
+    let name = pod.metadata.name.as_deref().unwrap_or_default();
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or_default();
+    let uid = pod.metadata.uid.as_deref();
+
+    events
+        .iter()
+        .filter(|event| events::involves(event, "Pod", name, namespace, uid))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(state: &str, reason: Option<&str>, last: Option<&str>) -> ContainerInfo {
+        ContainerInfo {
+            name: "app".to_string(),
+            ready: false,
+            restart_count: 0,
+            state: state.to_string(),
+            reason: reason.map(String::from),
+            message: None,
+            last_terminated_reason: last.map(String::from),
+            last_terminated_exit_code: None,
+            failing_image: None,
+        }
+    }
+
+    #[test]
+    fn oom_killed_now_or_previously() {
+        assert!(is_oom_killed(&container("Terminated", Some("OOMKilled"), None)));
+        assert!(is_oom_killed(&container("Waiting", Some("CrashLoopBackOff"), Some("OOMKilled"))));
+        assert!(!is_oom_killed(&container("Waiting", Some("OOMKilled"), None)));
+        assert!(!is_oom_killed(&container("Running", None, Some("Error"))));
+    }
+
+    #[test]
+    fn events_are_attributed_to_their_pod() {
+        let event = |reason: &str, kind: &str, name: &str, namespace: &str, uid: Option<&str>| Event {
+            reason: Some(reason.to_string()),
+            involved_object: k8s_openapi::api::core::v1::ObjectReference {
+                kind: Some(kind.to_string()),
+                name: Some(name.to_string()),
+                namespace: Some(namespace.to_string()),
+                uid: uid.map(String::from),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pod = Pod {
+            metadata: kube::api::ObjectMeta {
+                name: Some("web-0".to_string()),
+                namespace: Some("prod".to_string()),
+                uid: Some("current".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let events = vec![
+            event("Started", "Pod", "web-0", "prod", Some("current")),
+            event("Killing", "Pod", "web-0", "prod", Some("previous")),
+            event("Scheduled", "Pod", "web-0", "prod", None),
+            event("BackOff", "Pod", "web-1", "prod", Some("other")),
+            event("Started", "Pod", "web-0", "staging", None),
+            event("SuccessfulCreate", "StatefulSet", "web-0", "prod", None),
+        ];
+
+        let reasons: Vec<&str> = events_for_pod(&events, &pod)
+            .iter()
+            .filter_map(|e| e.reason.as_deref())
+            .collect();
+        assert_eq!(reasons, vec!["Started", "Scheduled"]);
+    }
+
+    #[test]
+    fn last_termination_is_read_from_last_state() {
+        use k8s_openapi::api::core::v1::{
+            ContainerState, ContainerStateRunning, ContainerStateTerminated, ContainerStatus,
+        };
+
+        let mut cs = ContainerStatus {
+            name: "app".to_string(),
+            restart_count: 12,
+            state: Some(ContainerState {
+                running: Some(ContainerStateRunning::default()),
+                ..Default::default()
+            }),
+            last_state: Some(ContainerState {
+                terminated: Some(ContainerStateTerminated {
+                    reason: Some("OOMKilled".to_string()),
+                    exit_code: 137,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(get_last_termination(&cs), (Some("OOMKilled".to_string()), Some(137)));
+        assert_eq!(get_container_state_info(&cs).0, "Running");
+
+        cs.last_state = None;
+        assert_eq!(get_last_termination(&cs), (None, None));
+    }
+
+    #[test]
+    fn cleanup_selects_only_terminated_pods() {
+        let pod = |phase: &str, reason: Option<&str>| Pod {
+            status: Some(k8s_openapi::api::core::v1::PodStatus {
+                phase: Some(phase.to_string()),
+                reason: reason.map(String::from),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(is_cleanup_candidate(&pod("Failed", Some("Evicted")), false));
+        assert!(!is_cleanup_candidate(&pod("Failed", Some("DeadlineExceeded")), false));
+        assert!(is_cleanup_candidate(&pod("Failed", Some("DeadlineExceeded")), true));
+        for all_failed in [false, true] {
+            assert!(!is_cleanup_candidate(&pod("Running", None), all_failed));
+            assert!(!is_cleanup_candidate(&pod("Running", Some("Evicted")), all_failed));
+            assert!(!is_cleanup_candidate(&pod("Succeeded", None), all_failed));
+            assert!(!is_cleanup_candidate(&Pod::default(), all_failed));
+        }
+    }
+
+    fn error_pod(name: &str, owner_kind: &str, owner_name: &str) -> PodInfo {
+        PodInfo {
+            name: name.to_string(),
+            namespace: "web".to_string(),
+            status: "Running".to_string(),
+            reason: Some("CrashLoopBackOff".to_string()),
+            message: None,
+            node: None,
+            restart_count: 3,
+            age: "1h".to_string(),
+            age_seconds: 3600,
+            oom_killed: false,
+            qos_class: None,
+            owner_kind: Some(owner_kind.to_string()),
+            owner_name: Some(owner_name.to_string()),
+            containers: vec![],
+        }
+    }
+
+    #[test]
+    fn replica_set_pods_resolve_to_their_deployment() {
+        let owners = HashMap::from([(
+            ("web".to_string(), "api-7d9f8".to_string()),
+            ("Deployment".to_string(), "api".to_string()),
+        )]);
+
+        let mut pods = vec![
+            error_pod("api-7d9f8-a", "ReplicaSet", "api-7d9f8"),
+            error_pod("api-7d9f8-b", "ReplicaSet", "api-7d9f8"),
+            error_pod("orphan-rs-x", "ReplicaSet", "orphan-rs"),
+            error_pod("db-0", "StatefulSet", "db"),
+        ];
+        for pod in &mut pods {
+            resolve_top_owner(pod, &owners);
+        }
+        assert_eq!(pods[0].owner_kind.as_deref(), Some("Deployment"));
+        assert_eq!(pods[0].owner_name.as_deref(), Some("api"));
+        assert_eq!(pods[2].owner_kind.as_deref(), Some("ReplicaSet"));
+        assert_eq!(pods[3].owner_kind.as_deref(), Some("StatefulSet"));
+
+        let groups = group_by_owner(&pods);
+        assert_eq!(groups[0].owner_name, "api");
+        assert_eq!(groups[0].error_pods, 2);
+        assert_eq!(groups[0].restart_count, 6);
+        assert_eq!(groups.len(), 3);
+    }
+
+    #[test]
+    fn configured_error_reason_is_detected() {
+        let extra = parse_extra_reasons("CSIMountFailed, ,SidecarCrash ");
+
+        assert_eq!(extra, vec!["CSIMountFailed", "SidecarCrash"]);
+        assert!(matches_error_reason("CSIMountFailed", &extra));
+        assert!(matches_error_reason("SidecarCrash", &extra));
+        assert!(!matches_error_reason("CSIMountFailed", &[]));
+        assert!(matches_error_reason("CrashLoopBackOff", &[]));
+        assert!(!matches_error_reason("Completed", &extra));
+    }
+
+    #[test]
+    fn qos_class_is_read_from_the_pod_status() {
+        let pod = |status: serde_json::Value| -> Pod {
+            serde_json::from_value(serde_json::json!({
+                "metadata": { "name": "web" },
+                "status": status
+            }))
+            .unwrap()
+        };
+
+        let best_effort = pod(serde_json::json!({ "phase": "Running", "qosClass": "BestEffort" }));
+        assert_eq!(qos_class(&best_effort).as_deref(), Some("BestEffort"));
+        assert!(is_best_effort(qos_class(&best_effort).as_deref()));
+
+        let guaranteed = pod(serde_json::json!({ "phase": "Running", "qosClass": "Guaranteed" }));
+        assert_eq!(qos_class(&guaranteed).as_deref(), Some("Guaranteed"));
+        assert!(!is_best_effort(qos_class(&guaranteed).as_deref()));
+
+        assert_eq!(qos_class(&pod(serde_json::json!({ "phase": "Pending" }))), None);
+    }
+
+    #[test]
+    fn waiting_container_is_matched_to_its_spec_image() {
+        let containers: Vec<Container> = serde_json::from_value(serde_json::json!([
+            { "name": "app", "image": "registry.example.com/shop/api:v2.3.1" },
+            { "name": "proxy", "image": "envoyproxy/envoy:v1.29" }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            image_pull_failure(Some("ImagePullBackOff"), Some(&containers), "app").as_deref(),
+            Some("registry.example.com/shop/api:v2.3.1")
+        );
+        assert_eq!(
+            image_pull_failure(Some("ErrImagePull"), Some(&containers), "proxy").as_deref(),
+            Some("envoyproxy/envoy:v1.29")
+        );
+        assert_eq!(image_pull_failure(Some("CrashLoopBackOff"), Some(&containers), "app"), None);
+        assert_eq!(image_pull_failure(Some("ImagePullBackOff"), Some(&containers), "sidecar"), None);
+        assert_eq!(image_pull_failure(None, Some(&containers), "app"), None);
+    }
+}
@@ -1,5 +1,16 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
+
+use crate::cache::TtlMap;
+use crate::error::KusanagiError;
+use crate::http_util;
+
+lazy_static::lazy_static! {
+    /// Recent instant query results keyed by PromQL
+    static ref QUERY_CACHE: TtlMap<Vec<PromResult>> = TtlMap::new(query_cache_ttl());
+}
 
 /// Prometheus metrics response
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,6 +23,22 @@ pub struct PrometheusMetrics {
     pub container_count: i32,
     pub alerts_firing: i32,
     pub alerts_pending: i32,
+    /// False when Prometheus could not be queried (all values are then zero)
+    #[serde(default = "default_available")]
+    pub available: bool,
+}
+
+fn default_available() -> bool {
+    true
+}
+
+/// CPU and memory utilization of one node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeUtilization {
+    pub node: String,
+    pub instance: String,
+    pub cpu_percent: Option<f64>,
+    pub memory_percent: Option<f64>,
 }
 
 /// Prometheus query result
@@ -35,73 +62,131 @@ struct PromData {
     result: Vec<PromResult>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct PromResult {
     metric: serde_json::Value,
     value: (f64, String),
 }
 
-fn get_prometheus_url() -> String {
+/// Prometheus request timeout (override with PROMETHEUS_TIMEOUT_SECS)
+fn prometheus_timeout() -> Duration {
+    http_util::timeout_from_env("PROMETHEUS_TIMEOUT_SECS", 10)
+}
+
+/// Client reused across queries
+static CLIENT: http_util::SharedClient = http_util::SharedClient::new(prometheus_timeout);
+
+/// How long identical instant queries reuse a result
+/// (PROMETHEUS_CACHE_TTL_SECS, default 10s, 0 disables caching)
+fn query_cache_ttl() -> Duration {
+    let secs = std::env::var("PROMETHEUS_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(10);
+    Duration::from_secs(secs)
+}
+
+/// Longest PromQL accepted from clients (override with PROMETHEUS_MAX_QUERY_LENGTH)
+fn max_query_length() -> usize {
+    std::env::var("PROMETHEUS_MAX_QUERY_LENGTH")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(2000)
+}
+
+pub fn get_prometheus_url() -> String {
     std::env::var("PROMETHEUS_URL")
         .unwrap_or_else(|_| "http://kube-prometheus-stack-prometheus.kube-prometheus-stack.svc:9090".to_string())
 }
 
 /// Execute a PromQL instant query
-pub async fn query_instant(query: &str) -> Result<f64, String> {
-    let client = reqwest::Client::new();
-    let url = format!("{}/api/v1/query", get_prometheus_url());
+pub async fn query_instant(query: &str) -> Result<f64, KusanagiError> {
+    let results = query_vector(query).await?;
+    
+    // Get first result value
+    if let Some(result) = results.first() {
+        result.value.1.parse::<f64>()
+            .map_err(|e| KusanagiError::Upstream(format!("Failed to parse metric value: {}", e)))
+    } else {
+        Ok(0.0)
+    }
+}
+
+/// Execute a PromQL instant query and return every series of the result vector
+/// Identical queries within the cache TTL reuse the previous result
+async fn query_vector(query: &str) -> Result<Vec<PromResult>, KusanagiError> {
+    query_vector_at(&get_prometheus_url(), query, &QUERY_CACHE).await
+}
+
+/// `query_vector` against the Prometheus at `base_url`, caching results in `cache`
+async fn query_vector_at(
+    base_url: &str,
+    query: &str,
+    cache: &TtlMap<Vec<PromResult>>,
+) -> Result<Vec<PromResult>, KusanagiError> {
+    if let Some(cached) = cache.get(query) {
+        return Ok(cached);
+    }
+
+    let client = CLIENT.get()?;
+    let url = format!("{}/api/v1/query", base_url);
     
     let response = client
         .get(&url)
         .query(&[("query", query)])
-        .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
-        .map_err(|e| format!("Prometheus request failed: {}", e))?;
+        .map_err(|e| KusanagiError::Upstream(format!("Prometheus request failed: {}", http_util::describe_error(&e))))?;
     
     if !response.status().is_success() {
-        return Err(format!("Prometheus returned status: {}", response.status()));
+        return Err(KusanagiError::Upstream(format!("Prometheus returned status: {}", response.status())));
     }
     
     let prom_response: PromResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse Prometheus response: {}", e))?;
+        .map_err(|e| KusanagiError::Upstream(format!("Failed to parse Prometheus response: {}", e)))?;
     
     if prom_response.status != "success" {
-        return Err("Prometheus query failed".to_string());
+        return Err(KusanagiError::Upstream("Prometheus query failed".to_string()));
     }
     
-    // Get first result value
-    if let Some(result) = prom_response.data.result.first() {
-        result.value.1.parse::<f64>()
-            .map_err(|e| format!("Failed to parse metric value: {}", e))
-    } else {
-        Ok(0.0)
-    }
+    cache.insert(query, prom_response.data.result.clone());
+    Ok(prom_response.data.result)
 }
 
 /// Execute a raw PromQL query and return the full result
-pub async fn query_raw(query: &str) -> Result<PrometheusQueryResult, String> {
-    let client = reqwest::Client::new();
+pub async fn query_raw(query: &str) -> Result<PrometheusQueryResult, KusanagiError> {
+    if query.trim().is_empty() {
+        return Err(KusanagiError::Parse("PromQL query must not be empty".to_string()));
+    }
+    let max_length = max_query_length();
+    if query.len() > max_length {
+        return Err(KusanagiError::Parse(format!(
+            "PromQL query is too long ({} > {} characters)",
+            query.len(),
+            max_length
+        )));
+    }
+
+    let client = CLIENT.get()?;
     let url = format!("{}/api/v1/query", get_prometheus_url());
     
     let response = client
         .get(&url)
         .query(&[("query", query)])
-        .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
-        .map_err(|e| format!("Prometheus request failed: {}", e))?;
+        .map_err(|e| KusanagiError::Upstream(format!("Prometheus request failed: {}", http_util::describe_error(&e))))?;
     
     if !response.status().is_success() {
-        return Err(format!("Prometheus returned status: {}", response.status()));
+        return Err(KusanagiError::Upstream(format!("Prometheus returned status: {}", response.status())));
     }
     
     let result: serde_json::Value = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse Prometheus response: {}", e))?;
+        .map_err(|e| KusanagiError::Upstream(format!("Failed to parse Prometheus response: {}", e)))?;
     
     Ok(PrometheusQueryResult {
         status: result["status"].as_str().unwrap_or("unknown").to_string(),
@@ -110,10 +195,28 @@ pub async fn query_raw(query: &str) -> Result<PrometheusQueryResult, String> {
 }
 
 /// Get comprehensive cluster metrics from Prometheus
-pub async fn get_cluster_metrics() -> Result<PrometheusMetrics, String> {
+pub async fn get_cluster_metrics() -> Result<PrometheusMetrics, KusanagiError> {
     // CPU usage across all nodes (percentage)
     let cpu_query = r#"100 - (avg(rate(node_cpu_seconds_total{mode="idle"}[5m])) * 100)"#;
-    let cpu_usage = query_instant(cpu_query).await.unwrap_or(0.0);
+    // If the first query fails Prometheus is unreachable; skip the rest instead of
+    // waiting for each of them to time out
+    let cpu_usage = match query_instant(cpu_query).await {
+        Ok(value) => value,
+        Err(e) => {
+            tracing::warn!("Prometheus unavailable: {}", e);
+            return Ok(PrometheusMetrics {
+                cpu_usage_percent: 0.0,
+                memory_usage_percent: 0.0,
+                memory_usage_bytes: 0,
+                pod_count: 0,
+                node_count: 0,
+                container_count: 0,
+                alerts_firing: 0,
+                alerts_pending: 0,
+                available: false,
+            });
+        }
+    };
     
     // Memory usage percentage
     let mem_percent_query = r#"(1 - (sum(node_memory_MemAvailable_bytes) / sum(node_memory_MemTotal_bytes))) * 100"#;
@@ -152,11 +255,12 @@ pub async fn get_cluster_metrics() -> Result<PrometheusMetrics, String> {
         container_count,
         alerts_firing,
         alerts_pending,
+        available: true,
     })
 }
 
 /// Get top resource-consuming pods
-pub async fn get_top_pods(limit: usize) -> Result<Vec<serde_json::Value>, String> {
+pub async fn get_top_pods(limit: usize) -> Result<Vec<serde_json::Value>, KusanagiError> {
     let query = format!(
         r#"topk({}, sum by (pod, namespace) (rate(container_cpu_usage_seconds_total{{container!=""}}[5m])))"#,
         limit
@@ -171,8 +275,42 @@ pub async fn get_top_pods(limit: usize) -> Result<Vec<serde_json::Value>, String
     }
 }
 
+/// CPU usage of one pod, summed over its containers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodCpu {
+    pub namespace: String,
+    pub pod: String,
+    pub cpu_cores: f64,
+}
+
+/// Get the top CPU-consuming pods as typed entries, highest first
+pub async fn get_top_pods_typed(limit: usize) -> Result<Vec<PodCpu>, KusanagiError> {
+    let query = format!(
+        r#"topk({}, sum by (pod, namespace) (rate(container_cpu_usage_seconds_total{{container!=""}}[5m])))"#,
+        limit
+    );
+
+    Ok(parse_top_pods(&query_vector(&query).await?))
+}
+
+/// Turn a `sum by (pod, namespace)` vector into pod entries, sorted by CPU descending
+fn parse_top_pods(results: &[PromResult]) -> Vec<PodCpu> {
+    let mut pods: Vec<PodCpu> = results
+        .iter()
+        .filter_map(|r| {
+            Some(PodCpu {
+                namespace: label(r, "namespace")?,
+                pod: label(r, "pod")?,
+                cpu_cores: r.value.1.parse::<f64>().ok()?,
+            })
+        })
+        .collect();
+    pods.sort_by(|a, b| b.cpu_cores.total_cmp(&a.cpu_cores));
+    pods
+}
+
 /// Get node resource utilization
-pub async fn get_node_resources() -> Result<Vec<serde_json::Value>, String> {
+pub async fn get_node_resources() -> Result<Vec<serde_json::Value>, KusanagiError> {
     let cpu_query = r#"100 - (avg by (instance) (rate(node_cpu_seconds_total{mode="idle"}[5m])) * 100)"#;
     let result = query_raw(cpu_query).await?;
     
@@ -182,3 +320,144 @@ pub async fn get_node_resources() -> Result<Vec<serde_json::Value>, String> {
         Ok(vec![])
     }
 }
+
+/// Get CPU and memory utilization per node
+/// Instances are mapped to node names through node_uname_info when available
+pub async fn get_node_utilization() -> Result<Vec<NodeUtilization>, KusanagiError> {
+    let cpu_query = r#"100 - (avg by (instance) (rate(node_cpu_seconds_total{mode="idle"}[5m])) * 100)"#;
+    let mem_query = r#"(1 - (node_memory_MemAvailable_bytes / node_memory_MemTotal_bytes)) * 100"#;
+
+    let cpu = values_by_label(&query_vector(cpu_query).await?, "instance");
+    let memory = values_by_label(&query_vector(mem_query).await?, "instance");
+    let node_names: HashMap<String, String> = query_vector("node_uname_info")
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|r| Some((label(r, "instance")?, label(r, "nodename")?)))
+        .collect();
+
+    Ok(join_node_utilization(&cpu, &memory, &node_names))
+}
+
+fn label(result: &PromResult, name: &str) -> Option<String> {
+    result.metric.get(name).and_then(|v| v.as_str()).map(|v| v.to_string())
+}
+
+/// Map a label value to the sample value of each series
+fn values_by_label(results: &[PromResult], name: &str) -> HashMap<String, f64> {
+    results
+        .iter()
+        .filter_map(|r| Some((label(r, name)?, r.value.1.parse::<f64>().ok()?)))
+        .collect()
+}
+
+/// Join per-instance CPU and memory into one entry per instance, sorted by node name
+fn join_node_utilization(
+    cpu: &HashMap<String, f64>,
+    memory: &HashMap<String, f64>,
+    node_names: &HashMap<String, String>,
+) -> Vec<NodeUtilization> {
+    let mut instances: Vec<&String> = cpu.keys().chain(memory.keys()).collect();
+    instances.sort();
+    instances.dedup();
+
+    let mut nodes: Vec<NodeUtilization> = instances
+        .into_iter()
+        .map(|instance| NodeUtilization {
+            node: node_names.get(instance).cloned().unwrap_or_else(|| {
+                // Fall back to the instance without its exporter port
+                instance.rsplit_once(':').map(|(host, _)| host).unwrap_or(instance).to_string()
+            }),
+            instance: instance.clone(),
+            cpu_percent: cpu.get(instance).copied(),
+            memory_percent: memory.get(instance).copied(),
+        })
+        .collect();
+    nodes.sort_by(|a, b| a.node.cmp(&b.node));
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(instance: &str, value: &str) -> PromResult {
+        PromResult {
+            metric: serde_json::json!({ "instance": instance }),
+            value: (1700000000.0, value.to_string()),
+        }
+    }
+
+    #[test]
+    fn cpu_and_memory_are_joined_per_instance() {
+        let cpu = values_by_label(&[series("10.0.0.1:9100", "42.5"), series("10.0.0.2:9100", "10")], "instance");
+        let memory = values_by_label(&[series("10.0.0.1:9100", "61"), series("10.0.0.3:9100", "n/a")], "instance");
+        let node_names = HashMap::from([("10.0.0.1:9100".to_string(), "worker-a".to_string())]);
+
+        let nodes = join_node_utilization(&cpu, &memory, &node_names);
+        let summary: Vec<(&str, Option<f64>, Option<f64>)> = nodes
+            .iter()
+            .map(|n| (n.node.as_str(), n.cpu_percent, n.memory_percent))
+            .collect();
+        // 10.0.0.3 has no parsable sample, so it is left out
+        assert_eq!(summary, vec![("10.0.0.2", Some(10.0), None), ("worker-a", Some(42.5), Some(61.0))]);
+    }
+
+    #[test]
+    fn top_pods_are_parsed_from_the_vector_result() {
+        let response: PromResponse = serde_json::from_value(serde_json::json!({
+            "status": "success",
+            "data": {
+                "resultType": "vector",
+                "result": [
+                    { "metric": { "namespace": "web", "pod": "api-1" }, "value": [1700000000.0, "0.25"] },
+                    { "metric": { "namespace": "db", "pod": "pg-0" }, "value": [1700000000.0, "1.5"] },
+                    { "metric": { "pod": "no-namespace" }, "value": [1700000000.0, "9"] }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let pods = parse_top_pods(&response.data.result);
+        let parsed: Vec<(&str, &str, f64)> = pods
+            .iter()
+            .map(|p| (p.namespace.as_str(), p.pod.as_str(), p.cpu_cores))
+            .collect();
+        assert_eq!(parsed, vec![("db", "pg-0", 1.5), ("web", "api-1", 0.25)]);
+    }
+
+    #[tokio::test]
+    async fn identical_queries_within_ttl_hit_prometheus_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Minimal Prometheus answering every query with one sample
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            let body = r#"{"status":"success","data":{"resultType":"vector","result":[{"metric":{},"value":[1700000000,"3"]}]}}"#;
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let cache = TtlMap::new(Duration::from_secs(60));
+        let query = r#"count(up{job="cache-test"})"#;
+        for _ in 0..2 {
+            let results = query_vector_at(&base_url, query, &cache).await.unwrap();
+            assert_eq!(results[0].value.1, "3");
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,156 @@
+//! Live CPU and memory usage from metrics-server (the `metrics.k8s.io` API)
+//! Clusters without metrics-server get a 501 explaining what is missing instead of the API's 404.
+
+use kube::{
+    api::{Api, ListParams},
+    core::DynamicObject,
+    discovery::ApiResource,
+};
+use serde::Serialize;
+use tracing::info;
+
+use crate::error::KusanagiError;
+use crate::{capabilities, cluster, clusters};
+
+/// Current usage of a node or pod
+#[derive(Clone, Debug, Serialize)]
+pub struct ResourceUsage {
+    pub name: String,
+    /// None for nodes
+    pub namespace: Option<String>,
+    pub cpu_millis: i64,
+    pub memory_bytes: i64,
+}
+
+fn metrics_resource(kind: &str, plural: &str) -> ApiResource {
+    ApiResource {
+        group: "metrics.k8s.io".to_string(),
+        version: "v1beta1".to_string(),
+        api_version: "metrics.k8s.io/v1beta1".to_string(),
+        kind: kind.to_string(),
+        plural: plural.to_string(),
+    }
+}
+
+/// CPU usage in millicores; metrics-server reports nanocores ("250000000n") or microcores ("250000u")
+pub fn parse_cpu_usage(cpu: &str) -> i64 {
+    let cpu = cpu.trim();
+    let scaled = |value: &str, divisor: f64| value.parse::<f64>().map_or(0, |v| (v / divisor) as i64);
+    if let Some(nanos) = cpu.strip_suffix('n') {
+        scaled(nanos, 1_000_000.0)
+    } else if let Some(micros) = cpu.strip_suffix('u') {
+        scaled(micros, 1_000.0)
+    } else if let Some(millis) = cpu.strip_suffix('m') {
+        scaled(millis, 1.0)
+    } else {
+        scaled(cpu, 0.001)
+    }
+}
+
+/// Sum a `usage` map ({"cpu": .., "memory": ..}) into (millicores, bytes)
+fn usage_of(usage: Option<&serde_json::Value>) -> (i64, i64) {
+    let field = |name: &str| usage.and_then(|u| u.get(name)).and_then(|v| v.as_str());
+    (
+        field("cpu").map_or(0, parse_cpu_usage),
+        field("memory").map_or(0, cluster::parse_capacity_to_bytes),
+    )
+}
+
+/// Usage of a NodeMetrics object
+pub fn node_usage(metrics: &DynamicObject) -> ResourceUsage {
+    let (cpu_millis, memory_bytes) = usage_of(metrics.data.get("usage"));
+    ResourceUsage {
+        name: metrics.metadata.name.clone().unwrap_or_default(),
+        namespace: None,
+        cpu_millis,
+        memory_bytes,
+    }
+}
+
+/// Usage of a PodMetrics object, summed over its containers
+pub fn pod_usage(metrics: &DynamicObject) -> ResourceUsage {
+    let (cpu_millis, memory_bytes) = metrics
+        .data
+        .get("containers")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .map(|c| usage_of(c.get("usage")))
+        .fold((0, 0), |(cpu, mem), (c, m)| (cpu + c, mem + m));
+    ResourceUsage {
+        name: metrics.metadata.name.clone().unwrap_or_default(),
+        namespace: metrics.metadata.namespace.clone(),
+        cpu_millis,
+        memory_bytes,
+    }
+}
+
+/// Current usage of every node, busiest CPU first
+pub async fn get_node_usage(cluster: Option<&str>) -> Result<Vec<ResourceUsage>, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+    capabilities::require_metrics_server(cluster, &client).await?;
+
+    let api: Api<DynamicObject> = Api::all_with(client, &metrics_resource("NodeMetrics", "nodes"));
+    let list = api
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list node metrics", e))?;
+
+    let mut nodes: Vec<ResourceUsage> = list.items.iter().map(node_usage).collect();
+    nodes.sort_by_key(|n| std::cmp::Reverse(n.cpu_millis));
+    info!("Node usage: {} nodes", nodes.len());
+    Ok(nodes)
+}
+
+/// Current usage of pods, in one namespace or cluster-wide, busiest CPU first
+pub async fn get_pod_usage(cluster: Option<&str>, namespace: Option<&str>) -> Result<Vec<ResourceUsage>, KusanagiError> {
+    let client = clusters::client(cluster).await?;
+    capabilities::require_metrics_server(cluster, &client).await?;
+
+    let resource = metrics_resource("PodMetrics", "pods");
+    let api: Api<DynamicObject> = match namespace {
+        Some(ns) => Api::namespaced_with(client, ns, &resource),
+        None => Api::all_with(client, &resource),
+    };
+    let list = api
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to list pod metrics", e))?;
+
+    let mut pods: Vec<ResourceUsage> = list.items.iter().map(pod_usage).collect();
+    pods.sort_by_key(|p| std::cmp::Reverse(p.cpu_millis));
+    info!("Pod usage: {} pods", pods.len());
+    Ok(pods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_usage_units_convert_to_millicores() {
+        assert_eq!(parse_cpu_usage("250000000n"), 250);
+        assert_eq!(parse_cpu_usage("1500000u"), 1500);
+        assert_eq!(parse_cpu_usage("75m"), 75);
+        assert_eq!(parse_cpu_usage("2"), 2000);
+        assert_eq!(parse_cpu_usage("lots"), 0);
+    }
+
+    #[test]
+    fn pod_usage_sums_containers() {
+        let metrics: DynamicObject = serde_json::from_value(serde_json::json!({
+            "apiVersion": "metrics.k8s.io/v1beta1",
+            "kind": "PodMetrics",
+            "metadata": { "name": "api-7d9f", "namespace": "shop" },
+            "containers": [
+                { "name": "api", "usage": { "cpu": "120000000n", "memory": "64Mi" } },
+                { "name": "proxy", "usage": { "cpu": "5m", "memory": "16Mi" } }
+            ]
+        }))
+        .unwrap();
+
+        let usage = pod_usage(&metrics);
+        assert_eq!(usage.namespace.as_deref(), Some("shop"));
+        assert_eq!((usage.cpu_millis, usage.memory_bytes), (125, 80 * 1024 * 1024));
+    }
+}
@@ -0,0 +1,110 @@
+//! Read-only access to arbitrary Kubernetes objects
+//! Serves the YAML of any resource for debugging without kubectl; only GETs are issued.
+
+use kube::{
+    api::Api,
+    core::{DynamicObject, GroupVersionKind},
+    discovery::ApiResource,
+};
+
+use crate::clusters;
+use crate::error::KusanagiError;
+
+/// Placeholder for Secret values, which are never returned
+const REDACTED: &str = "<redacted>";
+
+/// Annotation kubectl stores the applied manifest in, Secret data included
+const LAST_APPLIED_ANNOTATION: &str = "kubectl.kubernetes.io/last-applied-configuration";
+
+/// Drop noise and sensitive values before an object is shown:
+/// managedFields always, and for Secrets their values and the last-applied copy
+pub fn sanitize_object(obj: &mut DynamicObject) {
+    obj.metadata.managed_fields = None;
+
+    let is_secret = obj.types.as_ref().is_some_and(|t| t.kind == "Secret");
+    if !is_secret {
+        return;
+    }
+    if let Some(annotations) = obj.metadata.annotations.as_mut() {
+        annotations.remove(LAST_APPLIED_ANNOTATION);
+    }
+    for field in ["data", "stringData"] {
+        if let Some(values) = obj.data.get_mut(field).and_then(|v| v.as_object_mut()) {
+            for value in values.values_mut() {
+                *value = serde_json::Value::String(REDACTED.to_string());
+            }
+        }
+    }
+}
+
+/// Serialize a sanitized object to YAML
+pub fn object_yaml(mut obj: DynamicObject) -> Result<String, KusanagiError> {
+    sanitize_object(&mut obj);
+    serde_yaml::to_string(&obj).map_err(|e| KusanagiError::Internal(format!("Failed to serialize object: {}", e)))
+}
+
+/// Fetch one object by group/version/kind/plural and return it as YAML
+/// `group` is empty for core resources; `namespace` is None for cluster-scoped ones
+pub async fn get_object_yaml(
+    cluster: Option<&str>,
+    group: &str,
+    version: &str,
+    kind: &str,
+    plural: &str,
+    namespace: Option<&str>,
+    name: &str,
+) -> Result<String, KusanagiError> {
+    if [version, kind, plural, name].iter().any(|v| v.trim().is_empty()) {
+        return Err(KusanagiError::Parse(
+            "version, kind, plural and name are required".to_string(),
+        ));
+    }
+
+    let client = clusters::client(cluster).await?;
+
+    let resource = ApiResource::from_gvk_with_plural(&GroupVersionKind::gvk(group, version, kind), plural);
+    let api: Api<DynamicObject> = match namespace {
+        Some(ns) => Api::namespaced_with(client, ns, &resource),
+        None => Api::all_with(client, &resource),
+    };
+
+    let obj = api
+        .get(name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get {} {}", kind, name), e))?;
+
+    object_yaml(obj)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn managed_fields_are_stripped_from_yaml() {
+        let obj: DynamicObject = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {
+                "name": "settings",
+                "namespace": "shop",
+                "managedFields": [{
+                    "manager": "kubectl-client-side-apply",
+                    "operation": "Update",
+                    "apiVersion": "v1",
+                    "fieldsType": "FieldsV1",
+                    "fieldsV1": { "f:data": { "f:mode": {} } }
+                }]
+            },
+            "data": { "mode": "debug" }
+        }))
+        .unwrap();
+        assert!(obj.metadata.managed_fields.is_some());
+
+        let yaml = object_yaml(obj).unwrap();
+        assert!(!yaml.contains("managedFields"), "{}", yaml);
+        assert!(!yaml.contains("kubectl-client-side-apply"), "{}", yaml);
+        assert!(yaml.contains("name: settings"), "{}", yaml);
+        assert!(yaml.contains("mode: debug"), "{}", yaml);
+    }
+}
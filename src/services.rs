@@ -1,7 +1,10 @@
 use kube::{Client, Api, api::ListParams};
-use k8s_openapi::api::core::v1::Service;
+use k8s_openapi::api::core::v1::{Pod, Service};
 use serde::Serialize;
 use chrono::Utc;
+use std::collections::BTreeMap;
+
+use crate::k8s_cache;
 
 #[derive(Serialize)]
 pub struct ServiceInfo {
@@ -11,13 +14,37 @@ pub struct ServiceInfo {
     pub cluster_ip: String,
     pub external_ip: Option<String>,
     pub ports: String,
+    pub selector: BTreeMap<String, String>,
+    pub matched_pods: usize,
+    /// Service has a selector but no pod in its namespace matches it
+    pub no_matching_pods: bool,
     pub age: String,
 }
 
+/// Whether a pod's labels satisfy every key/value of a service selector
+pub fn selector_matches(selector: &BTreeMap<String, String>, pod: &Pod) -> bool {
+    let labels = pod.metadata.labels.as_ref();
+    selector
+        .iter()
+        .all(|(key, value)| labels.and_then(|l| l.get(key)) == Some(value))
+}
+
+/// Number of pods in `namespace` selected by `selector` (0 for an empty selector)
+pub fn count_matching_pods(selector: &BTreeMap<String, String>, namespace: &str, pods: &[Pod]) -> usize {
+    if selector.is_empty() {
+        return 0;
+    }
+    pods.iter()
+        .filter(|pod| pod.metadata.namespace.as_deref() == Some(namespace))
+        .filter(|pod| selector_matches(selector, pod))
+        .count()
+}
+
 pub async fn get_services() -> Result<Vec<ServiceInfo>, String> {
     let client = Client::try_default().await.map_err(|e| e.to_string())?;
-    let services: Api<Service> = Api::all(client);
+    let services: Api<Service> = Api::all(client.clone());
     let list = services.list(&ListParams::default()).await.map_err(|e| e.to_string())?;
+    let pods = k8s_cache::list_all_pods(client).await.map_err(|e| e.to_string())?;
 
     let mut service_infos = Vec::new();
 
@@ -26,6 +53,10 @@ pub async fn get_services() -> Result<Vec<ServiceInfo>, String> {
         let namespace = svc.metadata.namespace.clone().unwrap_or_default();
         let spec = svc.spec.unwrap_or_default();
         let type_ = spec.type_.unwrap_or_default();
+        // Services without a selector (ExternalName, manual Endpoints) are not flagged
+        let selector = spec.selector.clone().unwrap_or_default();
+        let matched_pods = count_matching_pods(&selector, &namespace, &pods);
+        let no_matching_pods = !selector.is_empty() && matched_pods == 0;
         let cluster_ip = spec.cluster_ip.unwrap_or_default();
         
         let external_ip = if let Some(status) = svc.status {
@@ -71,9 +102,48 @@ pub async fn get_services() -> Result<Vec<ServiceInfo>, String> {
             cluster_ip,
             external_ip,
             ports,
+            selector,
+            matched_pods,
+            no_matching_pods,
             age,
         });
     }
 
     Ok(service_infos)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn pod(namespace: &str, pod_labels: &[(&str, &str)]) -> Pod {
+        Pod {
+            metadata: kube::api::ObjectMeta {
+                namespace: Some(namespace.to_string()),
+                labels: Some(labels(pod_labels)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn labelled_pod_matches_service_selector() {
+        let selector = labels(&[("app", "web"), ("tier", "frontend")]);
+        let pods = vec![
+            pod("shop", &[("app", "web"), ("tier", "frontend"), ("pod-template-hash", "abc")]),
+            pod("shop", &[("app", "web")]),
+            pod("other", &[("app", "web"), ("tier", "frontend")]),
+        ];
+
+        assert!(selector_matches(&selector, &pods[0]));
+        assert!(!selector_matches(&selector, &pods[1]));
+        assert_eq!(count_matching_pods(&selector, "shop", &pods), 1);
+        assert_eq!(count_matching_pods(&selector, "empty", &pods), 0);
+        assert_eq!(count_matching_pods(&BTreeMap::new(), "shop", &pods), 0);
+    }
+}
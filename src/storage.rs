@@ -1,11 +1,15 @@
-use k8s_openapi::api::core::v1::{PersistentVolumeClaim, Node};
-use kube::{
-    api::{Api, ListParams},
-    Client,
-};
-use serde::Serialize;
-use tracing::error;
-use std::collections::HashMap;
+use k8s_openapi::api::core::v1::{Node, PersistentVolumeClaim, Pod};
+use k8s_openapi::api::storage::v1::StorageClass;
+use kube::api::{Api, DeleteParams, ListParams, Patch, PatchParams};
+use kube::Client;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::error::KusanagiError;
+use crate::{cluster, clusters, http_util, k8s_cache};
 
 /// Storage status response
 #[derive(Clone, Debug, Serialize)]
@@ -30,22 +34,24 @@ pub struct PvcInfo {
     pub access_modes: Vec<String>,
     pub volume_name: String,
     pub pods_using: Vec<String>,
+    /// ReadWriteOnce volume used by running pods on more than one node (Multi-Attach error)
+    pub multi_attach_conflict: bool,
 }
 
 /// Get all PVCs with usage information
-pub async fn get_storage_status() -> Result<StorageStatusResponse, String> {
-    let client = Client::try_default()
-        .await
-        .map_err(|e| format!("Failed to create Kubernetes client: {}", e))?;
+pub async fn get_storage_status(cluster: Option<&str>) -> Result<StorageStatusResponse, String> {
+    let client = clusters::client(cluster).await.map_err(|e| e.to_string())?;
 
-    let pvc_api: Api<PersistentVolumeClaim> = Api::all(client.clone());
     let node_api: Api<Node> = Api::all(client.clone());
 
-    // 1. List all PVCs
-    let pvcs = pvc_api
-        .list(&ListParams::default())
+    // 1. List all PVCs and the pods mounting them
+    let pvcs = k8s_cache::list_pvcs(client.clone(), cluster, None)
         .await
         .map_err(|e| format!("Failed to list PVCs: {}", e))?;
+    let pods = k8s_cache::list_pods(client.clone(), cluster, None)
+        .await
+        .map_err(|e| format!("Failed to list pods: {}", e))?;
+    let consumers = pvc_consumers(&pods);
 
     // 2. List all Nodes to query stats
     let nodes = node_api
@@ -53,93 +59,33 @@ pub async fn get_storage_status() -> Result<StorageStatusResponse, String> {
         .await
         .map_err(|e| format!("Failed to list Nodes: {}", e))?;
 
-    // 3. Collect usage stats from all nodes in parallel
+    // 3. Collect usage stats from all nodes
     // Map: (Namespace, PvcName) -> (UsedBytes, CapacityBytes)
+    // Bounded so one wedged kubelet can't hold the request; nodes not reached in time
+    // just have no usage data, like nodes whose metrics request failed
     let mut stats_map: HashMap<(String, String), (u64, u64)> = HashMap::new();
-
-    // We'll query nodes sequentially for simplicity to avoid complex async iterator handling in this snippet,
-    // but in production parallel futures would be better.
-    for node in nodes.items {
-        let node_name = node.metadata.name.clone().unwrap_or_default();
-        
-        // Query Kubelet Metrics
-        // Path: /api/v1/nodes/{node_name}/proxy/metrics
-        // We utilize the /metrics endpoint because /stats/summary often misses NFS usage data
-        let request = http::Request::builder()
-            .uri(format!("/api/v1/nodes/{}/proxy/metrics", node_name))
-            .body(vec![])
-            .map_err(|e| format!("Failed to build request: {}", e))?;
-
-        match client.request_text(request).await {
-            Ok(metrics_text) => {
-                // Parse Prometheus format line by line
-                // Example: kubelet_volume_stats_used_bytes{namespace="default",persistentvolumeclaim="data-pvc"} 1024
-                for line in metrics_text.lines() {
-                    let is_used = line.starts_with("kubelet_volume_stats_used_bytes{");
-                    let is_capacity = line.starts_with("kubelet_volume_stats_capacity_bytes{");
-
-                    if is_used || is_capacity {
-                        // Very simple parser to avoid unnecessary regex dependencies
-                        // 1. Extract content inside {}
-                        if let Some(start_brace) = line.find('{') {
-                            if let Some(end_brace) = line.find('}') {
-                                let labels_part = &line[start_brace+1..end_brace];
-                                let value_part = &line[end_brace+1..].trim();
-                                
-                                // Parse labels
-                                let mut ns = String::new();
-                                let mut pvc = String::new();
-                                
-                                for label in labels_part.split(',') {
-                                    let parts: Vec<&str> = label.split('=').collect();
-                                    if parts.len() == 2 {
-                                        let key = parts[0].trim();
-                                        let val = parts[1].trim().trim_matches('"');
-                                        
-                                        if key == "namespace" {
-                                            ns = val.to_string();
-                                        } else if key == "persistentvolumeclaim" {
-                                            pvc = val.to_string();
-                                        }
-                                    }
-                                }
-                                
-                                // Parse value
-                                if !ns.is_empty() && !pvc.is_empty() {
-                                    if let Ok(value) = value_part.parse::<f64>() {
-                                        let entry = stats_map.entry((ns, pvc)).or_insert((0, 0));
-                                        if is_used {
-                                            entry.0 = value as u64;
-                                        } else {
-                                            entry.1 = value as u64;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                // Just log error and continue, don't fail entire request if one node fails
-                error!("Failed to fetch metrics from node {}: {}", node_name, e);
-            }
-        }
+    let stats_deadline = http_util::scan_timeout() * 3 / 4;
+    let collect = collect_volume_stats(&client, &nodes.items, &mut stats_map);
+    if tokio::time::timeout(stats_deadline, collect).await.is_err() {
+        warn!(
+            "Volume stats collection timed out after {}s, usage is partial",
+            stats_deadline.as_secs()
+        );
     }
 
     // 4. Build response
     let mut response = StorageStatusResponse {
-        pvc_count: pvcs.items.len(),
+        pvc_count: pvcs.len(),
         pvc_total_capacity_bytes: 0,
         pvc_total_usage_bytes: 0,
         pvcs: Vec::new(),
     };
 
-    for pvc in pvcs.items {
-        let name = pvc.metadata.name.unwrap_or_default();
-        let namespace = pvc.metadata.namespace.unwrap_or_default();
-        let spec = pvc.spec.unwrap_or_default();
-        let status = pvc.status.unwrap_or_default();
+    for pvc in pvcs.iter() {
+        let name = pvc.metadata.name.clone().unwrap_or_default();
+        let namespace = pvc.metadata.namespace.clone().unwrap_or_default();
+        let spec = pvc.spec.clone().unwrap_or_default();
+        let status = pvc.status.clone().unwrap_or_default();
 
         let phase = status.phase.unwrap_or_else(|| "Unknown".to_string());
         
@@ -149,7 +95,7 @@ pub async fn get_storage_status() -> Result<StorageStatusResponse, String> {
             .map(|q| q.0.clone())
             .unwrap_or_else(|| "0".to_string());
             
-        let mut capacity_bytes = parse_capacity(&capacity_str);
+        let mut capacity_bytes = cluster::parse_quantity_bytes(&capacity_str).unwrap_or(0);
         
         // Get storage class
         let storage_class = spec.storage_class_name.unwrap_or_default();
@@ -190,6 +136,16 @@ pub async fn get_storage_status() -> Result<StorageStatusResponse, String> {
             response.pvc_total_usage_bytes += used;
         }
 
+        let users = consumers
+            .get(&(namespace.clone(), name.clone()))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let pods_using = users
+            .iter()
+            .map(|p| p.metadata.name.clone().unwrap_or_default())
+            .collect();
+        let multi_attach_conflict = multi_attach_conflict(&access_modes, users);
+
         response.pvcs.push(PvcInfo {
             name,
             namespace,
@@ -201,22 +157,403 @@ pub async fn get_storage_status() -> Result<StorageStatusResponse, String> {
             storage_class,
             access_modes,
             volume_name,
-            pods_using: Vec::new(), // Note: To populate this we'd need to list Pods and check volumes
+            pods_using,
+            multi_attach_conflict,
         });
     }
 
     Ok(response)
 }
 
-fn parse_capacity(cap: &str) -> u64 {
-    let cap = cap.trim();
-    if cap.ends_with("Gi") {
-        cap.trim_end_matches("Gi").parse::<f64>().unwrap_or(0.0) as u64 * 1024 * 1024 * 1024
-    } else if cap.ends_with("Mi") {
-        cap.trim_end_matches("Mi").parse::<f64>().unwrap_or(0.0) as u64 * 1024 * 1024
-    } else if cap.ends_with("Ki") {
-        cap.trim_end_matches("Ki").parse::<f64>().unwrap_or(0.0) as u64 * 1024
-    } else {
-        cap.parse::<u64>().unwrap_or(0)
+/// Kubelets queried at once for volume stats
+const NODE_STATS_CONCURRENCY: usize = 8;
+
+/// Deadline for one kubelet's metrics (KUBELET_STATS_TIMEOUT_SECS, default 5s)
+fn node_stats_timeout() -> Duration {
+    http_util::timeout_from_env("KUBELET_STATS_TIMEOUT_SECS", 5)
+}
+
+/// Read kubelet volume stats from each node into `stats_map`
+/// Entries land as each node answers, so a caller that times out keeps what was gathered
+async fn collect_volume_stats(
+    client: &Client,
+    nodes: &[Node],
+    stats_map: &mut HashMap<(String, String), (u64, u64)>,
+) {
+    let per_node = node_stats_timeout();
+    let mut responses = futures::stream::iter(nodes.iter().map(|node| {
+        let node_name = node.metadata.name.clone().unwrap_or_default();
+        async move {
+            // Query Kubelet Metrics
+            // Path: /api/v1/nodes/{node_name}/proxy/metrics
+            // We utilize the /metrics endpoint because /stats/summary often misses NFS usage data
+            let result = match http::Request::builder()
+                .uri(format!("/api/v1/nodes/{}/proxy/metrics", node_name))
+                .body(vec![])
+            {
+                Ok(request) => {
+                    let what = format!("Metrics from node {}", node_name);
+                    http_util::with_timeout(&what, per_node, client.request_text(request)).await
+                }
+                Err(e) => Err(format!("Failed to build request: {}", e)),
+            };
+            (node_name, result)
+        }
+    }))
+    .buffer_unordered(NODE_STATS_CONCURRENCY);
+
+    while let Some((node_name, result)) = responses.next().await {
+        match result {
+            Ok(metrics_text) => parse_volume_stats(&metrics_text, stats_map),
+            Err(e) => {
+                // Just log error and continue, don't fail entire request if one node fails
+                error!("Failed to fetch metrics from node {}: {}", node_name, e);
+            }
+        }
+    }
+}
+
+/// Add the kubelet_volume_stats used/capacity samples of one node's metrics to `stats_map`
+fn parse_volume_stats(metrics_text: &str, stats_map: &mut HashMap<(String, String), (u64, u64)>) {
+    // Parse Prometheus format line by line
+    // Example: kubelet_volume_stats_used_bytes{namespace="default",persistentvolumeclaim="data-pvc"} 1024
+    for line in metrics_text.lines() {
+        let is_used = line.starts_with("kubelet_volume_stats_used_bytes{");
+        let is_capacity = line.starts_with("kubelet_volume_stats_capacity_bytes{");
+
+        if is_used || is_capacity {
+            // Very simple parser to avoid unnecessary regex dependencies
+            // 1. Extract content inside {}
+            if let Some(start_brace) = line.find('{') {
+                if let Some(end_brace) = line.find('}') {
+                    let labels_part = &line[start_brace+1..end_brace];
+                    let value_part = &line[end_brace+1..].trim();
+                    
+                    // Parse labels
+                    let mut ns = String::new();
+                    let mut pvc = String::new();
+                    
+                    for label in labels_part.split(',') {
+                        let parts: Vec<&str> = label.split('=').collect();
+                        if parts.len() == 2 {
+                            let key = parts[0].trim();
+                            let val = parts[1].trim().trim_matches('"');
+                            
+                            if key == "namespace" {
+                                ns = val.to_string();
+                            } else if key == "persistentvolumeclaim" {
+                                pvc = val.to_string();
+                            }
+                        }
+                    }
+                    
+                    // Parse value
+                    if !ns.is_empty() && !pvc.is_empty() {
+                        if let Ok(value) = value_part.parse::<f64>() {
+                            let entry = stats_map.entry((ns, pvc)).or_insert((0, 0));
+                            if is_used {
+                                entry.0 = value as u64;
+                            } else {
+                                entry.1 = value as u64;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Request to expand a PVC
+#[derive(Clone, Debug, Deserialize)]
+pub struct ResizeRequest {
+    pub namespace: String,
+    pub name: String,
+    /// New requested size as a Kubernetes quantity, e.g. "20Gi"
+    pub size: String,
+}
+
+/// Parse a requested storage size, which must be a positive quantity
+pub fn parse_storage_size(size: &str) -> Result<u64, String> {
+    match cluster::parse_quantity_bytes(size) {
+        Ok(bytes) if bytes > 0 => Ok(bytes),
+        _ => Err(format!("Invalid storage size '{}': expected a quantity such as 20Gi", size.trim())),
+    }
+}
+
+/// Check that `new_size` is a valid quantity strictly larger than `current`
+pub fn validate_resize(current: &str, new_size: &str) -> Result<(), KusanagiError> {
+    let new_bytes = parse_storage_size(new_size).map_err(KusanagiError::Parse)?;
+    let current_bytes = parse_storage_size(current).map_err(KusanagiError::Internal)?;
+    if new_bytes <= current_bytes {
+        return Err(KusanagiError::Parse(format!(
+            "New size {} must be larger than the current size {} (PVCs cannot shrink)",
+            new_size, current
+        )));
+    }
+    Ok(())
+}
+
+/// Expand a PVC by patching `spec.resources.requests.storage`
+/// Rejects shrinks and StorageClasses without `allowVolumeExpansion`
+pub async fn resize_pvc(namespace: &str, name: &str, new_size: &str) -> Result<(), KusanagiError> {
+    let client = Client::try_default()
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to create Kubernetes client", e))?;
+
+    let pvc_api: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), namespace);
+    let pvc = pvc_api
+        .get(name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get PVC {}/{}", namespace, name), e))?;
+
+    let spec = pvc.spec.unwrap_or_default();
+    let current = spec
+        .resources
+        .as_ref()
+        .and_then(|r| r.requests.as_ref())
+        .and_then(|r| r.get("storage"))
+        .map(|q| q.0.clone())
+        .ok_or_else(|| KusanagiError::Internal(format!("PVC {}/{} has no storage request", namespace, name)))?;
+    validate_resize(&current, new_size)?;
+
+    let class_name = spec.storage_class_name.filter(|c| !c.is_empty()).ok_or_else(|| {
+        KusanagiError::Parse(format!("PVC {}/{} has no StorageClass, it cannot be expanded", namespace, name))
+    })?;
+    let class_api: Api<StorageClass> = Api::all(client);
+    let class = class_api
+        .get(&class_name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get StorageClass {}", class_name), e))?;
+    if class.allow_volume_expansion != Some(true) {
+        return Err(KusanagiError::Parse(format!(
+            "StorageClass {} does not allow volume expansion",
+            class_name
+        )));
+    }
+
+    let patch = serde_json::json!({
+        "spec": { "resources": { "requests": { "storage": new_size } } }
+    });
+    pvc_api
+        .patch(name, &PatchParams::default(), &Patch::Merge(&patch))
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to resize PVC {}/{}", namespace, name), e))?;
+
+    info!("Resized PVC {}/{} from {} to {}", namespace, name, current, new_size);
+    Ok(())
+}
+
+/// Pods per (namespace, claim name), from the pods' persistentVolumeClaim volumes
+/// Completed pods still count: deleting their claim would break a rerun
+pub fn pvc_consumers(pods: &[Pod]) -> HashMap<(String, String), Vec<&Pod>> {
+    let mut consumers: HashMap<(String, String), Vec<&Pod>> = HashMap::new();
+    for pod in pods {
+        let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+        let claims = pod
+            .spec
+            .iter()
+            .flat_map(|s| s.volumes.iter().flatten())
+            .filter_map(|v| v.persistent_volume_claim.as_ref());
+        for claim in claims {
+            consumers
+                .entry((namespace.clone(), claim.claim_name.clone()))
+                .or_default()
+                .push(pod);
+        }
+    }
+    consumers
+}
+
+/// A ReadWriteOnce volume can only be attached to one node: running pods
+/// scheduled on different nodes leave all but one stuck in ContainerCreating
+pub fn multi_attach_conflict(access_modes: &[String], pods: &[&Pod]) -> bool {
+    let rwo_only = access_modes.iter().any(|m| m == "ReadWriteOnce")
+        && !access_modes.iter().any(|m| m == "ReadWriteMany");
+    if !rwo_only {
+        return false;
+    }
+
+    let nodes: HashSet<&str> = pods
+        .iter()
+        .filter(|p| {
+            let phase = p.status.as_ref().and_then(|s| s.phase.as_deref());
+            phase != Some("Succeeded") && phase != Some("Failed")
+        })
+        .filter_map(|p| p.spec.as_ref().and_then(|s| s.node_name.as_deref()))
+        .collect();
+    nodes.len() > 1
+}
+
+/// A Bound PVC that no pod mounts
+pub fn is_orphaned(pvc: &PvcInfo) -> bool {
+    pvc.status == "Bound" && pvc.pods_using.is_empty()
+}
+
+/// Bound PVCs not mounted by any pod, e.g. leftovers from a namespace cleanup
+pub async fn find_orphaned_pvcs() -> Result<Vec<PvcInfo>, String> {
+    let status = get_storage_status(None).await?;
+    let orphaned: Vec<PvcInfo> = status.pvcs.into_iter().filter(is_orphaned).collect();
+    info!("Found {} orphaned PVCs", orphaned.len());
+    Ok(orphaned)
+}
+
+/// Request to delete a PVC
+#[derive(Clone, Debug, Deserialize)]
+pub struct PvcDeleteRequest {
+    pub namespace: String,
+    pub name: String,
+    /// Only report what would be deleted (default)
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+/// Result of a PVC deletion
+#[derive(Clone, Debug, Serialize)]
+pub struct PvcDeleteResponse {
+    pub success: bool,
+    pub dry_run: bool,
+    pub message: String,
+}
+
+/// Delete a PVC, refusing while a pod still mounts it
+/// With `dry_run` the checks run but nothing is deleted
+pub async fn delete_pvc(namespace: &str, name: &str, dry_run: bool) -> Result<PvcDeleteResponse, KusanagiError> {
+    let client = Client::try_default()
+        .await
+        .map_err(|e| KusanagiError::kube("Failed to create Kubernetes client", e))?;
+
+    let pvc_api: Api<PersistentVolumeClaim> = Api::namespaced(client.clone(), namespace);
+    pvc_api
+        .get(name)
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to get PVC {}/{}", namespace, name), e))?;
+
+    // Check against a fresh pod list rather than the cached snapshot
+    let pods_api: Api<Pod> = Api::namespaced(client, namespace);
+    let pods = pods_api
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to list pods in {}", namespace), e))?;
+    if let Some(users) = pvc_consumers(&pods.items).get(&(namespace.to_string(), name.to_string())) {
+        return Err(KusanagiError::Parse(format!(
+            "PVC {}/{} is still mounted by {}",
+            namespace,
+            name,
+            users
+                .iter()
+                .filter_map(|p| p.metadata.name.as_deref())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    if dry_run {
+        return Ok(PvcDeleteResponse {
+            success: true,
+            dry_run,
+            message: format!("Dry run: PVC {}/{} would be deleted", namespace, name),
+        });
+    }
+
+    pvc_api
+        .delete(name, &DeleteParams::default())
+        .await
+        .map_err(|e| KusanagiError::kube(&format!("Failed to delete PVC {}/{}", namespace, name), e))?;
+
+    info!("Deleted PVC {}/{}", namespace, name);
+    Ok(PvcDeleteResponse {
+        success: true,
+        dry_run,
+        message: format!("PVC {}/{} deleted", namespace, name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_volume_stats_reads_used_and_capacity() {
+        let metrics = r#"# HELP kubelet_volume_stats_used_bytes Number of used bytes in the volume
+kubelet_volume_stats_used_bytes{namespace="default",persistentvolumeclaim="data"} 1024
+kubelet_volume_stats_capacity_bytes{namespace="default",persistentvolumeclaim="data"} 4096
+kubelet_volume_stats_inodes{namespace="default",persistentvolumeclaim="data"} 10
+kubelet_volume_stats_used_bytes{namespace="other"} 5"#;
+        let mut stats = HashMap::new();
+        parse_volume_stats(metrics, &mut stats);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[&("default".to_string(), "data".to_string())], (1024, 4096));
+    }
+
+    #[test]
+    fn resize_needs_a_larger_positive_size() {
+        assert!(validate_resize("10Gi", "20Gi").is_ok());
+        assert!(validate_resize("10Gi", "20G").is_ok());
+        assert!(validate_resize("10Gi", "10G").is_err());
+        assert!(validate_resize("10Gi", "0").is_err());
+        assert!(validate_resize("10Gi", "lots").is_err());
+    }
+
+    #[test]
+    fn pvc_with_a_consuming_pod_is_not_orphaned() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "postgres-0", "namespace": "db" },
+            "spec": {
+                "containers": [],
+                "volumes": [{ "name": "data", "persistentVolumeClaim": { "claimName": "data-postgres-0" } }]
+            }
+        }))
+        .unwrap();
+        let pods = vec![pod];
+        let consumers = pvc_consumers(&pods);
+        let pvc = |namespace: &str, name: &str, status: &str| PvcInfo {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            status: status.to_string(),
+            capacity: "10Gi".to_string(),
+            capacity_bytes: 10 * 1024 * 1024 * 1024,
+            used_bytes: None,
+            usage_percent: None,
+            storage_class: "standard".to_string(),
+            access_modes: vec!["ReadWriteOnce".to_string()],
+            volume_name: "pv-1".to_string(),
+            pods_using: consumers
+                .get(&(namespace.to_string(), name.to_string()))
+                .map(|pods| pods.iter().filter_map(|p| p.metadata.name.clone()).collect())
+                .unwrap_or_default(),
+            multi_attach_conflict: false,
+        };
+
+        let mounted = pvc("db", "data-postgres-0", "Bound");
+        assert_eq!(mounted.pods_using, vec!["postgres-0"]);
+        assert!(!is_orphaned(&mounted));
+        // Same claim name in another namespace is a different PVC
+        assert!(is_orphaned(&pvc("staging", "data-postgres-0", "Bound")));
+        assert!(!is_orphaned(&pvc("db", "scratch", "Pending")));
+    }
+
+    #[test]
+    fn rwo_volume_on_two_nodes_is_a_conflict() {
+        let pod = |node: &str, phase: &str| -> Pod {
+            serde_json::from_value(serde_json::json!({
+                "metadata": { "name": format!("web-{}", node), "namespace": "apps" },
+                "spec": { "containers": [], "nodeName": node },
+                "status": { "phase": phase }
+            }))
+            .unwrap()
+        };
+        let rwo = vec!["ReadWriteOnce".to_string()];
+        let rwx = vec!["ReadWriteOnce".to_string(), "ReadWriteMany".to_string()];
+        let (a, b, a2, done) = (pod("a", "Running"), pod("b", "Pending"), pod("a", "Running"), pod("b", "Succeeded"));
+
+        assert!(multi_attach_conflict(&rwo, &[&a, &b]));
+        assert!(!multi_attach_conflict(&rwo, &[&a, &a2]));
+        assert!(!multi_attach_conflict(&rwo, &[&a, &done]));
+        assert!(!multi_attach_conflict(&rwx, &[&a, &b]));
     }
 }
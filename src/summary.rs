@@ -0,0 +1,494 @@
+//! Headline counts for the dashboard in a single call
+//! The sections are gathered concurrently; nodes, pods and storage read the shared
+//! pod/PVC snapshots in `k8s_cache`, so the whole summary costs one list of each.
+//! Storage usage needs a kubelet scan, so its numbers are cached for longer.
+//! The same counts feed the 0-100 health score.
+
+use chrono::Utc;
+use serde::Serialize;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::alertmanager::{self, AlertsResponse};
+use crate::argocd::{self, ArgoStatusResponse};
+use crate::events::{self, EventsResponse};
+use crate::nodes::{self, NodesStatusResponse};
+use crate::pods::{self, PodsStatusResponse};
+use crate::storage::{self, StorageStatusResponse};
+use crate::{cache::TtlCache, http_util};
+
+lazy_static::lazy_static! {
+    /// Storage status of the default cluster; reading volume usage scans every kubelet,
+    /// so dashboard loads reuse it for longer than the shared list snapshots
+    static ref STORAGE_STATUS: TtlCache<StorageStatusResponse> = TtlCache::new(storage_status_ttl());
+}
+
+/// How long summaries reuse storage numbers (STORAGE_SUMMARY_TTL_SECS, default 60s)
+fn storage_status_ttl() -> Duration {
+    let secs = std::env::var("STORAGE_SUMMARY_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+/// Default-cluster storage status, from STORAGE_STATUS while fresh
+async fn cached_storage_status() -> Result<StorageStatusResponse, String> {
+    STORAGE_STATUS
+        .get_or_fetch(|| storage::get_storage_status(None))
+        .await
+        .map(|status| (*status).clone())
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeCounts {
+    pub total: usize,
+    pub ready: usize,
+    pub not_ready: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppCounts {
+    pub total: usize,
+    pub healthy: usize,
+    pub unhealthy: usize,
+    pub out_of_sync: usize,
+    pub stale: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventCounts {
+    pub total: usize,
+    pub warnings: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PodCounts {
+    pub total: usize,
+    pub running: usize,
+    pub pending: usize,
+    pub failed: usize,
+    pub errors: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageCounts {
+    pub pvcs: usize,
+    pub capacity_bytes: u64,
+    pub usage_bytes: u64,
+    /// PVCs at or above 90% usage
+    pub full: usize,
+}
+
+/// Firing alerts by severity
+#[derive(Debug, Serialize)]
+pub struct AlertCounts {
+    pub critical: usize,
+    pub warning: usize,
+}
+
+/// Dashboard summary; a section is null when its source failed (see `errors`)
+#[derive(Debug, Serialize)]
+pub struct DashboardSummary {
+    pub generated_at: String,
+    pub nodes: Option<NodeCounts>,
+    pub argocd: Option<AppCounts>,
+    pub events: Option<EventCounts>,
+    pub pods: Option<PodCounts>,
+    pub storage: Option<StorageCounts>,
+    pub errors: Vec<String>,
+}
+
+impl From<&NodesStatusResponse> for NodeCounts {
+    fn from(nodes: &NodesStatusResponse) -> Self {
+        Self {
+            total: nodes.total_nodes,
+            ready: nodes.ready_nodes,
+            not_ready: nodes.not_ready_nodes,
+        }
+    }
+}
+
+impl From<&ArgoStatusResponse> for AppCounts {
+    fn from(apps: &ArgoStatusResponse) -> Self {
+        Self {
+            total: apps.total,
+            healthy: apps.healthy,
+            unhealthy: apps.unhealthy,
+            out_of_sync: apps.out_of_sync,
+            stale: apps.stale_apps,
+        }
+    }
+}
+
+impl From<&EventsResponse> for EventCounts {
+    fn from(events: &EventsResponse) -> Self {
+        Self {
+            total: events.total_events,
+            warnings: events.warning_count,
+        }
+    }
+}
+
+impl From<&PodsStatusResponse> for PodCounts {
+    fn from(pods: &PodsStatusResponse) -> Self {
+        Self {
+            total: pods.total_pods,
+            running: pods.running_pods,
+            pending: pods.pending_pods,
+            failed: pods.failed_pods,
+            errors: pods.error_pods,
+        }
+    }
+}
+
+impl From<&StorageStatusResponse> for StorageCounts {
+    fn from(storage: &StorageStatusResponse) -> Self {
+        Self {
+            pvcs: storage.pvc_count,
+            capacity_bytes: storage.pvc_total_capacity_bytes,
+            usage_bytes: storage.pvc_total_usage_bytes,
+            full: storage
+                .pvcs
+                .iter()
+                .filter(|p| p.usage_percent.is_some_and(|u| u >= 90.0))
+                .count(),
+        }
+    }
+}
+
+impl From<&AlertsResponse> for AlertCounts {
+    fn from(alerts: &AlertsResponse) -> Self {
+        Self {
+            critical: alerts.critical.iter().filter(|a| a.state == "firing").count(),
+            warning: alerts.warning.iter().filter(|a| a.state == "firing").count(),
+        }
+    }
+}
+
+/// Keep the counts of a successful section, or record why it failed
+fn section<R, C, E>(name: &str, result: Result<R, E>, errors: &mut Vec<String>) -> Option<C>
+where
+    C: for<'a> From<&'a R>,
+    E: std::fmt::Display,
+{
+    match result {
+        Ok(response) => Some(C::from(&response)),
+        Err(e) => {
+            errors.push(format!("{}: {}", name, e));
+            None
+        }
+    }
+}
+
+/// Gather the headline counts for the default cluster
+/// Storage comes from a cached status; a section slower than the scan timeout is left out
+pub async fn get_dashboard_summary() -> DashboardSummary {
+    gather_summary(
+        http_util::scan_timeout(),
+        nodes::get_nodes_status(None),
+        argocd::get_argocd_status(None),
+        events::get_events(None, None),
+        pods::get_pods_status(None, None),
+        cached_storage_status(),
+    )
+    .await
+}
+
+/// Run the sections concurrently, each bounded by `limit`, and keep the counts of those that answered
+async fn gather_summary(
+    limit: Duration,
+    nodes: impl Future<Output = Result<NodesStatusResponse, impl Display>>,
+    argocd: impl Future<Output = Result<ArgoStatusResponse, impl Display>>,
+    events: impl Future<Output = Result<EventsResponse, impl Display>>,
+    pods: impl Future<Output = Result<PodsStatusResponse, impl Display>>,
+    storage: impl Future<Output = Result<StorageStatusResponse, impl Display>>,
+) -> DashboardSummary {
+    let (nodes_result, argocd_result, events_result, pods_result, storage_result) = tokio::join!(
+        http_util::with_timeout("request", limit, nodes),
+        http_util::with_timeout("request", limit, argocd),
+        http_util::with_timeout("request", limit, events),
+        http_util::with_timeout("request", limit, pods),
+        http_util::with_timeout("request", limit, storage)
+    );
+
+    let mut errors = Vec::new();
+    DashboardSummary {
+        generated_at: Utc::now().to_rfc3339(),
+        nodes: section("nodes", nodes_result, &mut errors),
+        argocd: section("argocd", argocd_result, &mut errors),
+        events: section("events", events_result, &mut errors),
+        pods: section("pods", pods_result, &mut errors),
+        storage: section("storage", storage_result, &mut errors),
+        errors,
+    }
+}
+
+/// Weights of the health score components; they add up to 100
+const NODES_WEIGHT: f64 = 30.0;
+const ARGOCD_WEIGHT: f64 = 20.0;
+const PODS_WEIGHT: f64 = 20.0;
+const ALERTS_WEIGHT: f64 = 15.0;
+const STORAGE_WEIGHT: f64 = 15.0;
+
+/// Points lost per firing alert, capped at ALERTS_WEIGHT
+const CRITICAL_ALERT_PENALTY: f64 = 5.0;
+const WARNING_ALERT_PENALTY: f64 = 1.0;
+
+/// Counts the health score is computed from; None when the source failed
+#[derive(Debug)]
+pub struct HealthInputs {
+    pub nodes: Option<NodeCounts>,
+    pub argocd: Option<AppCounts>,
+    pub pods: Option<PodCounts>,
+    pub alerts: Option<AlertCounts>,
+    pub storage: Option<StorageCounts>,
+}
+
+/// One component pulling the score down
+#[derive(Debug, Serialize)]
+pub struct HealthFactor {
+    pub component: String,
+    /// Points lost, out of `weight`
+    pub penalty: f64,
+    pub weight: f64,
+    pub detail: String,
+}
+
+/// Cluster health score; `factors` lists what costs points, worst first
+#[derive(Debug, Serialize)]
+pub struct HealthScore {
+    pub generated_at: String,
+    pub score: u8,
+    pub factors: Vec<HealthFactor>,
+    pub errors: Vec<String>,
+}
+
+/// Share of `weight` lost for `bad` out of `total`
+fn ratio_penalty(weight: f64, bad: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    weight * (bad.min(total) as f64 / total as f64)
+}
+
+/// Score a cluster from 0 to 100, starting from 100 and deducting per component:
+/// - nodes (30): share of nodes not Ready
+/// - argocd (20): share of applications not Healthy
+/// - pods (20): share of pods in error
+/// - alerts (15): 5 per firing critical and 1 per firing warning alert, capped at 15
+/// - storage (15): share of PVCs at 90% usage or more
+///
+/// Components whose source failed are left out rather than counted as unhealthy.
+pub fn health_score(inputs: &HealthInputs) -> (u8, Vec<HealthFactor>) {
+    let mut factors = Vec::new();
+    let mut push = |component: &str, penalty: f64, weight: f64, detail: String| {
+        if penalty > 0.0 {
+            factors.push(HealthFactor {
+                component: component.to_string(),
+                penalty,
+                weight,
+                detail,
+            });
+        }
+    };
+
+    if let Some(nodes) = &inputs.nodes {
+        push(
+            "nodes",
+            ratio_penalty(NODES_WEIGHT, nodes.not_ready, nodes.total),
+            NODES_WEIGHT,
+            format!("{}/{} nodes not ready", nodes.not_ready, nodes.total),
+        );
+    }
+    if let Some(apps) = &inputs.argocd {
+        push(
+            "argocd",
+            ratio_penalty(ARGOCD_WEIGHT, apps.unhealthy, apps.total),
+            ARGOCD_WEIGHT,
+            format!("{}/{} applications unhealthy", apps.unhealthy, apps.total),
+        );
+    }
+    if let Some(pods) = &inputs.pods {
+        push(
+            "pods",
+            ratio_penalty(PODS_WEIGHT, pods.errors, pods.total),
+            PODS_WEIGHT,
+            format!("{}/{} pods in error", pods.errors, pods.total),
+        );
+    }
+    if let Some(alerts) = &inputs.alerts {
+        let penalty = alerts.critical as f64 * CRITICAL_ALERT_PENALTY
+            + alerts.warning as f64 * WARNING_ALERT_PENALTY;
+        push(
+            "alerts",
+            penalty.min(ALERTS_WEIGHT),
+            ALERTS_WEIGHT,
+            format!("{} critical, {} warning alerts firing", alerts.critical, alerts.warning),
+        );
+    }
+    if let Some(storage) = &inputs.storage {
+        push(
+            "storage",
+            ratio_penalty(STORAGE_WEIGHT, storage.full, storage.pvcs),
+            STORAGE_WEIGHT,
+            format!("{}/{} PVCs at 90%+ usage", storage.full, storage.pvcs),
+        );
+    }
+
+    factors.sort_by(|a, b| b.penalty.total_cmp(&a.penalty));
+    let lost: f64 = factors.iter().map(|f| f.penalty).sum();
+    let score = (100.0 - lost).round().clamp(0.0, 100.0) as u8;
+    (score, factors)
+}
+
+/// Gather the inputs for the default cluster and compute its health score
+pub async fn compute_health_score() -> HealthScore {
+    let (nodes_result, argocd_result, pods_result, alerts_result, storage_result) = tokio::join!(
+        nodes::get_nodes_status(None),
+        argocd::get_argocd_status(None),
+        pods::get_pods_status(None, None),
+        alertmanager::get_active_alerts(None),
+        cached_storage_status()
+    );
+
+    let mut errors = Vec::new();
+    let inputs = HealthInputs {
+        nodes: section("nodes", nodes_result, &mut errors),
+        argocd: section("argocd", argocd_result, &mut errors),
+        pods: section("pods", pods_result, &mut errors),
+        alerts: section("alerts", alerts_result, &mut errors),
+        storage: section("storage", storage_result, &mut errors),
+    };
+
+    let (score, factors) = health_score(&inputs);
+    HealthScore {
+        generated_at: Utc::now().to_rfc3339(),
+        score,
+        factors,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(not_ready: usize, unhealthy: usize, errors: usize, critical: usize, full: usize) -> HealthInputs {
+        HealthInputs {
+            nodes: Some(NodeCounts { total: 3, ready: 3 - not_ready, not_ready }),
+            argocd: Some(AppCounts { total: 10, healthy: 10 - unhealthy, unhealthy, out_of_sync: 0, stale: 0 }),
+            pods: Some(PodCounts { total: 20, running: 20 - errors, pending: 0, failed: 0, errors }),
+            alerts: Some(AlertCounts { critical, warning: 0 }),
+            storage: Some(StorageCounts { pvcs: 5, capacity_bytes: 0, usage_bytes: 0, full }),
+        }
+    }
+
+    #[test]
+    fn all_green_cluster_scores_100() {
+        let (score, factors) = health_score(&inputs(0, 0, 0, 0, 0));
+        assert_eq!(score, 100);
+        assert!(factors.is_empty());
+    }
+
+    #[test]
+    fn failures_cost_points_worst_first() {
+        // 1/3 nodes (10), 5/10 apps (10), 2/20 pods (2), 1 critical (5), 1/5 PVCs (3)
+        let (score, factors) = health_score(&inputs(1, 5, 2, 1, 1));
+        assert_eq!(score, 70);
+        let components: Vec<&str> = factors.iter().map(|f| f.component.as_str()).collect();
+        assert_eq!(components, vec!["nodes", "argocd", "alerts", "storage", "pods"]);
+    }
+
+    #[test]
+    fn alerts_are_capped_and_failed_sources_skipped() {
+        let mut inputs = inputs(0, 0, 0, 10, 0);
+        inputs.nodes = None;
+        let (score, factors) = health_score(&inputs);
+        assert_eq!(score, 85);
+        assert_eq!(factors.len(), 1);
+        assert_eq!(factors[0].penalty, ALERTS_WEIGHT);
+    }
+
+    #[test]
+    fn counts_match_the_individual_endpoints() {
+        let nodes = NodesStatusResponse { total_nodes: 4, ready_nodes: 3, not_ready_nodes: 1, nodes: vec![] };
+        let pods = PodsStatusResponse {
+            total_pods: 40,
+            running_pods: 35,
+            pending_pods: 2,
+            succeeded_pods: 1,
+            failed_pods: 2,
+            error_pods: 3,
+            oom_killed_pods: 1,
+            best_effort_error_pods: 0,
+            pods_in_error: vec![],
+            groups: None,
+        };
+        let events = EventsResponse { total_events: 12, warning_count: 5, normal_count: 7, events: vec![] };
+        let storage = StorageStatusResponse {
+            pvc_count: 6,
+            pvc_total_capacity_bytes: 1 << 30,
+            pvc_total_usage_bytes: 1 << 29,
+            pvcs: vec![],
+        };
+
+        let mut errors = Vec::new();
+        let node_counts: NodeCounts = section("nodes", Ok::<_, String>(nodes), &mut errors).unwrap();
+        let pod_counts: PodCounts = section("pods", Ok::<_, String>(pods), &mut errors).unwrap();
+        let event_counts: EventCounts = section("events", Ok::<_, String>(events), &mut errors).unwrap();
+        let storage_counts: StorageCounts = section("storage", Ok::<_, String>(storage), &mut errors).unwrap();
+        let argocd: Option<AppCounts> =
+            section("argocd", Err::<ArgoStatusResponse, _>("ArgoCD unreachable"), &mut errors);
+
+        assert_eq!((node_counts.total, node_counts.ready, node_counts.not_ready), (4, 3, 1));
+        assert_eq!(
+            (pod_counts.total, pod_counts.running, pod_counts.pending, pod_counts.failed, pod_counts.errors),
+            (40, 35, 2, 2, 3)
+        );
+        assert_eq!((event_counts.total, event_counts.warnings), (12, 5));
+        assert_eq!((storage_counts.pvcs, storage_counts.capacity_bytes, storage_counts.usage_bytes), (6, 1 << 30, 1 << 29));
+        assert!(argocd.is_none());
+        assert_eq!(errors, vec!["argocd: ArgoCD unreachable"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn summary_keeps_the_sections_that_answer_in_time() {
+        let nodes = NodesStatusResponse { total_nodes: 2, ready_nodes: 2, not_ready_nodes: 0, nodes: vec![] };
+        let events = EventsResponse { total_events: 3, warning_count: 1, normal_count: 2, events: vec![] };
+        let pods = PodsStatusResponse {
+            total_pods: 10,
+            running_pods: 9,
+            pending_pods: 1,
+            succeeded_pods: 0,
+            failed_pods: 0,
+            error_pods: 0,
+            oom_killed_pods: 0,
+            best_effort_error_pods: 0,
+            pods_in_error: vec![],
+            groups: None,
+        };
+        let stuck_storage = std::future::pending::<Result<StorageStatusResponse, String>>();
+
+        let summary = gather_summary(
+            Duration::from_secs(20),
+            async { Ok::<_, String>(nodes) },
+            async { Err::<ArgoStatusResponse, _>("ArgoCD unreachable") },
+            async { Ok::<_, String>(events) },
+            async { Ok::<_, String>(pods) },
+            stuck_storage,
+        )
+        .await;
+
+        assert_eq!(summary.nodes.map(|n| n.ready), Some(2));
+        assert_eq!(summary.events.map(|e| e.warnings), Some(1));
+        assert_eq!(summary.pods.map(|p| p.pending), Some(1));
+        assert!(summary.argocd.is_none());
+        assert!(summary.storage.is_none());
+        assert_eq!(
+            summary.errors,
+            vec!["argocd: ArgoCD unreachable", "storage: request timed out after 20s"]
+        );
+    }
+}
@@ -1,11 +1,23 @@
 //! OpenObserve Telemetry Module
 //! Sends APM metrics and logs to OpenObserve for performance monitoring
-
+//! and exposes the agent's own request metrics in Prometheus format
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    Error,
+};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
-use tracing::{info, warn, error};
+use tracing::{info, warn, error, Instrument};
+
+use crate::http_util;
 
 // ============================================================================
 // Configuration
@@ -14,35 +26,97 @@ use tracing::{info, warn, error};
 lazy_static::lazy_static! {
     static ref TELEMETRY_CONFIG: Mutex<TelemetryConfig> = Mutex::new(TelemetryConfig::default());
     static ref EVENT_QUEUE: Mutex<Vec<TelemetryEvent>> = Mutex::new(Vec::new());
+    /// Batches whose send failed, retried on the next flush
+    static ref RETRY_QUEUE: Mutex<VecDeque<FailedBatch>> = Mutex::new(VecDeque::new());
+    static ref PROMETHEUS_HANDLE: PrometheusHandle = PrometheusBuilder::new()
+        .install_recorder()
+        .expect("Failed to install Prometheus metrics recorder");
 }
 
 static TELEMETRY_ENABLED: AtomicBool = AtomicBool::new(true);
 
+tokio::task_local! {
+    /// Correlation ID of the request being handled on this task
+    static REQUEST_ID: String;
+}
+
 #[derive(Clone)]
 pub struct TelemetryConfig {
     pub endpoint: String,
     pub auth_token: Option<String>,
+    /// OTLP/HTTP collector base URL; when set, events go there instead of OpenObserve
+    pub otlp_endpoint: Option<String>,
     pub batch_size: usize,
     pub flush_interval_secs: u64,
     pub sample_rate: f64,
+    /// Per-span-name rates that take precedence over `sample_rate` (APM_SAMPLE_OVERRIDES)
+    pub sample_overrides: HashMap<String, f64>,
+    /// Roll spans up into per-name duration statistics before sending (APM_AGGREGATE)
+    pub aggregate: bool,
+    /// Most events held for retry after failed sends (APM_MAX_QUEUE_SIZE)
+    pub max_queue_size: usize,
 }
 
+/// Clients reused across flushes, one per backend so each keeps its own timeout
+static OPENOBSERVE_CLIENT: http_util::SharedClient =
+    http_util::SharedClient::new(|| http_util::timeout_from_env("OPENOBSERVE_TIMEOUT_SECS", 10));
+static OTLP_CLIENT: http_util::SharedClient =
+    http_util::SharedClient::new(|| http_util::timeout_from_env("OTLP_TIMEOUT_SECS", 10));
+
+/// Send attempts per batch before its events are dropped
+const MAX_SEND_ATTEMPTS: u32 = 3;
+
+/// Upper bound on queued events in aggregate mode before an early flush
+const MAX_AGGREGATE_QUEUE: usize = 10_000;
+
 impl Default for TelemetryConfig {
     fn default() -> Self {
         Self {
             endpoint: std::env::var("OPENOBSERVE_ENDPOINT")
                 .unwrap_or_else(|_| "https://o2-openobserve.p.zacharie.org/api/default/v1/logs".to_string()),
             auth_token: std::env::var("OPENOBSERVE_AUTH").ok(),
+            otlp_endpoint: std::env::var("OTLP_ENDPOINT").ok().filter(|s| !s.trim().is_empty()),
             batch_size: 10,
             flush_interval_secs: 5,
             sample_rate: std::env::var("APM_SAMPLE_RATE")
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(1.0),
+            sample_overrides: std::env::var("APM_SAMPLE_OVERRIDES")
+                .map(|v| parse_sample_overrides(&v))
+                .unwrap_or_default(),
+            aggregate: std::env::var("APM_AGGREGATE")
+                .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            max_queue_size: std::env::var("APM_MAX_QUEUE_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1000),
         }
     }
 }
 
+impl TelemetryConfig {
+    /// Sample rate for a span: its override if one is set, otherwise the global rate
+    pub fn sample_rate_for(&self, span_name: &str) -> f64 {
+        self.sample_overrides.get(span_name).copied().unwrap_or(self.sample_rate)
+    }
+}
+
+/// Parse `span:rate` pairs such as "ws_stats:0.1,report:1.0"
+/// Malformed entries are skipped and rates are clamped to 0..=1
+pub fn parse_sample_overrides(value: &str) -> HashMap<String, f64> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (name, rate) = entry.split_once(':')?;
+            let rate = rate.trim().parse::<f64>().ok()?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| (name.to_string(), rate.clamp(0.0, 1.0)))
+        })
+        .collect()
+}
+
 // ============================================================================
 // Telemetry Events
 // ============================================================================
@@ -240,14 +314,26 @@ impl Drop for SpanTimer {
 // Event Queue & Flushing
 // ============================================================================
 
-fn queue_event(event: TelemetryEvent) {
+/// Whether an event survives sampling at `rate`
+fn is_sampled(rate: f64, rng: &mut impl rand::Rng) -> bool {
+    rate >= 1.0 || rng.gen::<f64>() <= rate
+}
+
+fn queue_event(mut event: TelemetryEvent) {
     if !TELEMETRY_ENABLED.load(Ordering::Relaxed) {
         return;
     }
 
-    // Sample rate check
+    if let Some(request_id) = current_request_id() {
+        event
+            .extra
+            .entry("request_id".to_string())
+            .or_insert(serde_json::Value::String(request_id));
+    }
+
+    // Sample rate check, per span name first
     let config = TELEMETRY_CONFIG.lock().unwrap();
-    if config.sample_rate < 1.0 && rand::random::<f64>() > config.sample_rate {
+    if !is_sampled(config.sample_rate_for(&event.span_name), &mut rand::thread_rng()) {
         return;
     }
     drop(config);
@@ -255,7 +341,10 @@ fn queue_event(event: TelemetryEvent) {
     let mut queue = EVENT_QUEUE.lock().unwrap();
     queue.push(event);
     
-    let batch_size = TELEMETRY_CONFIG.lock().unwrap().batch_size;
+    // In aggregate mode the periodic flusher drains the queue once per interval
+    let config = TELEMETRY_CONFIG.lock().unwrap();
+    let batch_size = if config.aggregate { MAX_AGGREGATE_QUEUE } else { config.batch_size };
+    drop(config);
     if queue.len() >= batch_size {
         let events: Vec<_> = queue.drain(..).collect();
         drop(queue);
@@ -265,55 +354,340 @@ fn queue_event(event: TelemetryEvent) {
     }
 }
 
-async fn flush_events(events: Vec<TelemetryEvent>) {
-    if events.is_empty() {
-        return;
+/// Where queued events are sent
+#[derive(Debug, PartialEq)]
+pub enum Exporter {
+    /// Custom JSON array posted to OpenObserve's logs endpoint (default)
+    OpenObserve,
+    /// OTLP/HTTP JSON spans posted to `<endpoint>/v1/traces`
+    Otlp(String),
+}
+
+/// Pick the exporter for the configured OTLP endpoint: OTLP when one is set and not blank
+pub fn select_exporter(otlp_endpoint: Option<&str>) -> Exporter {
+    match otlp_endpoint.map(str::trim).filter(|e| !e.is_empty()) {
+        Some(endpoint) => Exporter::Otlp(endpoint.to_string()),
+        None => Exporter::OpenObserve,
+    }
+}
+
+/// A batch that failed to send, with the number of attempts made so far
+/// Events are stored as sent (post-aggregation) so a retry doesn't aggregate twice
+#[derive(Debug)]
+pub struct FailedBatch {
+    pub attempts: u32,
+    pub events: Vec<TelemetryEvent>,
+}
+
+/// Put a failed batch back for the next flush
+/// Batches that reached `max_attempts`, and the oldest batches beyond `max_events`
+/// queued events, are dropped; returns how many events were dropped
+pub fn requeue_failed(
+    pending: &mut VecDeque<FailedBatch>,
+    batch: FailedBatch,
+    max_events: usize,
+    max_attempts: u32,
+) -> usize {
+    if batch.attempts >= max_attempts {
+        return batch.events.len();
     }
 
+    pending.push_back(batch);
+    let mut queued: usize = pending.iter().map(|b| b.events.len()).sum();
+    let mut dropped = 0;
+    while queued > max_events {
+        let Some(oldest) = pending.pop_front() else { break };
+        queued -= oldest.events.len();
+        dropped += oldest.events.len();
+    }
+    dropped
+}
+
+async fn flush_events(events: Vec<TelemetryEvent>) {
     let config = TELEMETRY_CONFIG.lock().unwrap().clone();
-    
-    let auth_token = match config.auth_token {
+    let events = if config.aggregate {
+        aggregate(&events)
+            .into_iter()
+            .map(AggregatedMetric::into_event)
+            .collect()
+    } else {
+        events
+    };
+
+    let retries: Vec<FailedBatch> = RETRY_QUEUE.lock().unwrap().drain(..).collect();
+    let batches = retries
+        .into_iter()
+        .chain(std::iter::once(FailedBatch { attempts: 0, events }))
+        .filter(|batch| !batch.events.is_empty());
+
+    for mut batch in batches {
+        let sent = match select_exporter(config.otlp_endpoint.as_deref()) {
+            Exporter::Otlp(endpoint) => send_otlp(&endpoint, &batch.events).await,
+            Exporter::OpenObserve => send_openobserve(&config, &batch.events).await,
+        };
+        if sent.is_ok() {
+            continue;
+        }
+
+        batch.attempts += 1;
+        let dropped = requeue_failed(
+            &mut RETRY_QUEUE.lock().unwrap(),
+            batch,
+            config.max_queue_size,
+            MAX_SEND_ATTEMPTS,
+        );
+        if dropped > 0 {
+            warn!(dropped = dropped, "⏱️ APM: Dropping events after failed retries");
+            metrics::counter!("kusanagi_telemetry_dropped_after_retries_total").increment(dropped as u64);
+        }
+    }
+}
+
+/// Returns Err when the batch should be retried; a missing auth token is not retryable
+async fn send_openobserve(config: &TelemetryConfig, events: &[TelemetryEvent]) -> Result<(), ()> {
+    let auth_token = match &config.auth_token {
         Some(token) => token,
         None => {
             warn!("⏱️ APM: No auth token configured, skipping OpenObserve send");
-            return;
+            return Ok(());
         }
     };
 
-    let client = reqwest::Client::new();
+    let client = match OPENOBSERVE_CLIENT.get() {
+        Ok(c) => c,
+        Err(e) => {
+            error!(error = %e, "⏱️ APM: Failed to create OpenObserve client");
+            return Err(());
+        }
+    };
     
     match client
         .post(&config.endpoint)
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Basic {}", auth_token))
-        .json(&events)
+        .json(events)
         .send()
         .await
     {
         Ok(response) => {
             if response.status().is_success() {
                 info!(count = events.len(), "⏱️ APM: Sent {} events to OpenObserve", events.len());
+                Ok(())
             } else {
                 warn!(
                     status = %response.status(),
                     "⏱️ APM: OpenObserve returned error status"
                 );
+                Err(())
             }
         }
         Err(e) => {
-            error!(error = %e, "⏱️ APM: Failed to send events to OpenObserve");
+            error!(error = %http_util::describe_error(&e), "⏱️ APM: Failed to send events to OpenObserve");
+            Err(())
+        }
+    }
+}
+
+// ============================================================================
+// OTLP Export
+// ============================================================================
+
+fn otlp_string(key: &str, value: &str) -> serde_json::Value {
+    serde_json::json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Convert an event to an OTLP span; the event timestamp marks the span end
+fn otlp_span(event: &TelemetryEvent) -> serde_json::Value {
+    let end = chrono::DateTime::parse_from_rfc3339(&event.timestamp)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|_| chrono::Utc::now());
+    let end_ns = end.timestamp_nanos_opt().unwrap_or_default();
+    let start_ns = end_ns - (event.duration_ms * 1_000_000.0) as i64;
+
+    let mut attributes = vec![otlp_string("kusanagi.event_type", &event.event_type)];
+    if let Some(ns) = &event.namespace {
+        attributes.push(otlp_string("k8s.namespace.name", ns));
+    }
+    if let Some(endpoint) = &event.endpoint {
+        attributes.push(otlp_string("kusanagi.endpoint", endpoint));
+    }
+    if let Some(count) = event.items_count {
+        attributes.push(serde_json::json!({
+            "key": "kusanagi.items_count",
+            "value": { "intValue": count.to_string() }
+        }));
+    }
+    for (key, value) in &event.extra {
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        attributes.push(otlp_string(key, &value));
+    }
+
+    // STATUS_CODE_OK = 1, STATUS_CODE_ERROR = 2
+    let status = match &event.error {
+        Some(message) => serde_json::json!({ "code": 2, "message": message }),
+        None => serde_json::json!({ "code": 1 }),
+    };
+
+    serde_json::json!({
+        "traceId": format!("{:032x}", rand::random::<u128>()),
+        "spanId": format!("{:016x}", rand::random::<u64>()),
+        "name": event.span_name,
+        "kind": 1,
+        "startTimeUnixNano": start_ns.to_string(),
+        "endTimeUnixNano": end_ns.to_string(),
+        "attributes": attributes,
+        "status": status,
+    })
+}
+
+/// Build an OTLP/HTTP JSON `ExportTraceServiceRequest` body
+pub fn otlp_payload(events: &[TelemetryEvent]) -> serde_json::Value {
+    let version = events
+        .first()
+        .map(|e| e.version.clone())
+        .unwrap_or_else(|| env!("CARGO_PKG_VERSION").to_string());
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    otlp_string("service.name", "kusanagi"),
+                    otlp_string("service.version", &version),
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "kusanagi" },
+                "spans": events.iter().map(otlp_span).collect::<Vec<_>>(),
+            }]
+        }]
+    })
+}
+
+/// Returns Err when the batch should be retried
+async fn send_otlp(endpoint: &str, events: &[TelemetryEvent]) -> Result<(), ()> {
+    let client = match OTLP_CLIENT.get() {
+        Ok(c) => c,
+        Err(e) => {
+            error!(error = %e, "⏱️ APM: Failed to create OTLP client");
+            return Err(());
+        }
+    };
+
+    let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    match client.post(&url).json(&otlp_payload(events)).send().await {
+        Ok(response) if response.status().is_success() => {
+            info!(count = events.len(), "⏱️ APM: Sent {} spans to OTLP collector", events.len());
+            Ok(())
+        }
+        Ok(response) => {
+            warn!(status = %response.status(), "⏱️ APM: OTLP collector returned error status");
+            Err(())
+        }
+        Err(e) => {
+            error!(error = %http_util::describe_error(&e), "⏱️ APM: Failed to send spans to OTLP collector");
+            Err(())
         }
     }
 }
 
+// ============================================================================
+// Aggregation
+// ============================================================================
+
+/// Duration statistics for one span name over a flush interval
+#[derive(Serialize, Clone, Debug)]
+pub struct AggregatedMetric {
+    pub span_name: String,
+    pub count: usize,
+    pub error_count: usize,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+impl AggregatedMetric {
+    /// Wrap the statistics in an event so both exporters can send them unchanged
+    fn into_event(self) -> TelemetryEvent {
+        let mut event = TelemetryEvent::new(&self.span_name, Duration::ZERO);
+        event.event_type = "apm_aggregate".to_string();
+        event.duration_ms = self.p50_ms;
+        event.items_count = Some(self.count as u64);
+        event.extra.insert("count".to_string(), self.count.into());
+        event.extra.insert("error_count".to_string(), self.error_count.into());
+        event.extra.insert("min_ms".to_string(), self.min_ms.into());
+        event.extra.insert("max_ms".to_string(), self.max_ms.into());
+        event.extra.insert("p50_ms".to_string(), self.p50_ms.into());
+        event.extra.insert("p95_ms".to_string(), self.p95_ms.into());
+        event
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Roll events up per span name into count/min/max/p50/p95, sorted by span name
+pub fn aggregate(events: &[TelemetryEvent]) -> Vec<AggregatedMetric> {
+    let mut by_span: std::collections::BTreeMap<&str, (Vec<f64>, usize)> =
+        std::collections::BTreeMap::new();
+    for event in events {
+        let (durations, errors) = by_span.entry(event.span_name.as_str()).or_default();
+        durations.push(event.duration_ms);
+        if event.error.is_some() {
+            *errors += 1;
+        }
+    }
+
+    by_span
+        .into_iter()
+        .map(|(span_name, (mut durations, error_count))| {
+            durations.sort_by(|a, b| a.total_cmp(b));
+            AggregatedMetric {
+                span_name: span_name.to_string(),
+                count: durations.len(),
+                error_count,
+                min_ms: durations[0],
+                max_ms: durations[durations.len() - 1],
+                p50_ms: percentile(&durations, 50.0),
+                p95_ms: percentile(&durations, 95.0),
+            }
+        })
+        .collect()
+}
+
+/// In aggregate mode, flush the queue every `flush_interval_secs`
+/// Call once from `main()`; does nothing in raw mode
+pub fn start_periodic_flush() {
+    let config = TELEMETRY_CONFIG.lock().unwrap().clone();
+    if !config.aggregate {
+        return;
+    }
+
+    let interval = Duration::from_secs(config.flush_interval_secs.max(1));
+    info!(interval_secs = interval.as_secs(), "⏱️ APM: Aggregating spans before sending");
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            force_flush().await;
+        }
+    });
+}
+
 /// Force flush all queued events
 pub async fn force_flush() {
     let events: Vec<_> = {
         let mut queue = EVENT_QUEUE.lock().unwrap();
         queue.drain(..).collect()
     };
-    
-    if !events.is_empty() {
+    let has_retries = !RETRY_QUEUE.lock().unwrap().is_empty();
+
+    if !events.is_empty() || has_retries {
         flush_events(events).await;
     }
 }
@@ -361,3 +735,247 @@ pub fn set_enabled(enabled: bool) {
     TELEMETRY_ENABLED.store(enabled, Ordering::Relaxed);
     info!(enabled = enabled, "⏱️ APM: Telemetry status changed");
 }
+
+// ============================================================================
+// Prometheus Exporter (agent self-metrics)
+// ============================================================================
+
+/// Install the global Prometheus recorder. Must be called once from `main()`
+/// before the server starts handling requests.
+pub fn install_metrics_recorder() {
+    lazy_static::initialize(&PROMETHEUS_HANDLE);
+    info!("⏱️ APM: Prometheus metrics recorder installed");
+}
+
+/// Render all recorded metrics in the Prometheus text exposition format
+pub fn render_metrics() -> String {
+    PROMETHEUS_HANDLE.render()
+}
+
+/// Middleware recording per-endpoint request counters and latency histograms
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let start = Instant::now();
+    let method = req.method().to_string();
+
+    let res = next.call(req).await?;
+
+    // Use the route pattern (e.g. /api/nodes/{name}) to keep label cardinality bounded
+    let endpoint = res
+        .request()
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let status = res.status().as_u16().to_string();
+    let elapsed = start.elapsed().as_secs_f64();
+
+    metrics::counter!(
+        "kusanagi_http_requests_total",
+        "method" => method.clone(),
+        "endpoint" => endpoint.clone(),
+        "status" => status
+    )
+    .increment(1);
+    metrics::histogram!(
+        "kusanagi_http_request_duration_seconds",
+        "method" => method,
+        "endpoint" => endpoint
+    )
+    .record(elapsed);
+
+    Ok(res)
+}
+
+// ============================================================================
+// Request IDs
+// ============================================================================
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The request ID of the request handled on the current task, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Accept a client-supplied ID only if it is short, printable ASCII
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+fn generate_request_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Middleware that reads or generates an `X-Request-Id`, runs the request inside a
+/// tracing span carrying it, tags telemetry events with it and echoes it back
+pub async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| valid_request_id(v))
+        .map(|v| v.to_string())
+        .unwrap_or_else(generate_request_id);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %req.method(),
+        path = %req.path()
+    );
+
+    let mut res = REQUEST_ID
+        .scope(id.clone(), next.call(req).instrument(span))
+        .await?;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(span: &str, millis: u64) -> TelemetryEvent {
+        TelemetryEvent::new(span, Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let durations: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(percentile(&durations, 50.0), 10.0);
+        assert_eq!(percentile(&durations, 95.0), 19.0);
+        assert_eq!(percentile(&durations, 100.0), 20.0);
+        assert_eq!(percentile(&[7.0], 95.0), 7.0);
+    }
+
+    #[test]
+    fn aggregate_rolls_up_per_span() {
+        let mut events: Vec<TelemetryEvent> = [40, 10, 30, 20].iter().map(|ms| event("get_nodes", *ms)).collect();
+        events[0].error = Some("timeout".to_string());
+        events.push(event("get_pods", 5));
+
+        let metrics = aggregate(&events);
+        assert_eq!(metrics.len(), 2);
+        let nodes = &metrics[0];
+        assert_eq!(nodes.span_name, "get_nodes");
+        assert_eq!((nodes.count, nodes.error_count), (4, 1));
+        assert_eq!((nodes.min_ms, nodes.max_ms), (10.0, 40.0));
+        assert_eq!((nodes.p50_ms, nodes.p95_ms), (20.0, 40.0));
+        assert_eq!(metrics[1].span_name, "get_pods");
+    }
+
+    #[actix_web::test]
+    async fn response_carries_the_request_id() {
+        use actix_web::{middleware, test, web, App, HttpResponse};
+
+        let app = test::init_service(
+            App::new().wrap(middleware::from_fn(request_id)).route(
+                "/",
+                web::get().to(|| async { HttpResponse::Ok().body(current_request_id().unwrap_or_default()) }),
+            ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/").insert_header(("X-Request-Id", "abc-123")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "abc-123");
+        assert_eq!(test::read_body(res).await, "abc-123");
+
+        // Missing or unusable IDs are replaced with a generated one
+        for req in [
+            test::TestRequest::get().uri("/"),
+            test::TestRequest::get().uri("/").insert_header(("X-Request-Id", "has space")),
+        ] {
+            let res = test::call_service(&app, req.to_request()).await;
+            let id = res.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+            assert_eq!(id.len(), 32);
+            assert_eq!(test::read_body(res).await, id);
+        }
+    }
+
+    #[test]
+    fn otlp_endpoint_selects_the_otlp_exporter() {
+        assert_eq!(
+            select_exporter(Some("http://otel-collector:4318")),
+            Exporter::Otlp("http://otel-collector:4318".to_string())
+        );
+        assert_eq!(select_exporter(Some("  ")), Exporter::OpenObserve);
+        assert_eq!(select_exporter(None), Exporter::OpenObserve);
+    }
+
+    #[test]
+    fn otlp_payload_carries_one_span_per_event() {
+        let events = vec![event("get_nodes", 20), event("get_pods", 5).with_error("boom")];
+        let payload = otlp_payload(&events);
+        let spans = payload["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0]["name"], "get_nodes");
+        assert_eq!(spans[0]["status"]["code"], 1);
+        assert_eq!(spans[1]["status"], serde_json::json!({ "code": 2, "message": "boom" }));
+    }
+
+    #[test]
+    fn failed_batch_requeues_then_drops_at_the_cap() {
+        let mut pending = VecDeque::new();
+        let batch = |attempts| FailedBatch {
+            attempts,
+            events: vec![event("get_nodes", 10), event("get_pods", 20)],
+        };
+
+        // First failure: held for the next flush
+        assert_eq!(requeue_failed(&mut pending, batch(1), 100, 2), 0);
+        assert_eq!(pending.len(), 1);
+
+        // Retry failed too: dropped instead of looping forever
+        let mut retry = pending.pop_front().unwrap();
+        retry.attempts += 1;
+        assert_eq!(requeue_failed(&mut pending, retry, 100, 2), 2);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn requeue_is_bounded_by_queue_size() {
+        let mut pending = VecDeque::new();
+        let batch = |span: &str| FailedBatch {
+            attempts: 1,
+            events: vec![event(span, 10), event(span, 20)],
+        };
+
+        assert_eq!(requeue_failed(&mut pending, batch("oldest"), 3, 5), 0);
+        assert_eq!(requeue_failed(&mut pending, batch("newest"), 3, 5), 2);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].events[0].span_name, "newest");
+    }
+
+    #[test]
+    fn overridden_span_is_sampled_at_its_own_rate() {
+        use rand::SeedableRng;
+
+        let config = TelemetryConfig {
+            sample_rate: 1.0,
+            sample_overrides: parse_sample_overrides("ws_stats:0.1, report:1.0,bad,noise:x"),
+            ..Default::default()
+        };
+        assert_eq!(config.sample_overrides.len(), 2);
+        assert_eq!(config.sample_rate_for("ws_stats"), 0.1);
+        assert_eq!(config.sample_rate_for("get_nodes"), 1.0);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let kept = |span: &str, rng: &mut rand::rngs::StdRng| {
+            (0..10_000).filter(|_| is_sampled(config.sample_rate_for(span), rng)).count()
+        };
+        let ws_stats = kept("ws_stats", &mut rng);
+        assert!((800..1200).contains(&ws_stats), "kept {} of 10000 ws_stats spans", ws_stats);
+        assert_eq!(kept("report", &mut rng), 10_000);
+        assert_eq!(kept("get_nodes", &mut rng), 10_000);
+    }
+}
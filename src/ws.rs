@@ -1,18 +1,33 @@
 use actix::{Actor, ActorContext, AsyncContext, Handler, Message, StreamHandler};
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Event;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tracing::info;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
-use crate::{argocd, events, pods};
+use crate::{alertmanager, argocd, cilium, events, notifiers, pods};
 
 /// How often heartbeat pings are sent
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 /// How long before lack of client response causes a timeout
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Messages sent without a heartbeat reply before non-critical ones are dropped
+const MAX_UNANSWERED_MESSAGES: usize = 3;
 /// How often to check for new alerts
 const ALERT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Alerts buffered for a session that is slow to pick them up
+const ALERT_UPDATES_CAPACITY: usize = 16;
+
+lazy_static::lazy_static! {
+    /// Alerts found by the single background check, fanned out to every session
+    static ref ALERT_UPDATES: broadcast::Sender<NotificationMessage> = broadcast::channel(ALERT_UPDATES_CAPACITY).0;
+}
 
 /// WebSocket notification message types
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -25,6 +40,9 @@ pub enum NotificationMessage {
         message: String,
         source: String,
         timestamp: String,
+        /// Upstream identity (the Alertmanager fingerprint), used to de-duplicate webhook deliveries
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fingerprint: Option<String>,
     },
     #[serde(rename = "stats_update")]
     StatsUpdate {
@@ -36,6 +54,95 @@ pub enum NotificationMessage {
     Connected { message: String },
     #[serde(rename = "heartbeat")]
     Heartbeat { timestamp: String },
+    /// Hubble Relay went down, is being retried, or came back
+    #[serde(rename = "relay_status")]
+    RelayStatus { status: cilium::RelayStatus },
+}
+
+/// Notification channels a client can subscribe to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Alerts,
+    Stats,
+    Hubble,
+}
+
+const ALL_CHANNELS: [Channel; 3] = [Channel::Alerts, Channel::Stats, Channel::Hubble];
+
+impl NotificationMessage {
+    /// Channel the message belongs to; None for session messages that always go out
+    fn channel(&self) -> Option<Channel> {
+        match self {
+            NotificationMessage::Alert { .. } => Some(Channel::Alerts),
+            NotificationMessage::StatsUpdate { .. } => Some(Channel::Stats),
+            NotificationMessage::RelayStatus { .. } => Some(Channel::Hubble),
+            NotificationMessage::Connected { .. } | NotificationMessage::Heartbeat { .. } => None,
+        }
+    }
+
+    /// Stats and heartbeats are superseded by the next one, so they can be dropped for a slow client
+    fn is_droppable(&self) -> bool {
+        matches!(
+            self,
+            NotificationMessage::StatsUpdate { .. } | NotificationMessage::Heartbeat { .. }
+        )
+    }
+}
+
+/// A client is behind when it missed a heartbeat and messages keep piling up unanswered
+pub fn should_drop(message: &NotificationMessage, since_reply: Duration, unanswered: usize) -> bool {
+    message.is_droppable() && since_reply > HEARTBEAT_INTERVAL && unanswered >= MAX_UNANSWERED_MESSAGES
+}
+
+/// Client command, e.g. `{"action": "subscribe", "channels": ["stats"]}`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ChannelCommand {
+    Subscribe { channels: Vec<Channel> },
+    Unsubscribe { channels: Vec<Channel> },
+}
+
+/// Channels a session forwards; `None` means every channel (clients that never subscribe)
+#[derive(Debug, Default)]
+pub struct Subscriptions(Option<HashSet<Channel>>);
+
+impl Subscriptions {
+    /// The first subscribe narrows the session from "everything" to the listed channels
+    fn subscribe(&mut self, channels: &[Channel]) {
+        self.0.get_or_insert_with(HashSet::new).extend(channels);
+    }
+
+    fn unsubscribe(&mut self, channels: &[Channel]) {
+        let current = self.0.get_or_insert_with(|| ALL_CHANNELS.into_iter().collect());
+        for channel in channels {
+            current.remove(channel);
+        }
+    }
+
+    pub fn wants(&self, message: &NotificationMessage) -> bool {
+        match (message.channel(), &self.0) {
+            (None, _) | (_, None) => true,
+            (Some(channel), Some(channels)) => channels.contains(&channel),
+        }
+    }
+}
+
+/// A WebSocket actor that tracks when the client last answered
+trait Heartbeat: Actor<Context = ws::WebsocketContext<Self>> {
+    fn last_heartbeat(&self) -> Instant;
+}
+
+/// Ping the client every HEARTBEAT_INTERVAL and disconnect it after CLIENT_TIMEOUT without a reply
+fn start_heartbeat<A: Heartbeat>(ctx: &mut ws::WebsocketContext<A>) {
+    ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+        if Instant::now().duration_since(act.last_heartbeat()) > CLIENT_TIMEOUT {
+            info!("WebSocket client heartbeat failed, disconnecting");
+            ctx.stop();
+            return;
+        }
+        ctx.ping(b"");
+    });
 }
 
 /// Internal message for sending notifications
@@ -47,44 +154,69 @@ pub struct SendNotification(pub NotificationMessage);
 pub struct NotificationSession {
     /// Client must send ping at least once per CLIENT_TIMEOUT
     hb: Instant,
+    /// Messages sent since the client last answered a heartbeat
+    unanswered: usize,
     /// Last known state for change detection
     last_argocd_issues: usize,
     last_error_pods: usize,
     last_warning_events: usize,
+    /// Channels this client wants
+    subscriptions: Subscriptions,
+    /// Task forwarding Hubble Relay status changes to this session
+    relay_forwarder: Option<JoinHandle<()>>,
+    /// Task forwarding alerts from the shared alert check to this session
+    alert_forwarder: Option<JoinHandle<()>>,
 }
 
 impl NotificationSession {
     pub fn new() -> Self {
         Self {
             hb: Instant::now(),
+            unanswered: 0,
             last_argocd_issues: 0,
             last_error_pods: 0,
             last_warning_events: 0,
+            subscriptions: Subscriptions::default(),
+            relay_forwarder: None,
+            alert_forwarder: None,
         }
     }
 
-    /// Heartbeat to keep connection alive
-    fn hb(&self, ctx: &mut <Self as Actor>::Context) {
-        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
-            if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
-                info!("WebSocket client heartbeat failed, disconnecting");
-                ctx.stop();
-                return;
-            }
-            ctx.ping(b"");
-        });
+    /// The client answered: it is keeping up again
+    fn heartbeat_received(&mut self) {
+        self.hb = Instant::now();
+        self.unanswered = 0;
     }
 
-    /// Check for alerts periodically
-    fn check_alerts(&self, ctx: &mut <Self as Actor>::Context) {
-        ctx.run_interval(ALERT_CHECK_INTERVAL, |act, ctx| {
-            let addr = ctx.address();
-            actix::spawn(async move {
-                if let Some(notification) = check_for_new_alerts().await {
-                    addr.do_send(SendNotification(notification));
+    /// Send a message unless the client is behind and the message can be dropped
+    fn send(&mut self, message: &NotificationMessage, ctx: &mut <Self as Actor>::Context) {
+        if should_drop(message, self.hb.elapsed(), self.unanswered) {
+            return;
+        }
+        if let Ok(json) = serde_json::to_string(message) {
+            self.unanswered += 1;
+            ctx.text(json);
+        }
+    }
+
+    /// Forward alerts from the shared background check started by `start_alert_monitor`
+    fn forward_alerts(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let addr = ctx.address();
+        let mut alerts = ALERT_UPDATES.subscribe();
+        self.alert_forwarder = Some(actix::spawn(async move {
+            loop {
+                match alerts.recv().await {
+                    Ok(alert) => addr.do_send(SendNotification(alert)),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("WebSocket client fell behind, {} alerts skipped", missed)
+                    }
+                    Err(RecvError::Closed) => break,
                 }
-            });
-        });
+                if !addr.connected() {
+                    break;
+                }
+            }
+        }));
     }
 }
 
@@ -95,10 +227,10 @@ impl Actor for NotificationSession {
         info!("WebSocket client connected");
         
         // Start heartbeat
-        self.hb(ctx);
+        start_heartbeat(ctx);
         
-        // Start alert checking
-        self.check_alerts(ctx);
+        // Receive alerts from the shared check
+        self.forward_alerts(ctx);
         
         // Send welcome message
         let welcome = NotificationMessage::Connected {
@@ -115,23 +247,50 @@ impl Actor for NotificationSession {
                 addr.do_send(SendNotification(stats));
             }
         });
+
+        // Tell the client when Hubble Relay drops and comes back, starting with
+        // the current state if it is already reconnecting
+        let addr = ctx.address();
+        let mut relay = cilium::subscribe_relay_status();
+        let current = relay.borrow_and_update().clone();
+        if matches!(current, cilium::RelayStatus::Reconnecting { .. }) {
+            addr.do_send(SendNotification(NotificationMessage::RelayStatus { status: current }));
+        }
+        self.relay_forwarder = Some(actix::spawn(async move {
+            while relay.changed().await.is_ok() {
+                let status = relay.borrow_and_update().clone();
+                if !addr.connected() {
+                    break;
+                }
+                addr.do_send(SendNotification(NotificationMessage::RelayStatus { status }));
+            }
+        }));
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
+        for forwarder in [self.relay_forwarder.take(), self.alert_forwarder.take()].into_iter().flatten() {
+            forwarder.abort();
+        }
         info!("WebSocket client disconnected");
     }
 }
 
+impl Heartbeat for NotificationSession {
+    fn last_heartbeat(&self) -> Instant {
+        self.hb
+    }
+}
+
 /// Handle messages from client
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for NotificationSession {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         match msg {
             Ok(ws::Message::Ping(msg)) => {
-                self.hb = Instant::now();
+                self.heartbeat_received();
                 ctx.pong(&msg);
             }
             Ok(ws::Message::Pong(_)) => {
-                self.hb = Instant::now();
+                self.heartbeat_received();
             }
             Ok(ws::Message::Text(text)) => {
                 // Handle client commands if needed
@@ -139,9 +298,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for NotificationSessi
                     let hb = NotificationMessage::Heartbeat {
                         timestamp: chrono::Utc::now().to_rfc3339(),
                     };
-                    if let Ok(json) = serde_json::to_string(&hb) {
-                        ctx.text(json);
-                    }
+                    self.send(&hb, ctx);
                 } else if text.trim() == "stats" {
                     // Request immediate stats update
                     let addr = ctx.address();
@@ -150,6 +307,16 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for NotificationSessi
                             addr.do_send(SendNotification(stats));
                         }
                     });
+                } else {
+                    match serde_json::from_str::<ChannelCommand>(&text) {
+                        Ok(ChannelCommand::Subscribe { channels }) => {
+                            self.subscriptions.subscribe(&channels)
+                        }
+                        Ok(ChannelCommand::Unsubscribe { channels }) => {
+                            self.subscriptions.unsubscribe(&channels)
+                        }
+                        Err(e) => warn!("Ignoring WebSocket client message: {}", e),
+                    }
                 }
             }
             Ok(ws::Message::Binary(_)) => {}
@@ -167,8 +334,8 @@ impl Handler<SendNotification> for NotificationSession {
     type Result = ();
 
     fn handle(&mut self, msg: SendNotification, ctx: &mut Self::Context) {
-        if let Ok(json) = serde_json::to_string(&msg.0) {
-            ctx.text(json);
+        if self.subscriptions.wants(&msg.0) {
+            self.send(&msg.0, ctx);
         }
     }
 }
@@ -184,7 +351,7 @@ async fn check_for_new_alerts() -> Option<NotificationMessage> {
     let mut alerts = Vec::new();
 
     // Check ArgoCD status
-    if let Ok(argocd_status) = argocd::get_argocd_status().await {
+    if let Ok(argocd_status) = argocd::get_argocd_status(None).await {
         if argocd_status.unhealthy > 0 {
             alerts.push(NotificationMessage::Alert {
                 severity: "warning".to_string(),
@@ -192,12 +359,13 @@ async fn check_for_new_alerts() -> Option<NotificationMessage> {
                 message: format!("{} applications need attention", argocd_status.unhealthy),
                 source: "argocd".to_string(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
+                fingerprint: None,
             });
         }
     }
 
     // Check pods in error
-    if let Ok(pods_status) = pods::get_pods_status().await {
+    if let Ok(pods_status) = pods::get_pods_status(None, None).await {
         if pods_status.error_pods > 0 {
             alerts.push(NotificationMessage::Alert {
                 severity: "error".to_string(),
@@ -205,27 +373,80 @@ async fn check_for_new_alerts() -> Option<NotificationMessage> {
                 message: format!("{} pods are in error state", pods_status.error_pods),
                 source: "pods".to_string(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
+                fingerprint: None,
             });
         }
     }
 
+    // Critical Alertmanager alerts, one notification each
+    if let Ok(am_alerts) = alertmanager::get_active_alerts(None).await {
+        alerts.extend(critical_alertmanager_alerts(&am_alerts));
+    }
+
+    // Critical alerts also go to the webhook, once each
+    notifiers::notify_critical(&alerts).await;
+
     // Return first alert if any (we can batch later)
     alerts.into_iter().next()
 }
 
+/// Notifications for critical Alertmanager alerts that are not silenced or inhibited
+pub fn critical_alertmanager_alerts(resp: &alertmanager::AlertsResponse) -> Vec<NotificationMessage> {
+    resp.critical
+        .iter()
+        .filter(|a| a.state != "suppressed")
+        .map(|a| {
+            let target = match (&a.namespace, &a.pod) {
+                (Some(ns), Some(pod)) => format!(" ({}/{})", ns, pod),
+                (Some(ns), None) => format!(" ({})", ns),
+                _ => String::new(),
+            };
+            NotificationMessage::Alert {
+                severity: "critical".to_string(),
+                title: a.name.clone(),
+                message: format!("{}{}", a.summary, target),
+                source: "alertmanager".to_string(),
+                timestamp: a.started_at.to_rfc3339(),
+                fingerprint: Some(a.fingerprint.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Check for alerts once per interval for the whole process, publishing them to every
+/// connected session and to ALERT_WEBHOOK_URL; skipped while nobody would receive them
+pub fn start_alert_monitor() {
+    let webhook = notifiers::webhook_url().is_some();
+    if webhook {
+        info!("Sending critical alerts to the configured webhook");
+    }
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(ALERT_CHECK_INTERVAL).await;
+            if !webhook && ALERT_UPDATES.receiver_count() == 0 {
+                continue;
+            }
+            if let Some(alert) = check_for_new_alerts().await {
+                // No receivers just means no dashboard is open
+                let _ = ALERT_UPDATES.send(alert);
+            }
+        }
+    });
+}
+
 /// Get current cluster stats for WebSocket update
 async fn get_current_stats() -> Option<NotificationMessage> {
-    let argocd_issues = argocd::get_argocd_status()
+    let argocd_issues = argocd::get_argocd_status(None)
         .await
         .map(|s| s.unhealthy)
         .unwrap_or(0);
 
-    let error_pods = pods::get_pods_status()
+    let error_pods = pods::get_pods_status(None, None)
         .await
         .map(|s| s.error_pods)
         .unwrap_or(0);
 
-    let warning_events = events::get_events(None)
+    let warning_events = events::get_events(None, None)
         .await
         .map(|s| s.warning_count)
         .unwrap_or(0);
@@ -236,3 +457,523 @@ async fn get_current_stats() -> Option<NotificationMessage> {
         warning_events,
     })
 }
+
+// ============================================================================
+// Live Kubernetes Events
+// ============================================================================
+
+/// Which event types an events session forwards
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventFilter {
+    /// Only Warning events (default)
+    #[default]
+    Warning,
+    /// Every event type
+    All,
+}
+
+impl EventFilter {
+    fn matches(&self, event_type: &str) -> bool {
+        match self {
+            EventFilter::Warning => event_type == "Warning",
+            EventFilter::All => true,
+        }
+    }
+}
+
+/// Connection options for /ws/events
+#[derive(Debug, Deserialize)]
+pub struct EventStreamQuery {
+    #[serde(default)]
+    pub filter: EventFilter,
+}
+
+/// Client command to change the filter, e.g. `{"filter": "all"}`
+#[derive(Debug, Deserialize)]
+struct EventFilterCommand {
+    filter: EventFilter,
+}
+
+/// Messages sent on /ws/events
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum EventStreamMessage {
+    #[serde(rename = "connected")]
+    Connected { message: String },
+    #[serde(rename = "event")]
+    Event { event: Box<events::EventInfo> },
+}
+
+/// A Kubernetes event delivered by the shared event watch
+#[derive(Message)]
+#[rtype(result = "()")]
+struct WatchedEvent(Arc<Event>);
+
+/// WebSocket actor streaming Kubernetes events as they happen
+pub struct EventStreamSession {
+    hb: Instant,
+    filter: EventFilter,
+    /// Events last seen before the session started are part of the initial list, not new
+    started_at: DateTime<Utc>,
+    /// Task forwarding the shared watch's updates to this session
+    forwarder: Option<JoinHandle<()>>,
+}
+
+impl EventStreamSession {
+    pub fn new(filter: EventFilter) -> Self {
+        Self {
+            hb: Instant::now(),
+            filter,
+            started_at: Utc::now(),
+            forwarder: None,
+        }
+    }
+
+    /// Switch the filter on `{"filter": "all"}` / `{"filter": "warning"}`; other text is ignored
+    fn apply_command(&mut self, text: &str) {
+        if let Ok(command) = serde_json::from_str::<EventFilterCommand>(text) {
+            self.filter = command.filter;
+        }
+    }
+
+    fn send(&self, msg: &EventStreamMessage, ctx: &mut <Self as Actor>::Context) {
+        if let Ok(json) = serde_json::to_string(msg) {
+            ctx.text(json);
+        }
+    }
+}
+
+impl Heartbeat for EventStreamSession {
+    fn last_heartbeat(&self) -> Instant {
+        self.hb
+    }
+}
+
+impl Actor for EventStreamSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        info!("Events WebSocket client connected ({:?})", self.filter);
+
+        start_heartbeat(ctx);
+
+        self.send(
+            &EventStreamMessage::Connected {
+                message: "Connected to Kusanagi live events".to_string(),
+            },
+            ctx,
+        );
+
+        // Every session shares the cluster-wide watch started by events::watch_events
+        let addr = ctx.address();
+        let mut updates = events::subscribe();
+        self.forwarder = Some(actix::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(event) => addr.do_send(WatchedEvent(event)),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Events WebSocket client fell behind, {} events skipped", missed)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+                if !addr.connected() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if let Some(forwarder) = self.forwarder.take() {
+            forwarder.abort();
+        }
+        info!("Events WebSocket client disconnected");
+    }
+}
+
+impl Handler<WatchedEvent> for EventStreamSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: WatchedEvent, ctx: &mut Self::Context) {
+        let event = msg.0;
+
+        if matches!(events::last_seen(&event), Some(ts) if ts < self.started_at) {
+            return;
+        }
+
+        let info = events::to_event_info(&event, Utc::now());
+        if self.filter.matches(&info.event_type) {
+            self.send(&EventStreamMessage::Event { event: Box::new(info) }, ctx);
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for EventStreamSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.hb = Instant::now();
+            }
+            Ok(ws::Message::Text(text)) => self.apply_command(&text),
+            Ok(ws::Message::Binary(_)) => {}
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => ctx.stop(),
+        }
+    }
+}
+
+/// WebSocket handshake endpoint for live events (`?filter=all` for every type)
+pub async fn ws_events(
+    req: HttpRequest,
+    stream: web::Payload,
+    query: web::Query<EventStreamQuery>,
+) -> Result<HttpResponse, Error> {
+    ws::start(EventStreamSession::new(query.filter), &req, stream)
+}
+
+// ============================================================================
+// Live Hubble Flows
+// ============================================================================
+
+/// How often a flows session fetches flows while the relay is reachable
+const FLOW_STREAM_INTERVAL: Duration = Duration::from_secs(5);
+/// Flows sent per update by default
+const DEFAULT_FLOW_STREAM_LIMIT: usize = 100;
+
+/// Connection options for /ws/cilium/flows
+#[derive(Debug, Deserialize)]
+pub struct FlowStreamQuery {
+    pub namespace: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Messages sent on /ws/cilium/flows
+#[derive(Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum FlowStreamMessage {
+    #[serde(rename = "connected")]
+    Connected { message: String },
+    /// Flows seen since the previous update
+    #[serde(rename = "flows")]
+    Flows { flows: Box<cilium::HubbleFlowsResponse> },
+    /// The stream lost the relay and is reconnecting, or got it back
+    #[serde(rename = "relay_status")]
+    RelayStatus { status: cilium::RelayStatus },
+}
+
+/// An update produced by a session's flow stream task
+#[derive(Message)]
+#[rtype(result = "()")]
+struct FlowUpdate(FlowStreamMessage);
+
+/// Consecutive failures and the wait before the next fetch, after a fetch that
+/// succeeded or not: the regular interval when it did, the reconnect backoff when it didn't
+fn next_flow_poll(succeeded: bool, attempt: u32, max_backoff: Duration) -> (u32, Duration) {
+    if succeeded {
+        (0, FLOW_STREAM_INTERVAL)
+    } else {
+        (attempt.saturating_add(1), cilium::hubble_reconnect_delay(attempt, max_backoff))
+    }
+}
+
+/// WebSocket actor streaming Hubble flows, reconnecting with backoff when the relay drops
+pub struct FlowStreamSession {
+    hb: Instant,
+    namespace: Option<String>,
+    limit: usize,
+    /// Task fetching flows and reconnecting for this session
+    stream: Option<JoinHandle<()>>,
+}
+
+impl FlowStreamSession {
+    pub fn new(namespace: Option<String>, limit: usize) -> Self {
+        Self {
+            hb: Instant::now(),
+            namespace,
+            limit,
+            stream: None,
+        }
+    }
+
+    fn send(&self, msg: &FlowStreamMessage, ctx: &mut <Self as Actor>::Context) {
+        if let Ok(json) = serde_json::to_string(msg) {
+            ctx.text(json);
+        }
+    }
+}
+
+impl Heartbeat for FlowStreamSession {
+    fn last_heartbeat(&self) -> Instant {
+        self.hb
+    }
+}
+
+impl Actor for FlowStreamSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        info!(namespace = ?self.namespace, "Hubble flows WebSocket client connected");
+
+        start_heartbeat(ctx);
+
+        self.send(
+            &FlowStreamMessage::Connected {
+                message: "Connected to Kusanagi live flows".to_string(),
+            },
+            ctx,
+        );
+
+        let addr = ctx.address();
+        let namespace = self.namespace.clone();
+        let limit = self.limit;
+        self.stream = Some(actix::spawn(async move {
+            let max_backoff = cilium::hubble_max_backoff();
+            let mut attempt: u32 = 0;
+            while addr.connected() {
+                let result = cilium::get_hubble_flows(namespace.as_deref(), limit, Some(FLOW_STREAM_INTERVAL)).await;
+                let (next_attempt, delay) = next_flow_poll(result.is_ok(), attempt, max_backoff);
+                match result {
+                    Ok(flows) => {
+                        if attempt > 0 {
+                            info!(attempts = attempt, "✅ Hubble flow stream reconnected");
+                            addr.do_send(FlowUpdate(FlowStreamMessage::RelayStatus {
+                                status: cilium::RelayStatus::Connected,
+                            }));
+                        }
+                        addr.do_send(FlowUpdate(FlowStreamMessage::Flows { flows: Box::new(flows) }));
+                    }
+                    Err(error) => {
+                        warn!(attempt = next_attempt, retry_in_ms = delay.as_millis() as u64, %error, "⚠️ Hubble flow stream lost, reconnecting");
+                        addr.do_send(FlowUpdate(FlowStreamMessage::RelayStatus {
+                            status: cilium::RelayStatus::Reconnecting {
+                                attempt: next_attempt,
+                                retry_in_secs: delay.as_secs(),
+                                error,
+                            },
+                        }));
+                    }
+                }
+                attempt = next_attempt;
+                tokio::time::sleep(delay).await;
+            }
+        }));
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if let Some(stream) = self.stream.take() {
+            stream.abort();
+        }
+        info!("Hubble flows WebSocket client disconnected");
+    }
+}
+
+impl Handler<FlowUpdate> for FlowStreamSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: FlowUpdate, ctx: &mut Self::Context) {
+        self.send(&msg.0, ctx);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for FlowStreamSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.hb = Instant::now();
+            }
+            Ok(ws::Message::Text(_)) | Ok(ws::Message::Binary(_)) => {}
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => ctx.stop(),
+        }
+    }
+}
+
+/// WebSocket handshake endpoint for live Hubble flows (`?namespace=` and `?limit=` as on /api/cilium/flows)
+pub async fn ws_cilium_flows(
+    req: HttpRequest,
+    stream: web::Payload,
+    query: web::Query<FlowStreamQuery>,
+) -> Result<HttpResponse, Error> {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_FLOW_STREAM_LIMIT);
+    ws::start(FlowStreamSession::new(query.namespace, limit), &req, stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alertmanager::{Alert, AlertsResponse};
+
+    fn am_alert(name: &str, state: &str, fingerprint: &str) -> Alert {
+        Alert {
+            name: name.to_string(),
+            severity: "critical".to_string(),
+            state: state.to_string(),
+            summary: "Node is not ready".to_string(),
+            description: None,
+            namespace: Some("kube-system".to_string()),
+            pod: None,
+            started_at: Utc::now(),
+            fingerprint: fingerprint.to_string(),
+        }
+    }
+
+    #[test]
+    fn critical_alertmanager_alerts_skip_suppressed() {
+        let resp = AlertsResponse {
+            critical: vec![am_alert("KubeNodeNotReady", "active", "f1"), am_alert("Silenced", "suppressed", "f2")],
+            warning: vec![],
+            info: vec![],
+            total: 2,
+            firing: 0,
+            pending: 2,
+        };
+        let alerts = critical_alertmanager_alerts(&resp);
+        assert_eq!(alerts.len(), 1);
+        match &alerts[0] {
+            NotificationMessage::Alert { severity, title, message, fingerprint, .. } => {
+                assert_eq!(severity, "critical");
+                assert_eq!(title, "KubeNodeNotReady");
+                assert_eq!(message, "Node is not ready (kube-system)");
+                assert_eq!(fingerprint.as_deref(), Some("f1"));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    fn stats() -> NotificationMessage {
+        NotificationMessage::StatsUpdate {
+            argocd_issues: 0,
+            error_pods: 1,
+            warning_events: 2,
+        }
+    }
+
+    fn alert() -> NotificationMessage {
+        NotificationMessage::Alert {
+            severity: "critical".to_string(),
+            title: "KubeNodeNotReady".to_string(),
+            message: "Node is not ready".to_string(),
+            source: "alertmanager".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            fingerprint: None,
+        }
+    }
+
+    fn apply(subscriptions: &mut Subscriptions, text: &str) {
+        match serde_json::from_str::<ChannelCommand>(text).unwrap() {
+            ChannelCommand::Subscribe { channels } => subscriptions.subscribe(&channels),
+            ChannelCommand::Unsubscribe { channels } => subscriptions.unsubscribe(&channels),
+        }
+    }
+
+    #[test]
+    fn unsubscribed_channels_are_not_sent() {
+        let heartbeat = NotificationMessage::Heartbeat { timestamp: Utc::now().to_rfc3339() };
+
+        let mut subscriptions = Subscriptions::default();
+        assert!(subscriptions.wants(&alert()) && subscriptions.wants(&stats()));
+
+        apply(&mut subscriptions, r#"{"action": "subscribe", "channels": ["stats"]}"#);
+        assert!(subscriptions.wants(&stats()));
+        assert!(!subscriptions.wants(&alert()));
+        assert!(subscriptions.wants(&heartbeat));
+
+        let mut subscriptions = Subscriptions::default();
+        apply(&mut subscriptions, r#"{"action": "unsubscribe", "channels": ["alerts"]}"#);
+        assert!(!subscriptions.wants(&alert()));
+        assert!(subscriptions.wants(&stats()));
+
+        assert!(serde_json::from_str::<ChannelCommand>(r#"{"action": "subscribe", "channels": ["bogus"]}"#).is_err());
+    }
+
+    #[test]
+    fn only_droppable_messages_are_dropped_when_behind() {
+        let stats = NotificationMessage::StatsUpdate { argocd_issues: 1, error_pods: 2, warning_events: 3 };
+        let heartbeat = NotificationMessage::Heartbeat { timestamp: Utc::now().to_rfc3339() };
+        let alert = NotificationMessage::Alert {
+            severity: "critical".to_string(),
+            title: "KubeNodeNotReady".to_string(),
+            message: "Node is not ready".to_string(),
+            source: "alertmanager".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            fingerprint: None,
+        };
+        let behind = HEARTBEAT_INTERVAL + Duration::from_secs(1);
+
+        assert!(should_drop(&stats, behind, MAX_UNANSWERED_MESSAGES));
+        assert!(should_drop(&heartbeat, behind, MAX_UNANSWERED_MESSAGES));
+        assert!(!should_drop(&alert, behind, MAX_UNANSWERED_MESSAGES));
+        // A client that answered recently, or hasn't piled up messages, still gets everything
+        assert!(!should_drop(&stats, HEARTBEAT_INTERVAL, MAX_UNANSWERED_MESSAGES));
+        assert!(!should_drop(&stats, behind, MAX_UNANSWERED_MESSAGES - 1));
+    }
+
+    #[test]
+    fn event_stream_defaults_to_warnings_until_switched() {
+        let mut session = EventStreamSession::new(EventFilter::default());
+        assert_eq!(session.filter, EventFilter::Warning);
+        assert!(session.filter.matches("Warning"));
+        assert!(!session.filter.matches("Normal"));
+
+        let query: EventStreamQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.filter, EventFilter::Warning);
+
+        session.apply_command("ping");
+        assert_eq!(session.filter, EventFilter::Warning);
+        session.apply_command(r#"{"filter":"all"}"#);
+        assert_eq!(session.filter, EventFilter::All);
+        assert!(session.filter.matches("Normal"));
+        assert!(session.filter.matches("Warning"));
+    }
+
+    #[test]
+    fn flow_stream_backs_off_until_the_relay_is_back() {
+        let max = Duration::from_secs(4);
+        let mut attempt = 0;
+        let mut delays = Vec::new();
+        for _ in 0..5 {
+            let (next, delay) = next_flow_poll(false, attempt, max);
+            assert_eq!(next, attempt + 1);
+            attempt = next;
+            delays.push(delay);
+        }
+        let millis: Vec<u128> = delays.iter().map(Duration::as_millis).collect();
+        assert_eq!(millis, vec![500, 1000, 2000, 4000, 4000]);
+
+        assert_eq!(next_flow_poll(true, attempt, max), (0, FLOW_STREAM_INTERVAL));
+    }
+
+    #[test]
+    fn flow_stream_status_uses_the_relay_status_shape() {
+        let message = FlowStreamMessage::RelayStatus {
+            status: cilium::RelayStatus::Reconnecting {
+                attempt: 1,
+                retry_in_secs: 0,
+                error: "refused".to_string(),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({
+                "type": "relay_status",
+                "status": {"state": "reconnecting", "attempt": 1, "retry_in_secs": 0, "error": "refused"}
+            })
+        );
+    }
+}